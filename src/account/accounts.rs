@@ -0,0 +1,473 @@
+use crate::account::password;
+use crate::cooking_book::date::Date;
+
+/// The days a login stays valid.
+const SESSION_DAYS: i64 = 30;
+const MIN_PASSWORD_LENGTH: usize = 8;
+const MAX_NAME_LENGTH: usize = 64;
+
+/// A household owns its own ingredients, recipes, shopping list, pantry and meal plan.
+/// The id names its directory. The share link, if any, is only stored as hash.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Household {
+    pub id: String,
+    pub name: String,
+    pub share_hash: Option<String>,
+}
+
+/// A user belongs to exactly one household.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct User {
+    pub name: String,
+    pub household: String,
+    pub password_hash: String,
+}
+
+/// A session expires after some days, an API token when it is revoked.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum TokenKind {
+    Session(Date),
+    Api,
+}
+
+/// A token authenticating a user, only stored as hash.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Token {
+    pub hash: String,
+    pub user: String,
+    pub kind: TokenKind,
+}
+
+/// All households, users and their tokens.
+#[derive(PartialEq, Eq, Debug)]
+pub struct Accounts {
+    pub households: Vec<Household>,
+    pub users: Vec<User>,
+    pub tokens: Vec<Token>,
+}
+
+impl Accounts {
+    pub fn new() -> Accounts {
+        return Accounts {
+            households: Vec::new(),
+            users: Vec::new(),
+            tokens: Vec::new(),
+        };
+    }
+
+    /// Parses the accounts file, one household, user, session or token per line like
+    /// `user;toto;<household id>;<password hash>`. Invalid lines are skipped.
+    ///
+    /// #Arguments
+    ///
+    /// * `text` The content of the accounts file.
+    pub fn new_by_text(text: &str) -> Accounts {
+        let mut accounts = Accounts::new();
+        for line in text.lines().filter(|l| !l.starts_with('#')) {
+            let values: Vec<&str> = line.split(';').collect();
+            match values.as_slice() {
+                ["household", id, name, share_hash] if is_household_id(id) => {
+                    accounts.households.push(Household {
+                        id: id.to_string(),
+                        name: name.to_string(),
+                        share_hash: Some(share_hash.to_string()).filter(|h| !h.is_empty()),
+                    })
+                }
+                ["user", name, household, password_hash] if is_household_id(household) => {
+                    accounts.users.push(User {
+                        name: name.to_string(),
+                        household: household.to_string(),
+                        password_hash: password_hash.to_string(),
+                    })
+                }
+                ["session", hash, user, expires] => {
+                    if let Some(expires) = Date::parse(expires) {
+                        accounts.tokens.push(Token {
+                            hash: hash.to_string(),
+                            user: user.to_string(),
+                            kind: TokenKind::Session(expires),
+                        });
+                    }
+                }
+                ["token", hash, user] => accounts.tokens.push(Token {
+                    hash: hash.to_string(),
+                    user: user.to_string(),
+                    kind: TokenKind::Api,
+                }),
+                _ => (),
+            }
+        }
+        return accounts;
+    }
+
+    /// Returns the content of the accounts file.
+    pub fn to_text(&self) -> String {
+        let mut text =
+            String::from("#household;id;name;share hash\n#user;name;household;password hash\n");
+        for household in &self.households {
+            text.push_str(&format!(
+                "household;{};{};{}\n",
+                household.id,
+                household.name,
+                household.share_hash.as_ref().map_or("", |h| h.as_str())
+            ));
+        }
+        for user in &self.users {
+            text.push_str(&format!(
+                "user;{};{};{}\n",
+                user.name, user.household, user.password_hash
+            ));
+        }
+        for token in &self.tokens {
+            match token.kind {
+                TokenKind::Session(expires) => text.push_str(&format!(
+                    "session;{};{};{}\n",
+                    token.hash, token.user, expires
+                )),
+                TokenKind::Api => text.push_str(&format!("token;{};{}\n", token.hash, token.user)),
+            }
+        }
+        return text;
+    }
+
+    /// Creates a new household with its first user.
+    /// Returns the id of the household.
+    ///
+    /// #Arguments
+    ///
+    /// * `name` The name of the user.
+    /// * `password` The password of the user.
+    /// * `household_name` The name of the household.
+    pub fn register(
+        &mut self,
+        name: &str,
+        password: &str,
+        household_name: &str,
+    ) -> Result<String, String> {
+        let name = name.trim();
+        check_name(household_name)?;
+        check_name(name)?;
+        if self.lookup_user(name).is_some() {
+            return Err(format!("User '{}' already exists", name));
+        }
+        let id = password::random_token()?[..16].to_string();
+        let user = new_user(name, password, &id)?;
+        self.households.push(Household {
+            id: id.to_string(),
+            name: household_name.trim().to_string(),
+            share_hash: None,
+        });
+        self.users.push(user);
+        return Ok(id);
+    }
+
+    /// Adds another user to an existing household.
+    ///
+    /// #Arguments
+    ///
+    /// * `household` The id of the household.
+    /// * `name` The name of the new user.
+    /// * `password` The password of the new user.
+    pub fn add_member(
+        &mut self,
+        household: &str,
+        name: &str,
+        password: &str,
+    ) -> Result<(), String> {
+        let name = name.trim();
+        check_name(name)?;
+        if self.lookup_household(household).is_none() {
+            return Err(format!("Unknown household '{}'", household));
+        }
+        if self.lookup_user(name).is_some() {
+            return Err(format!("User '{}' already exists", name));
+        }
+        let user = new_user(name, password, household)?;
+        self.users.push(user);
+        return Ok(());
+    }
+
+    /// Checks the password and starts a session. Expired sessions are removed.
+    /// Returns the token of the new session.
+    ///
+    /// #Arguments
+    ///
+    /// * `name` The name of the user.
+    /// * `password` The password of the user.
+    /// * `today` The day of the login.
+    pub fn login(&mut self, name: &str, password: &str, today: Date) -> Result<String, String> {
+        let is_valid = match self.lookup_user(name) {
+            Some(user) => password::verify_password(password, &user.password_hash),
+            None => {
+                password::verify_without_user(password);
+                false
+            }
+        };
+        if !is_valid {
+            return Err("Unknown user or wrong password".to_string());
+        }
+
+        self.tokens.retain(|t| match t.kind {
+            TokenKind::Session(expires) => expires >= today,
+            TokenKind::Api => true,
+        });
+        let token = password::random_token()?;
+        self.tokens.push(Token {
+            hash: password::hash_token(&token),
+            user: name.to_string(),
+            kind: TokenKind::Session(today.add_days(SESSION_DAYS)),
+        });
+        return Ok(token);
+    }
+
+    /// Creates a token for scripts and other clients, valid until it is revoked.
+    ///
+    /// #Arguments
+    ///
+    /// * `name` The name of the user.
+    pub fn create_api_token(&mut self, name: &str) -> Result<String, String> {
+        if self.lookup_user(name).is_none() {
+            return Err(format!("Unknown user '{}'", name));
+        }
+        let token = password::random_token()?;
+        self.tokens.push(Token {
+            hash: password::hash_token(&token),
+            user: name.to_string(),
+            kind: TokenKind::Api,
+        });
+        return Ok(token);
+    }
+
+    /// Returns the user of a valid session or API token.
+    ///
+    /// #Arguments
+    ///
+    /// * `token` The token given by the client.
+    /// * `today` The day of the request, expired sessions are rejected.
+    pub fn authenticate(&self, token: &str, today: Date) -> Option<&User> {
+        let hash = password::hash_token(token);
+        let token = self.tokens.iter().find(|t| t.hash == hash)?;
+        if let TokenKind::Session(expires) = token.kind {
+            if expires < today {
+                return None;
+            }
+        }
+        return self.lookup_user(&token.user);
+    }
+
+    /// Ends a session or revokes an API token.
+    ///
+    /// #Arguments
+    ///
+    /// * `token` The token given by the client.
+    pub fn logout(&mut self, token: &str) {
+        let hash = password::hash_token(token);
+        self.tokens.retain(|t| t.hash != hash);
+    }
+
+    /// Creates a new read-only share link of a household, replacing the old one.
+    /// Returns the token of the link.
+    ///
+    /// #Arguments
+    ///
+    /// * `household` The id of the household.
+    pub fn share(&mut self, household: &str) -> Result<String, String> {
+        let token = password::random_token()?;
+        return match self.households.iter_mut().find(|h| h.id == household) {
+            Some(household) => {
+                household.share_hash = Some(password::hash_token(&token));
+                Ok(token)
+            }
+            None => Err(format!("Unknown household '{}'", household)),
+        };
+    }
+
+    /// Revokes the share link of a household.
+    ///
+    /// #Arguments
+    ///
+    /// * `household` The id of the household.
+    pub fn unshare(&mut self, household: &str) {
+        if let Some(household) = self.households.iter_mut().find(|h| h.id == household) {
+            household.share_hash = None;
+        }
+    }
+
+    /// Returns the household shared by the token of a share link.
+    ///
+    /// #Arguments
+    ///
+    /// * `token` The token of the share link.
+    pub fn lookup_share(&self, token: &str) -> Option<&Household> {
+        let hash = password::hash_token(token);
+        return self
+            .households
+            .iter()
+            .find(|h| h.share_hash.as_ref() == Some(&hash));
+    }
+
+    pub fn lookup_user(&self, name: &str) -> Option<&User> {
+        return self.users.iter().find(|u| u.name == name);
+    }
+
+    pub fn lookup_household(&self, id: &str) -> Option<&Household> {
+        return self.households.iter().find(|h| h.id == id);
+    }
+
+    /// Exports the user with the household and its members to JSON.
+    ///
+    /// #Arguments
+    ///
+    /// * `user` The user to export.
+    pub fn user_to_json(&self, user: &User) -> String {
+        let household = self.lookup_household(&user.household);
+        let mut members: Vec<String> = self
+            .users
+            .iter()
+            .filter(|u| u.household == user.household)
+            .map(|u| format!("\"{}\"", u.name))
+            .collect();
+        members.sort();
+        return format!(
+            "{{\"name\": \"{}\", \"household\": {{\"id\": \"{}\", \"name\": \"{}\", \"shared\": {}, \"members\": [{}]}}}}",
+            user.name,
+            user.household,
+            household.map_or("", |h| h.name.as_str()),
+            household.map_or(false, |h| h.share_hash.is_some()),
+            members.join(", ")
+        );
+    }
+}
+
+/// Returns whether the text can be the id of a household, which is also a directory name.
+///
+/// #Arguments
+///
+/// * `id` The text to check.
+pub fn is_household_id(id: &str) -> bool {
+    return !id.is_empty() && id.chars().all(|c| c.is_ascii_hexdigit());
+}
+
+fn check_name(name: &str) -> Result<(), String> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH {
+        return Err(format!("A name needs 1 to {} characters", MAX_NAME_LENGTH));
+    }
+    if name.contains(|c: char| c == ';' || c == '"' || c == '\\' || c.is_control()) {
+        return Err(format!("Invalid name '{}'", name));
+    }
+    return Ok(());
+}
+
+fn new_user(name: &str, password: &str, household: &str) -> Result<User, String> {
+    if password.chars().count() < MIN_PASSWORD_LENGTH {
+        return Err(format!(
+            "A password needs at least {} characters",
+            MIN_PASSWORD_LENGTH
+        ));
+    }
+    return Ok(User {
+        name: name.to_string(),
+        household: household.to_string(),
+        password_hash: password::hash_password(password)?,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_household_id, Accounts, TokenKind};
+    use crate::cooking_book::date::Date;
+
+    #[test]
+    fn test_register_and_login() {
+        let today = Date::new(2026, 10, 19).unwrap();
+        let mut accounts = Accounts::new();
+        let id = accounts.register("toto", "sehr geheim", "Quast").unwrap();
+        assert!(is_household_id(&id));
+        assert_eq!(
+            accounts.register("toto", "sehr geheim", "Quast"),
+            Err("User 'toto' already exists".to_string())
+        );
+        assert_eq!(
+            accounts.add_member(&id, "anna", "kurz"),
+            Err("A password needs at least 8 characters".to_string())
+        );
+        assert_eq!(
+            accounts.add_member(&id, "an;na", "sehr geheim"),
+            Err("Invalid name 'an;na'".to_string())
+        );
+        accounts.add_member(&id, "anna", "auch geheim").unwrap();
+
+        assert!(accounts.login("anna", "sehr geheim", today).is_err());
+        assert!(accounts.login("otto", "sehr geheim", today).is_err());
+        let token = accounts.login("anna", "auch geheim", today).unwrap();
+        assert!(!accounts.to_text().contains(&token));
+        assert_eq!(accounts.authenticate(&token, today).unwrap().name, "anna");
+        assert!(accounts.authenticate(&token, today.add_days(30)).is_some());
+        assert!(accounts.authenticate(&token, today.add_days(31)).is_none());
+        assert!(accounts.authenticate("falsch", today).is_none());
+
+        assert_eq!(
+            accounts.user_to_json(accounts.lookup_user("toto").unwrap()),
+            format!(
+                "{{\"name\": \"toto\", \"household\": {{\"id\": \"{}\", \"name\": \"Quast\", \"shared\": false, \"members\": [\"anna\", \"toto\"]}}}}",
+                id
+            )
+        );
+
+        accounts.logout(&token);
+        assert!(accounts.authenticate(&token, today).is_none());
+    }
+
+    #[test]
+    fn test_tokens_and_share() {
+        let today = Date::new(2026, 10, 19).unwrap();
+        let mut accounts = Accounts::new();
+        let id = accounts.register("toto", "sehr geheim", "Quast").unwrap();
+        let other = accounts
+            .register("otto", "sehr geheim", "Nachbarn")
+            .unwrap();
+
+        let token = accounts.create_api_token("toto").unwrap();
+        assert_eq!(
+            accounts
+                .authenticate(&token, today.add_days(1000))
+                .unwrap()
+                .household,
+            id
+        );
+
+        let share = accounts.share(&id).unwrap();
+        assert_eq!(accounts.lookup_share(&share).unwrap().id, id);
+        assert!(accounts.lookup_share(&token).is_none());
+        assert!(accounts.authenticate(&share, today).is_none());
+        let new_share = accounts.share(&id).unwrap();
+        assert!(accounts.lookup_share(&share).is_none());
+        assert_eq!(accounts.lookup_share(&new_share).unwrap().id, id);
+        accounts.unshare(&id);
+        assert!(accounts.lookup_share(&new_share).is_none());
+        assert!(accounts.share(&other).is_ok());
+    }
+
+    #[test]
+    fn test_text() {
+        let text = "#household;id;name;share hash\n#user;name;household;password hash\nhousehold;00ff;Quast;ab12\nhousehold;../x;Böse;\nuser;toto;00ff;pbkdf2-sha256$1$00$00\nuser;mallory;../..;pbkdf2-sha256$1$00$00\nsession;cd34;toto;2026-11-18\ntoken;ef56;toto\nsession;cd34;toto;morgen\n";
+        let accounts = Accounts::new_by_text(text);
+        assert_eq!(accounts.households.len(), 1);
+        assert_eq!(accounts.households[0].share_hash, Some("ab12".to_string()));
+        assert_eq!(accounts.users.len(), 1);
+        assert_eq!(accounts.users[0].household, "00ff");
+        assert_eq!(
+            accounts.tokens[0].kind,
+            TokenKind::Session(Date::new(2026, 11, 18).unwrap())
+        );
+        assert_eq!(accounts.tokens[1].kind, TokenKind::Api);
+        assert_eq!(accounts.tokens.len(), 2);
+        assert_eq!(
+            accounts.to_text(),
+            text.replace("household;../x;Böse;\n", "")
+                .replace("user;mallory;../..;pbkdf2-sha256$1$00$00\n", "")
+                .replace("session;cd34;toto;morgen\n", "")
+        );
+    }
+}
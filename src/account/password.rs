@@ -0,0 +1,179 @@
+use ring::digest;
+use ring::pbkdf2;
+use ring::rand::{SecureRandom, SystemRandom};
+
+/// The scheme of the stored password hashes, followed by the iterations, salt and hash.
+const SCHEME: &'static str = "pbkdf2-sha256";
+/// The iterations of PBKDF2 for new passwords.
+const ITERATIONS: u32 = 100_000;
+/// The most iterations accepted from a stored hash, so a tampered hash can't stall logins.
+const MAX_ITERATIONS: u32 = 1_000_000;
+const SALT_LENGTH: usize = 16;
+const KEY_LENGTH: usize = 32;
+const TOKEN_LENGTH: usize = 32;
+
+/// Returns random bytes of the operating system.
+///
+/// #Arguments
+///
+/// * `length` The number of bytes.
+pub fn random_bytes(length: usize) -> Result<Vec<u8>, String> {
+    let mut bytes = vec![0u8; length];
+    if SystemRandom::new().fill(&mut bytes).is_err() {
+        return Err("Couldn't get random bytes".to_string());
+    }
+    return Ok(bytes);
+}
+
+/// Returns a new random token like for sessions or share links, as 64 hex digits.
+pub fn random_token() -> Result<String, String> {
+    return Ok(to_hex(&random_bytes(TOKEN_LENGTH)?));
+}
+
+/// Returns the SHA-256 hash of a token to store instead of the token itself.
+///
+/// #Arguments
+///
+/// * `token` The token.
+pub fn hash_token(token: &str) -> String {
+    return to_hex(digest::digest(&digest::SHA256, token.as_bytes()).as_ref());
+}
+
+/// Hashes a password with a random salt, like `pbkdf2-sha256$100000$<salt>$<hash>`.
+///
+/// #Arguments
+///
+/// * `password` The password to hash.
+pub fn hash_password(password: &str) -> Result<String, String> {
+    let salt = random_bytes(SALT_LENGTH)?;
+    return Ok(hash_with(password, &salt, ITERATIONS));
+}
+
+fn hash_with(password: &str, salt: &[u8], iterations: u32) -> String {
+    let mut key = [0u8; KEY_LENGTH];
+    pbkdf2::derive(
+        &digest::SHA256,
+        iterations,
+        salt,
+        password.as_bytes(),
+        &mut key,
+    );
+    return format!(
+        "{}${}${}${}",
+        SCHEME,
+        iterations,
+        to_hex(salt),
+        to_hex(&key)
+    );
+}
+
+/// Returns whether the password matches the hash. Malformed hashes and hashes with more than
+/// `MAX_ITERATIONS` never match.
+///
+/// #Arguments
+///
+/// * `password` The password given by the user.
+/// * `hash` The stored hash of `hash_password`.
+pub fn verify_password(password: &str, hash: &str) -> bool {
+    let parts: Vec<&str> = hash.split('$').collect();
+    if parts.len() != 4 || parts[0] != SCHEME {
+        return false;
+    }
+    let iterations = match parts[1].parse::<u32>() {
+        Ok(iterations) if iterations > 0 && iterations <= MAX_ITERATIONS => iterations,
+        _ => return false,
+    };
+    let (salt, key) = match (from_hex(parts[2]), from_hex(parts[3])) {
+        (Some(salt), Some(key)) => (salt, key),
+        _ => return false,
+    };
+    return pbkdf2::verify(
+        &digest::SHA256,
+        iterations,
+        &salt,
+        password.as_bytes(),
+        &key,
+    )
+    .is_ok();
+}
+
+/// Takes as long as verifying a password of a user, for a user who doesn't exist.
+/// So the response time doesn't tell which user names exist.
+///
+/// #Arguments
+///
+/// * `password` The password given for the unknown user.
+pub fn verify_without_user(password: &str) {
+    let mut key = [0u8; KEY_LENGTH];
+    pbkdf2::derive(
+        &digest::SHA256,
+        ITERATIONS,
+        &[0u8; SALT_LENGTH],
+        password.as_bytes(),
+        &mut key,
+    );
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    let hex: Vec<String> = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    return hex.concat();
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 || !hex.is_ascii() {
+        return None;
+    }
+    return (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{from_hex, hash_password, hash_token, hash_with, random_token, verify_password};
+
+    #[test]
+    fn test_hash_token() {
+        assert_eq!(
+            hash_token("abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_hash_with() {
+        assert_eq!(
+            hash_with("password", b"salt", 2),
+            "pbkdf2-sha256$2$73616c74$ae4d0c95af6b46d32d0adff928f06dd02a303f8ef3c251dfd6e2d85a95474c43"
+        );
+    }
+
+    #[test]
+    fn test_verify_password() {
+        let hash = hash_with("geheim", b"salz", 3);
+        assert!(hash.starts_with("pbkdf2-sha256$3$73616c7a$"));
+        assert!(verify_password("geheim", &hash));
+        assert!(!verify_password("Geheim", &hash));
+        assert!(!verify_password("geheim", &hash.replace("$3$", "$4$")));
+        assert!(!verify_password("geheim", "geheim"));
+        assert!(!verify_password("geheim", "pbkdf2-sha256$0$73616c7a$00"));
+        assert!(!verify_password(
+            "geheim",
+            &hash.replace("$3$", "$4294967295$")
+        ));
+        assert_eq!(from_hex("0aff"), Some(vec![10, 255]));
+        assert_eq!(from_hex("0g"), None);
+
+        let hash = hash_password("geheim").unwrap();
+        assert_ne!(hash, hash_password("geheim").unwrap());
+        assert!(verify_password("geheim", &hash));
+    }
+
+    #[test]
+    fn test_random_token() {
+        let token = random_token().unwrap();
+        assert_eq!(token.len(), 64);
+        assert_ne!(token, random_token().unwrap());
+    }
+}
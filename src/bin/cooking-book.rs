@@ -0,0 +1,5 @@
+/// Runs a command of the command line interface, see `cli::USAGE`.
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    std::process::exit(rezept::cli::run(&args));
+}
@@ -0,0 +1,793 @@
+use std::env;
+use std::fs;
+use std::io;
+
+use crate::account::accounts;
+use crate::cooking_book::date::Date;
+use crate::cooking_book::group::Group;
+use crate::cooking_book::ingredient::Ingredient;
+use crate::cooking_book::locale::Locale;
+use crate::cooking_book::query::Query;
+use crate::cooking_book::recipe::Recipe;
+use crate::cooking_book::render;
+use crate::cooking_book::render::Format;
+use crate::cooking_book::shopping_list::ShoppingList;
+use crate::cooking_book::store::Store;
+use crate::file_access::archive;
+use crate::file_access::check;
+use crate::file_access::cooklang;
+use crate::file_access::persistency;
+
+/// The command succeeded.
+pub const EXIT_OK: i32 = 0;
+/// The command failed, e.g. a file couldn't be written or problems were found.
+pub const EXIT_FAILURE: i32 = 1;
+/// The command or its arguments are invalid.
+pub const EXIT_USAGE: i32 = 2;
+/// The ingredient, recipe or file doesn't exist.
+pub const EXIT_NOT_FOUND: i32 = 3;
+/// The ingredient already exists.
+pub const EXIT_CONFLICT: i32 = 4;
+
+pub const USAGE: &'static str =
+    "Usage: cooking-book <command> [--json] [--lang de|en] [--household <id>]
+
+Commands:
+  serve                                   Start the web server (only with rezept)
+  ingredient list                         List all ingredients
+  ingredient add <name> [<group> [<store>]]
+                                          Add a new ingredient
+  ingredient set-group <name> <group>     Change the group of an ingredient
+  ingredient set-store <name> <store>     Change the store of an ingredient
+  list show                               Show the shopping list
+  list add <text>                         Add to the shopping list, e.g. \"2 Liter Milch\"
+  list remove <name>                      Remove an item from the shopping list
+  list clear                              Remove all items from the shopping list
+  recipe list                             List all recipes
+  recipe show <name>                      Show a recipe with its details
+  recipe search <query>                   Search recipes, e.g. \"#vegan AND NOT Milch\"
+  import <file> [--mode merge|replace] [--dry-run]
+                                          Import a whole cooking book exported before
+  export [<file>]                         Export the whole cooking book as JSON
+  import-cooklang <directory>             Import all .cook files of a directory
+  export-cooklang <directory>             Export all recipes as .cook files
+  check                                   Check all files for problems
+  household list                          List the households of the web server
+  household create <household> <user>     Create a household with its first user, the
+                                          password is read from the standard input
+
+Groups and stores are given by id like vegetable and rewe, by name like Gemüse, or by number.
+--json prints JSON like the web server returns instead of tables.
+--lang selects the language of the output, by default taken from LANG.
+--household selects the files of a household by id, it may be left out if there is only one.
+The first household created takes over the files from before there were households.
+
+Exit codes: 0 success, 1 failure, 2 invalid usage, 3 not found, 4 already exists";
+
+/// The arguments of a command line, split into words and options.
+#[derive(PartialEq, Eq, Debug)]
+pub struct Args {
+    pub words: Vec<String>,
+    pub json: bool,
+    pub dry_run: bool,
+    pub mode: Option<String>,
+    pub lang: Option<String>,
+    pub household: Option<String>,
+}
+
+impl Args {
+    /// Splits the arguments into words and the options `--json`, `--dry-run`, `--mode <mode>`
+    /// `--lang <language>` and `--household <id>`, which may be given anywhere.
+    /// Returns an error for unknown options, languages and invalid household ids.
+    ///
+    /// #Arguments
+    ///
+    /// * `args` The arguments without the program name.
+    pub fn parse(args: &[String]) -> Result<Args, String> {
+        let mut parsed = Args {
+            words: Vec::new(),
+            json: false,
+            dry_run: false,
+            mode: None,
+            lang: None,
+            household: None,
+        };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--json" => parsed.json = true,
+                "--dry-run" => parsed.dry_run = true,
+                "--mode" => match args.next() {
+                    Some(mode) => parsed.mode = Some(mode.to_string()),
+                    None => return Err("Missing value of option '--mode'".to_string()),
+                },
+                _ if arg.starts_with("--mode=") => parsed.mode = Some(arg[7..].to_string()),
+                "--lang" => match args.next() {
+                    Some(lang) => parsed.lang = Some(lang.to_string()),
+                    None => return Err("Missing value of option '--lang'".to_string()),
+                },
+                _ if arg.starts_with("--lang=") => parsed.lang = Some(arg[7..].to_string()),
+                "--household" => match args.next() {
+                    Some(household) => parsed.household = Some(household.to_string()),
+                    None => return Err("Missing value of option '--household'".to_string()),
+                },
+                _ if arg.starts_with("--household=") => {
+                    parsed.household = Some(arg[12..].to_string())
+                }
+                _ if arg.starts_with("--") => return Err(format!("Unknown option '{}'", arg)),
+                _ => parsed.words.push(arg.to_string()),
+            }
+        }
+        if let Some(lang) = &parsed.lang {
+            if Locale::lookup_locale(lang).is_none() {
+                return Err(format!("Unknown language '{}'", lang));
+            }
+        }
+        if let Some(household) = &parsed.household {
+            if !accounts::is_household_id(household) {
+                return Err(format!("Invalid household '{}'", household));
+            }
+        }
+        return Ok(parsed);
+    }
+
+    /// Returns the language of the output: the one given by `--lang`, else the one of the
+    /// environment variable `LANG`, else German.
+    pub fn locale(&self) -> Locale {
+        let lang = match &self.lang {
+            Some(lang) => lang.to_string(),
+            None => env::var("LANG").unwrap_or_default(),
+        };
+        return Locale::lookup_locale(&lang).unwrap_or(Locale::De);
+    }
+}
+
+/// Runs a command and returns its exit code. Results are printed to stdout, errors to stderr.
+///
+/// #Arguments
+///
+/// * `args` The arguments without the program name, like `list add Milch`.
+pub fn run(args: &[String]) -> i32 {
+    let args = match Args::parse(args) {
+        Ok(args) => args,
+        Err(e) => return usage_error(&e),
+    };
+    if args
+        .words
+        .first()
+        .map_or(true, |w| w != "household" && w != "help")
+    {
+        if let Err(code) = select_household(&args) {
+            return code;
+        }
+    }
+    // the text to add and the query may be given as several words
+    let mut words: Vec<String> = args.words.to_vec();
+    if words.len() > 3 && (words[..2] == ["list", "add"] || words[..2] == ["recipe", "search"]) {
+        let rest = words.split_off(2).join(" ");
+        words.push(rest);
+    }
+    let words: Vec<&str> = words.iter().map(|w| w.as_str()).collect();
+
+    return match words.as_slice() {
+        ["help"] => {
+            println!("{}", USAGE);
+            EXIT_OK
+        }
+        ["serve"] => fail(
+            EXIT_USAGE,
+            "The web server is started by rezept, built with the feature 'server'",
+        ),
+        ["ingredient", "list"] => ingredient_list(&args),
+        ["ingredient", "add", name] => ingredient_add(name, "other", "any", &args),
+        ["ingredient", "add", name, group] => ingredient_add(name, group, "any", &args),
+        ["ingredient", "add", name, group, store] => ingredient_add(name, group, store, &args),
+        ["ingredient", "set-group", name, group] => ingredient_set(name, Some(group), None, &args),
+        ["ingredient", "set-store", name, store] => ingredient_set(name, None, Some(store), &args),
+        ["list"] | ["list", "show"] => list_show(&persistency::load_shopping_list(), &args),
+        ["list", "add", text] => list_add(text, &args),
+        ["list", "remove", name] => list_remove(name, &args),
+        ["list", "clear"] => list_write(&ShoppingList::new(), &args),
+        ["recipe", "list"] => recipe_list(None, &args),
+        ["recipe", "show", name] => recipe_show(name, &args),
+        ["recipe", "search", query] => recipe_list(Some(query), &args),
+        ["import", file] => import(file, &args),
+        ["export"] => export(None),
+        ["export", file] => export(Some(file)),
+        ["import-cooklang", directory] => import_cooklang(directory),
+        ["export-cooklang", directory] => export_cooklang(directory),
+        ["check"] => check(&args),
+        ["household", "list"] => household_list(&args),
+        ["household", "create", household, user] => household_create(household, user, &args),
+        _ => usage_error(&format!("Invalid command '{}'", args.words.join(" "))),
+    };
+}
+
+/// Formats rows as table with aligned columns below a header.
+///
+/// #Arguments
+///
+/// * `header` The names of the columns.
+/// * `rows` The rows with a value for each column.
+pub fn table(header: &[&str], rows: &Vec<Vec<String>>) -> String {
+    let mut widths: Vec<usize> = header.iter().map(|h| h.chars().count()).collect();
+    for row in rows {
+        for (i, value) in row.iter().enumerate() {
+            widths[i] = widths[i].max(value.chars().count());
+        }
+    }
+
+    let format_row = |values: &Vec<String>| -> String {
+        let cells: Vec<String> = values
+            .iter()
+            .enumerate()
+            .map(|(i, v)| format!("{}{}", v, " ".repeat(widths[i] - v.chars().count())))
+            .collect();
+        return format!("{}\n", cells.join("  ").trim_end());
+    };
+
+    let mut table = format_row(&header.iter().map(|h| h.to_string()).collect());
+    table.push_str(&format_row(
+        &widths.iter().map(|w| "-".repeat(*w)).collect(),
+    ));
+    for row in rows {
+        table.push_str(&format_row(row));
+    }
+    return table;
+}
+
+/// Returns the labels of the message ids, like the header of a table.
+fn labels(ids: &[&str], locale: Locale) -> Vec<&'static str> {
+    return ids.iter().map(|id| locale.text(id)).collect();
+}
+
+fn usage_error(message: &str) -> i32 {
+    eprintln!("{}\n\n{}", message, USAGE);
+    return EXIT_USAGE;
+}
+
+fn fail(code: i32, message: &str) -> i32 {
+    eprintln!("{}", message);
+    return code;
+}
+
+/// Selects the household given by `--household`, else the only one.
+/// Without households the files directly in `persistency` are used.
+/// Returns the exit code if the household is unknown or several households exist.
+fn select_household(args: &Args) -> Result<(), i32> {
+    let accounts = persistency::load_accounts();
+    let household = match &args.household {
+        Some(household) => match accounts.lookup_household(household) {
+            Some(household) => household,
+            None => {
+                return Err(fail(
+                    EXIT_NOT_FOUND,
+                    &format!("Unknown household '{}'", household),
+                ))
+            }
+        },
+        None => match accounts.households.as_slice() {
+            [] => return Ok(()),
+            [household] => household,
+            _ => {
+                return Err(usage_error(
+                    "There are several households, choose one with --household",
+                ))
+            }
+        },
+    };
+    persistency::select_household(Some(&household.id));
+    return Ok(());
+}
+
+fn household_list(args: &Args) -> i32 {
+    let accounts = persistency::load_accounts();
+    let rows: Vec<Vec<String>> = accounts
+        .households
+        .iter()
+        .map(|h| {
+            let members: Vec<&str> = accounts
+                .users
+                .iter()
+                .filter(|u| u.household == h.id)
+                .map(|u| u.name.as_str())
+                .collect();
+            vec![h.id.to_string(), h.name.to_string(), members.join(", ")]
+        })
+        .collect();
+    if args.json {
+        let households: Vec<String> = rows
+            .iter()
+            .map(|r| format!("{{\"id\": \"{}\", \"name\": \"{}\"}}", r[0], r[1]))
+            .collect();
+        println!("[{}]", households.join(", "));
+    } else {
+        let header = ["label.household", "label.name", "label.user"];
+        print!("{}", table(&labels(&header, args.locale()), &rows));
+    }
+    return EXIT_OK;
+}
+
+/// Creates a household with its first user. The password is the first line of the standard
+/// input. The first household takes over the files from before there were households.
+fn household_create(household: &str, user: &str, args: &Args) -> i32 {
+    let mut password = String::new();
+    if let Err(e) = io::stdin().read_line(&mut password) {
+        return fail(EXIT_FAILURE, &format!("Couldn't read the password: {}", e));
+    }
+    let password = password.trim_end_matches(|c| c == '\n' || c == '\r');
+
+    let mut accounts = persistency::load_accounts();
+    let is_first = accounts.households.is_empty();
+    let id = match accounts.register(user, password, household) {
+        Ok(id) => id,
+        Err(e) => return fail(EXIT_USAGE, &e),
+    };
+    if let Err(e) = persistency::create_household_directory(&id) {
+        return fail(EXIT_FAILURE, &e);
+    }
+    if let Err(e) = persistency::write_accounts(&accounts) {
+        return fail(EXIT_FAILURE, &e);
+    }
+    if is_first {
+        if let Err(e) = persistency::adopt_existing_files(&id) {
+            return fail(EXIT_FAILURE, &e);
+        }
+    }
+    if args.json {
+        println!(
+            "{}",
+            accounts.user_to_json(accounts.lookup_user(user.trim()).unwrap())
+        );
+    } else {
+        println!("{}", id);
+    }
+    return EXIT_OK;
+}
+
+fn ingredient_list(args: &Args) -> i32 {
+    let ingredients = persistency::load_ingredients();
+    if args.json {
+        println!("{}", Ingredient::all_to_json(&ingredients, args.locale()));
+        return EXIT_OK;
+    }
+
+    let locale = args.locale();
+    let mut names: Vec<&String> = ingredients.keys().collect();
+    names.sort();
+    let rows: Vec<Vec<String>> = names
+        .iter()
+        .map(|n| {
+            let ingredient = &ingredients[*n];
+            return vec![
+                ingredient.name.to_string(),
+                ingredient.group.label(locale).to_string(),
+                ingredient.preferred_store.label(locale).to_string(),
+                ingredient.aliases.join(", "),
+            ];
+        })
+        .collect();
+    let header = ["label.name", "label.group", "label.store", "label.aliases"];
+    print!("{}", table(&labels(&header, locale), &rows));
+    return EXIT_OK;
+}
+
+fn ingredient_add(name: &str, group: &str, store: &str, args: &Args) -> i32 {
+    if !Ingredient::is_valid_name(name) {
+        return fail(EXIT_USAGE, &format!("Invalid ingredient name '{}'", name));
+    }
+    let ingredients = persistency::load_ingredients();
+    match Ingredient::resolve(&ingredients, name) {
+        Some(existing) if existing.name == name => {
+            return fail(
+                EXIT_CONFLICT,
+                &format!("Ingredient '{}' already exists", name),
+            )
+        }
+        Some(existing) => {
+            return fail(
+                EXIT_CONFLICT,
+                &format!("'{}' already refers to '{}'", name, existing.name),
+            )
+        }
+        None => {}
+    }
+    let mut ingredient = Ingredient::new_by_name(name.to_string());
+    ingredient.group = match Group::lookup_group_name(group) {
+        Some(group) => group,
+        None => return fail(EXIT_USAGE, &format!("Unknown group '{}'", group)),
+    };
+    ingredient.preferred_store = match Store::lookup_store_name(store) {
+        Some(store) => store,
+        None => return fail(EXIT_USAGE, &format!("Unknown store '{}'", store)),
+    };
+
+    if let Err(e) = persistency::write_single_ingredient(&ingredient) {
+        return fail(EXIT_FAILURE, &e);
+    }
+    return print_ingredient(&ingredient, args);
+}
+
+fn ingredient_set(name: &str, group: Option<&str>, store: Option<&str>, args: &Args) -> i32 {
+    let mut ingredients = persistency::load_ingredients();
+    let ingredient = match ingredients.get_mut(name) {
+        Some(ingredient) => ingredient,
+        None => return fail(EXIT_NOT_FOUND, &format!("Unknown ingredient '{}'", name)),
+    };
+    if let Some(group) = group {
+        ingredient.group = match Group::lookup_group_name(group) {
+            Some(group) => group,
+            None => return fail(EXIT_USAGE, &format!("Unknown group '{}'", group)),
+        };
+    }
+    if let Some(store) = store {
+        ingredient.preferred_store = match Store::lookup_store_name(store) {
+            Some(store) => store,
+            None => return fail(EXIT_USAGE, &format!("Unknown store '{}'", store)),
+        };
+    }
+    let ingredient = ingredient.clone();
+
+    if let Err(e) = persistency::write_all_ingredients(&ingredients) {
+        return fail(EXIT_FAILURE, &e);
+    }
+    return print_ingredient(&ingredient, args);
+}
+
+fn print_ingredient(ingredient: &Ingredient, args: &Args) -> i32 {
+    let locale = args.locale();
+    if args.json {
+        println!("{}", ingredient.to_json(locale));
+    } else {
+        println!(
+            "{}: {}, {}",
+            ingredient.name,
+            ingredient.group.label(locale),
+            ingredient.preferred_store.label(locale)
+        );
+    }
+    return EXIT_OK;
+}
+
+fn list_show(shopping_list: &ShoppingList, args: &Args) -> i32 {
+    if args.json {
+        println!("{}", shopping_list.to_json(args.locale()));
+    } else {
+        print!(
+            "{}",
+            render::shopping_list(shopping_list, Format::Text, args.locale())
+        );
+    }
+    return EXIT_OK;
+}
+
+fn list_add(text: &str, args: &Args) -> i32 {
+    let mut ingredients = persistency::load_ingredients();
+    let mut shopping_list = persistency::load_shopping_list();
+    if let Err(e) = shopping_list.add_text(text, &mut ingredients) {
+        eprintln!("{}", e);
+    }
+    return list_write(&shopping_list, args);
+}
+
+fn list_remove(name: &str, args: &Args) -> i32 {
+    let mut shopping_list = persistency::load_shopping_list();
+    if !shopping_list.to_buy.keys().any(|i| i.name == name) {
+        return fail(
+            EXIT_NOT_FOUND,
+            &format!("'{}' is not on the shopping list", name),
+        );
+    }
+    shopping_list.remove_by_name(name);
+    return list_write(&shopping_list, args);
+}
+
+fn list_write(shopping_list: &ShoppingList, args: &Args) -> i32 {
+    if let Err(e) = persistency::write_shopping_list(shopping_list) {
+        return fail(EXIT_FAILURE, &e);
+    }
+    return list_show(shopping_list, args);
+}
+
+fn recipe_list(query: Option<&str>, args: &Args) -> i32 {
+    let recipes = persistency::load_recipes();
+    let found: Vec<&Recipe> = match query {
+        Some(query) => match Query::parse(query) {
+            Ok(query) => Recipe::get_recipes_by_query(&recipes, &query),
+            Err(e) => return fail(EXIT_USAGE, &format!("Invalid query at {}", e)),
+        },
+        None => {
+            let mut all_recipes: Vec<&Recipe> = recipes.values().collect();
+            all_recipes.sort_by(|a, b| a.name.cmp(&b.name));
+            all_recipes
+        }
+    };
+    if args.json {
+        println!("{}", Recipe::all_to_json(&found, args.locale()));
+        return EXIT_OK;
+    }
+
+    let rows: Vec<Vec<String>> = found
+        .iter()
+        .map(|r| {
+            let mut tags: Vec<&String> = r.tags.iter().collect();
+            tags.sort();
+            let tags: Vec<&str> = tags.iter().map(|t| t.as_str()).collect();
+            let time = match r.details.total_time() {
+                Some(minutes) => format!("{} min", minutes),
+                None => String::new(),
+            };
+            return vec![r.name.to_string(), time, tags.join(" ")];
+        })
+        .collect();
+    let header = ["label.name", "label.time", "label.tags"];
+    print!("{}", table(&labels(&header, args.locale()), &rows));
+    if query.is_some() && found.is_empty() {
+        return EXIT_NOT_FOUND;
+    }
+    return EXIT_OK;
+}
+
+fn recipe_show(name: &str, args: &Args) -> i32 {
+    let recipes = persistency::load_recipes();
+    let recipe = match recipes.get(name) {
+        Some(recipe) => recipe,
+        None => return fail(EXIT_NOT_FOUND, &format!("Unknown recipe '{}'", name)),
+    };
+    if args.json {
+        println!("{}", recipe.to_json(args.locale()));
+    } else {
+        print!(
+            "{}",
+            render::recipes(&vec![recipe], Format::Text, args.locale())
+        );
+    }
+    return EXIT_OK;
+}
+
+/// Imports a document of `export`, only printing the result for `--dry-run`.
+fn import(file: &str, args: &Args) -> i32 {
+    let mode = args.mode.as_ref().map_or("merge", |m| m.as_str());
+    let mode = match archive::Mode::lookup_mode(mode) {
+        Some(mode) => mode,
+        None => return fail(EXIT_USAGE, &format!("Unknown mode '{}'", mode)),
+    };
+    let document = match fs::read_to_string(file) {
+        Ok(document) => document,
+        Err(e) => return fail(EXIT_NOT_FOUND, &format!("Couldn't read '{}': {}", file, e)),
+    };
+    let imported = match archive::import(&document, &persistency::read_all_files(), mode) {
+        Ok(imported) => imported,
+        Err(e) => return fail(EXIT_FAILURE, &e),
+    };
+
+    if args.json {
+        println!("{}", imported.to_json());
+    } else {
+        let rows: Vec<Vec<String>> = imported
+            .imported
+            .iter()
+            .map(|(part, count)| vec![part.to_string(), count.to_string()])
+            .collect();
+        let header = ["label.part", "label.entries"];
+        print!("{}", table(&labels(&header, args.locale()), &rows));
+        for conflict in &imported.conflicts {
+            println!(
+                "kept {} '{}': {}",
+                conflict.part, conflict.key, conflict.existing
+            );
+        }
+    }
+    if !imported.diagnostics.is_empty() {
+        for diagnostic in &imported.diagnostics {
+            eprintln!("{}", diagnostic);
+        }
+        return fail(
+            EXIT_FAILURE,
+            &format!(
+                "{} problem(s) found, nothing imported",
+                imported.diagnostics.len()
+            ),
+        );
+    }
+    if !args.dry_run {
+        if let Err(e) = persistency::write_files_atomically(&imported.files) {
+            return fail(EXIT_FAILURE, &e);
+        }
+    }
+    return EXIT_OK;
+}
+
+/// Exports the whole cooking book to a file or stdout.
+fn export(file: Option<&str>) -> i32 {
+    let document = archive::export(&persistency::read_all_files(), Date::today());
+    match file {
+        Some(file) => {
+            if let Err(e) = fs::write(file, document) {
+                return fail(EXIT_FAILURE, &format!("Couldn't write '{}': {}", file, e));
+            }
+        }
+        None => println!("{}", document),
+    }
+    return EXIT_OK;
+}
+
+/// Imports all `.cook` files of a directory as new recipes.
+/// Recipes which already exist are left unchanged.
+/// Fails if any file couldn't be imported.
+///
+/// #Arguments
+///
+/// * `directory` The directory containing the `.cook` files
+fn import_cooklang(directory: &str) -> i32 {
+    let all_ingredients = persistency::load_ingredients();
+    let files = match cooklang::from_directory(directory, &all_ingredients) {
+        Ok(files) => files,
+        Err(e) => return fail(EXIT_FAILURE, &e),
+    };
+
+    let mut recipes = persistency::load_recipes();
+    let mut failed = 0;
+    for (file_name, imported) in files {
+        let imported = match imported {
+            Ok(imported) => imported,
+            Err(e) => {
+                eprintln!("{}: {}", file_name, e);
+                failed += 1;
+                continue;
+            }
+        };
+        let name = imported.recipe.name.to_string();
+        if recipes.contains_key(&name) {
+            eprintln!("{}: Recipe '{}' already exists", file_name, name);
+            failed += 1;
+            continue;
+        }
+        println!("{}: imported '{}'", file_name, name);
+        for ingredient in &imported.unresolved {
+            println!("  missing ingredient '{}'", ingredient);
+        }
+        for skipped in &imported.skipped {
+            println!("  skipped '{}'", skipped);
+        }
+        recipes.insert(name, imported.recipe);
+    }
+    if let Err(e) = persistency::write_all_recipes(&recipes) {
+        return fail(EXIT_FAILURE, &e);
+    }
+    if failed > 0 {
+        return fail(EXIT_FAILURE, &format!("{} file(s) not imported", failed));
+    }
+    return EXIT_OK;
+}
+
+/// Writes all recipes as `.cook` files to a directory.
+///
+/// #Arguments
+///
+/// * `directory` The directory to write to, it is created if missing
+fn export_cooklang(directory: &str) -> i32 {
+    let recipes = persistency::load_recipes();
+    let mut all_recipes: Vec<&Recipe> = recipes.values().collect();
+    all_recipes.sort_by(|a, b| a.name.cmp(&b.name));
+    return match cooklang::to_directory(directory, &all_recipes) {
+        Ok(count) => {
+            println!("{} recipe(s) exported", count);
+            EXIT_OK
+        }
+        Err(e) => fail(EXIT_FAILURE, &e),
+    };
+}
+
+/// Prints the problems of all persistency files.
+/// Fails if there are any.
+fn check(args: &Args) -> i32 {
+    let diagnostics = check::check_all();
+    if args.json {
+        println!("{}", check::Diagnostic::all_to_json(&diagnostics));
+    } else {
+        for diagnostic in &diagnostics {
+            println!("{}", diagnostic);
+        }
+    }
+    if !diagnostics.is_empty() {
+        return fail(
+            EXIT_FAILURE,
+            &format!("{} problem(s) found", diagnostics.len()),
+        );
+    }
+    return EXIT_OK;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{table, Args};
+    use crate::cooking_book::locale::Locale;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        return args.iter().map(|a| a.to_string()).collect();
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(
+            Args::parse(&args(&["list", "--json", "add", "2 L Milch"])),
+            Ok(Args {
+                words: args(&["list", "add", "2 L Milch"]),
+                json: true,
+                dry_run: false,
+                mode: None,
+                lang: None,
+                household: None,
+            })
+        );
+        assert_eq!(
+            Args::parse(&args(&[
+                "import",
+                "--mode",
+                "replace",
+                "book.json",
+                "--dry-run"
+            ])),
+            Ok(Args {
+                words: args(&["import", "book.json"]),
+                json: false,
+                dry_run: true,
+                mode: Some("replace".to_string()),
+                lang: None,
+                household: None,
+            })
+        );
+        assert_eq!(
+            Args::parse(&args(&["import", "--mode=merge"]))
+                .unwrap()
+                .mode,
+            Some("merge".to_string())
+        );
+        assert_eq!(
+            Args::parse(&args(&["import", "--mode"])),
+            Err("Missing value of option '--mode'".to_string())
+        );
+        assert_eq!(
+            Args::parse(&args(&["list", "--all"])),
+            Err("Unknown option '--all'".to_string())
+        );
+        assert_eq!(
+            Args::parse(&args(&["list", "--lang", "en-US"]))
+                .unwrap()
+                .locale(),
+            Locale::En
+        );
+        assert_eq!(
+            Args::parse(&args(&["list", "--lang=fr"])),
+            Err("Unknown language 'fr'".to_string())
+        );
+        assert_eq!(
+            Args::parse(&args(&["list", "--household", "00ff"]))
+                .unwrap()
+                .household,
+            Some("00ff".to_string())
+        );
+        assert_eq!(
+            Args::parse(&args(&["list", "--household=../x"])),
+            Err("Invalid household '../x'".to_string())
+        );
+    }
+
+    #[test]
+    fn test_table() {
+        let rows = vec![
+            vec!["Käse".to_string(), "Kühlung".to_string(), String::new()],
+            vec![
+                "Mehl".to_string(),
+                "Backzutat".to_string(),
+                "Weizenmehl".to_string(),
+            ],
+        ];
+        assert_eq!(
+            table(&["Name", "Gruppe", "Aliase"], &rows),
+            "Name  Gruppe     Aliase\n----  ---------  ----------\nKäse  Kühlung\nMehl  Backzutat  Weizenmehl\n"
+        );
+        assert_eq!(table(&["Name"], &Vec::new()), "Name\n----\n");
+    }
+}
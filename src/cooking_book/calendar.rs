@@ -0,0 +1,363 @@
+use std::collections::HashMap;
+
+use crate::cooking_book::date::Date;
+use crate::cooking_book::ingredient::Ingredient;
+use crate::cooking_book::locale::Locale;
+use crate::cooking_book::meal_plan::{MealPlan, Slot};
+use crate::cooking_book::recipe::Recipe;
+use crate::cooking_book::render;
+use crate::cooking_book::shopping_list::ShoppingList;
+
+const PRODID: &'static str = "-//cooking-book//Essensplan//DE";
+const UID_DOMAIN: &'static str = "cooking-book";
+/// The maximal length of a content line in octets, without the line break.
+const MAX_LINE_LENGTH: usize = 75;
+
+/// Exports the meal plan and the shopping list as iCalendar (RFC 5545) for calendar apps.
+/// Every planned meal is a VEVENT with the ingredients in its description, every item
+/// of the shopping list a VTODO below one VTODO per store.
+/// The UIDs only depend on the day and meal or on the store id and ingredient, so calendar
+/// clients update their entries when the plan or the language changes.
+///
+/// #Arguments
+///
+/// * `meal_plan` The planned meals.
+/// * `recipes` All recipes by name, to look up the ingredients.
+/// * `shopping_list` The shopping list.
+/// * `today` The day of the export, used as time stamp.
+/// * `locale` The language of the names of meals, stores and groups.
+pub fn to_ical(
+    meal_plan: &MealPlan,
+    recipes: &HashMap<String, Recipe>,
+    shopping_list: &ShoppingList,
+    today: Date,
+    locale: Locale,
+) -> String {
+    let stamp = format!("DTSTAMP:{}T000000Z", compact_date(&today));
+    let mut lines: Vec<String> = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        format!("PRODID:{}", PRODID),
+        "CALSCALE:GREGORIAN".to_string(),
+        "METHOD:PUBLISH".to_string(),
+        format!(
+            "X-WR-CALNAME:{}",
+            escape_text(locale.text("label.meal_plan"))
+        ),
+    ];
+
+    for ((date, slot), name) in &meal_plan.meals {
+        let recipe = recipes.get(name);
+        let minutes = recipe
+            .and_then(|r| r.details.total_time())
+            .filter(|m| *m > 0)
+            .unwrap_or(60);
+
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!("UID:meal-{}-{}@{}", date, *slot as u8, UID_DOMAIN));
+        lines.push(stamp.to_string());
+        lines.push(format!(
+            "DTSTART:{}T{:02}0000",
+            compact_date(date),
+            start_hour(*slot)
+        ));
+        lines.push(format!("DURATION:PT{}M", minutes));
+        lines.push(format!(
+            "SUMMARY:{}",
+            escape_text(&format!("{}: {}", slot.label(locale), name))
+        ));
+        if let Some(recipe) = recipe {
+            let expanded = recipe.expand(recipes);
+            let recipe = expanded.as_ref().unwrap_or(recipe);
+            let mut ingredients: Vec<String> = recipe
+                .ingredients
+                .iter()
+                .map(|l| format!("- {}", render::ingredient_text(l)))
+                .collect();
+            if !ingredients.is_empty() {
+                ingredients.insert(0, format!("{}:", locale.text("label.ingredients")));
+                lines.push(format!(
+                    "DESCRIPTION:{}",
+                    escape_text(&ingredients.join("\n"))
+                ));
+            }
+        }
+        lines.push("END:VEVENT".to_string());
+    }
+
+    let mut to_buy: Vec<(&Ingredient, &u16)> = shopping_list.to_buy.iter().collect();
+    to_buy.sort();
+    let mut last_store_uid = String::new();
+    for (ingredient, amount) in to_buy {
+        let store = ingredient.preferred_store.label(locale);
+        let store_id = ingredient.preferred_store.id();
+        let store_uid = format!("shopping-{}@{}", store_id, UID_DOMAIN);
+        if store_uid != last_store_uid {
+            lines.push("BEGIN:VTODO".to_string());
+            lines.push(format!("UID:{}", store_uid));
+            lines.push(stamp.to_string());
+            lines.push(format!(
+                "SUMMARY:{}",
+                escape_text(&format!("{}: {}", locale.text("label.shopping"), store))
+            ));
+            lines.push("STATUS:NEEDS-ACTION".to_string());
+            lines.push("END:VTODO".to_string());
+            last_store_uid = store_uid.to_string();
+        }
+
+        let summary = match amount {
+            1 => ingredient.name.to_string(),
+            _ => format!("{} × {}", amount, ingredient.name),
+        };
+        lines.push("BEGIN:VTODO".to_string());
+        lines.push(format!(
+            "UID:shopping-{}-{}@{}",
+            store_id,
+            encode_uid(&ingredient.name),
+            UID_DOMAIN
+        ));
+        lines.push(stamp.to_string());
+        lines.push(format!("SUMMARY:{}", escape_text(&summary)));
+        lines.push(format!(
+            "CATEGORIES:{},{}",
+            escape_text(store),
+            escape_text(ingredient.group.label(locale))
+        ));
+        lines.push(format!("RELATED-TO;RELTYPE=PARENT:{}", store_uid));
+        lines.push("STATUS:NEEDS-ACTION".to_string());
+        lines.push("END:VTODO".to_string());
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    let mut ical = String::new();
+    for line in lines {
+        ical.push_str(&fold(&line));
+        ical.push_str("\r\n");
+    }
+    return ical;
+}
+
+/// Returns the hour a meal starts, in local time.
+fn start_hour(slot: Slot) -> u32 {
+    return match slot {
+        Slot::Breakfast => 8,
+        Slot::Lunch => 12,
+        Slot::Dinner => 18,
+    };
+}
+
+/// Returns a date like `20191024`.
+fn compact_date(date: &Date) -> String {
+    return format!("{:04}{:02}{:02}", date.year, date.month, date.day);
+}
+
+/// Escapes a value of type TEXT, see RFC 5545 section 3.3.11.
+fn escape_text(text: &str) -> String {
+    return text
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n");
+}
+
+/// Keeps letters and digits of a name for a UID, other characters are percent encoded.
+fn encode_uid(name: &str) -> String {
+    let mut uid = String::new();
+    for byte in name.to_lowercase().bytes() {
+        if byte.is_ascii_alphanumeric() {
+            uid.push(byte as char);
+        } else {
+            uid.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    return uid;
+}
+
+/// Folds a content line longer than 75 octets, without splitting characters.
+/// Continuation lines start with a space, see RFC 5545 section 3.1.
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > MAX_LINE_LENGTH {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+        folded.push(c);
+        length += c.len_utf8();
+    }
+    return folded;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{escape_text, fold, to_ical};
+    use crate::cooking_book::date::Date;
+    use crate::cooking_book::ingredient::Ingredient;
+    use crate::cooking_book::locale::Locale;
+    use crate::cooking_book::meal_plan::{MealPlan, Slot};
+    use crate::cooking_book::recipe::Recipe;
+    use crate::cooking_book::recipe_details::RecipeDetails;
+    use crate::cooking_book::shopping_list::ShoppingList;
+    use std::collections::HashMap;
+
+    /// Checks the structure of iCalendar text as required by RFC 5545 and returns the
+    /// unfolded content lines.
+    fn validate(ical: &str) -> Vec<String> {
+        assert!(ical.ends_with("\r\n"));
+        let mut lines: Vec<String> = Vec::new();
+        for line in ical[..ical.len() - 2].split("\r\n") {
+            assert!(!line.contains('\n') && !line.contains('\r'), "{}", line);
+            assert!(line.len() <= 75, "line too long: {}", line);
+            if line.starts_with(' ') {
+                lines.last_mut().unwrap().push_str(&line[1..]);
+            } else {
+                lines.push(line.to_string());
+            }
+        }
+
+        let mut open: Vec<(String, Vec<String>)> = Vec::new();
+        let mut uids: Vec<String> = Vec::new();
+        for line in &lines {
+            let name_end = line.find(|c| c == ':' || c == ';').expect(line);
+            let name = &line[..name_end];
+            assert!(
+                !name.is_empty()
+                    && name
+                        .chars()
+                        .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '-'),
+                "{}",
+                line
+            );
+            let value = &line[line.find(':').unwrap() + 1..];
+            match name {
+                "BEGIN" => open.push((value.to_string(), Vec::new())),
+                "END" => {
+                    let (component, properties) = open.pop().expect(line);
+                    assert_eq!(component, value);
+                    let count = |p: &str| properties.iter().filter(|n| *n == p).count();
+                    let required: &[&str] = match value {
+                        "VCALENDAR" => &["VERSION", "PRODID"],
+                        _ => &["UID", "DTSTAMP"],
+                    };
+                    for property in required {
+                        assert_eq!(count(property), 1, "{} in {}", property, value);
+                    }
+                }
+                _ => {
+                    if name == "UID" {
+                        assert!(!uids.contains(&value.to_string()), "{}", line);
+                        uids.push(value.to_string());
+                    }
+                    open.last_mut().expect(line).1.push(name.to_string());
+                }
+            }
+        }
+        assert!(open.is_empty());
+        assert_eq!(lines.first().unwrap(), "BEGIN:VCALENDAR");
+        return lines;
+    }
+
+    fn get_data() -> (MealPlan, HashMap<String, Recipe>, ShoppingList) {
+        let mut recipes: HashMap<String, Recipe> = HashMap::new();
+        let mut pancakes = Recipe::new_by_line(
+            "Pfannkuchen;Mehl,300,G,gesiebt;Milch,500,ML;@Apfelmus,1/2",
+            &HashMap::new(),
+        );
+        pancakes.details = RecipeDetails::new_by_text("prep_time: 10\ncook_time: 20").0;
+        recipes.insert(pancakes.name.to_string(), pancakes);
+        let applesauce = Recipe::new_by_line("Apfelmus;Äpfel,4,", &HashMap::new());
+        recipes.insert(applesauce.name.to_string(), applesauce);
+
+        let mut meal_plan = MealPlan::new();
+        meal_plan.plan(Date::new(2019, 10, 24).unwrap(), Slot::Lunch, "Pfannkuchen");
+        meal_plan.plan(Date::new(2019, 10, 24).unwrap(), Slot::Dinner, "Brot");
+
+        let mut shopping_list = ShoppingList::new();
+        shopping_list.add_item(Ingredient::new_by_line("Mehl;5;0"), 2);
+        shopping_list.add_item(Ingredient::new_by_line("Milch;2;0"), 1);
+        shopping_list.add_item(Ingredient::new_by_line("Zahnpasta;-1;1"), 1);
+        return (meal_plan, recipes, shopping_list);
+    }
+
+    #[test]
+    fn test_escape_and_fold() {
+        assert_eq!(escape_text("a,b;c\\d\ne"), "a\\,b\\;c\\\\d\\ne");
+        assert_eq!(fold("SUMMARY:kurz"), "SUMMARY:kurz");
+
+        let line = format!("DESCRIPTION:{}", "ä".repeat(40));
+        let folded = fold(&line);
+        let parts: Vec<&str> = folded.split("\r\n").collect();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].len(), 74);
+        assert!(parts[1].starts_with(' '));
+        assert_eq!(folded.replace("\r\n ", ""), line);
+    }
+
+    #[test]
+    fn test_to_ical() {
+        let (meal_plan, recipes, shopping_list) = get_data();
+        let ical = to_ical(
+            &meal_plan,
+            &recipes,
+            &shopping_list,
+            Date::new(2019, 10, 20).unwrap(),
+            Locale::De,
+        );
+        let lines = validate(&ical);
+
+        assert_eq!(
+            lines[..13].to_vec(),
+            vec![
+                "BEGIN:VCALENDAR",
+                "VERSION:2.0",
+                "PRODID:-//cooking-book//Essensplan//DE",
+                "CALSCALE:GREGORIAN",
+                "METHOD:PUBLISH",
+                "X-WR-CALNAME:Essensplan",
+                "BEGIN:VEVENT",
+                "UID:meal-2019-10-24-1@cooking-book",
+                "DTSTAMP:20191020T000000Z",
+                "DTSTART:20191024T120000",
+                "DURATION:PT30M",
+                "SUMMARY:Mittagessen: Pfannkuchen",
+                "DESCRIPTION:Zutaten:\\n- 300 G Mehl\\n- 500 ML Milch\\n- 2 Äpfel",
+            ]
+        );
+        assert!(ical.contains(
+            "BEGIN:VEVENT\r\nUID:meal-2019-10-24-2@cooking-book\r\nDTSTAMP:20191020T000000Z\r\nDTSTART:20191024T180000\r\nDURATION:PT60M\r\nSUMMARY:Abendessen: Brot\r\nEND:VEVENT\r\n"
+        ));
+        assert!(ical.contains(
+            "BEGIN:VTODO\r\nUID:shopping-rewe@cooking-book\r\nDTSTAMP:20191020T000000Z\r\nSUMMARY:Einkaufen: Rewe\r\nSTATUS:NEEDS-ACTION\r\nEND:VTODO\r\n"
+        ));
+        assert!(ical.contains(
+            "UID:shopping-rewe-mehl@cooking-book\r\nDTSTAMP:20191020T000000Z\r\nSUMMARY:2 × Mehl\r\nCATEGORIES:Rewe,Backzutat\r\nRELATED-TO;RELTYPE=PARENT:shopping-rewe@cooking-book\r\n"
+        ));
+        assert!(ical.contains("UID:shopping-dm-zahnpasta@cooking-book\r\n"));
+        assert_eq!(lines.iter().filter(|l| *l == "BEGIN:VTODO").count(), 5);
+        assert_eq!(lines.last().unwrap(), "END:VCALENDAR");
+    }
+
+    #[test]
+    fn test_stable_uids() {
+        let (mut meal_plan, recipes, shopping_list) = get_data();
+        let today = Date::new(2019, 10, 20).unwrap();
+        let uids = |meal_plan: &MealPlan, locale: Locale| -> Vec<String> {
+            return validate(&to_ical(meal_plan, &recipes, &shopping_list, today, locale))
+                .into_iter()
+                .filter(|l| l.starts_with("UID:"))
+                .collect();
+        };
+        let before = uids(&meal_plan, Locale::De);
+        assert_eq!(uids(&meal_plan, Locale::En), before);
+
+        meal_plan.plan(Date::new(2019, 10, 24).unwrap(), Slot::Dinner, "Apfelmus");
+        assert_eq!(uids(&meal_plan, Locale::De), before);
+
+        assert_eq!(
+            to_ical(&MealPlan::new(), &HashMap::new(), &ShoppingList::new(), today, Locale::En),
+            "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//cooking-book//Essensplan//DE\r\nCALSCALE:GREGORIAN\r\nMETHOD:PUBLISH\r\nX-WR-CALNAME:Meal plan\r\nEND:VCALENDAR\r\n"
+        );
+    }
+}
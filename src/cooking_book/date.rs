@@ -0,0 +1,166 @@
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A calendar date without time zone, written as `YYYY-MM-DD`.
+#[derive(Eq, Ord, PartialEq, PartialOrd, Clone, Debug, Copy, Hash)]
+pub struct Date {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl Date {
+    /// Returns the date if it is valid.
+    ///
+    /// #Arguments
+    ///
+    /// * `year` The year.
+    /// * `month` The month from 1 to 12.
+    /// * `day` The day of the month starting at 1.
+    pub fn new(year: i32, month: u32, day: u32) -> Option<Date> {
+        if month < 1 || month > 12 || day < 1 || day > Date::days_in_month(year, month) {
+            return None;
+        }
+        return Some(Date { year, month, day });
+    }
+
+    /// Parses a date written as `YYYY-MM-DD`.
+    ///
+    /// #Arguments
+    ///
+    /// * `text` The text to parse.
+    pub fn parse(text: &str) -> Option<Date> {
+        let mut values = text.trim().split('-');
+        let year = values.next()?.parse::<i32>().ok()?;
+        let month = values.next()?.parse::<u32>().ok()?;
+        let day = values.next()?.parse::<u32>().ok()?;
+        if values.next().is_some() {
+            return None;
+        }
+        return Date::new(year, month, day);
+    }
+
+    /// Returns the current date in UTC.
+    pub fn today() -> Date {
+        let seconds = match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(duration) => duration.as_secs() as i64,
+            Err(_) => 0,
+        };
+        return Date::from_days(seconds / 86400);
+    }
+
+    /// Returns the date for a number of days since 1970-01-01.
+    ///
+    /// #Arguments
+    ///
+    /// * `days` The days since 1970-01-01.
+    pub fn from_days(days: i64) -> Date {
+        // civil from days, see http://howardhinnant.github.io/date_algorithms.html
+        let z = days + 719468;
+        let era = if z >= 0 { z } else { z - 146096 } / 146097;
+        let doe = z - era * 146097;
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = (yoe + era * 400 + if month <= 2 { 1 } else { 0 }) as i32;
+        return Date { year, month, day };
+    }
+
+    /// Returns the number of days since 1970-01-01.
+    pub fn to_days(&self) -> i64 {
+        let year = self.year as i64 - if self.month <= 2 { 1 } else { 0 };
+        let era = if year >= 0 { year } else { year - 399 } / 400;
+        let yoe = year - era * 400;
+        let month = self.month as i64;
+        let doy =
+            (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + self.day as i64 - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        return era * 146097 + doe - 719468;
+    }
+
+    /// Returns the date some days later, or earlier for negative days.
+    ///
+    /// #Arguments
+    ///
+    /// * `days` The number of days to add.
+    pub fn add_days(&self, days: i64) -> Date {
+        return Date::from_days(self.to_days() + days);
+    }
+
+    /// Returns the number of days from this date until the other one.
+    ///
+    /// #Arguments
+    ///
+    /// * `other` The other date.
+    pub fn days_until(&self, other: &Date) -> i64 {
+        return other.to_days() - self.to_days();
+    }
+
+    /// Returns the day of the week from 0 for Monday to 6 for Sunday.
+    pub fn weekday(&self) -> u32 {
+        // 1970-01-01 was a Thursday
+        return (((self.to_days() + 3) % 7 + 7) % 7) as u32;
+    }
+
+    fn days_in_month(year: i32, month: u32) -> u32 {
+        match month {
+            2 => {
+                if (year % 4 == 0 && year % 100 != 0) || year % 400 == 0 {
+                    29
+                } else {
+                    28
+                }
+            }
+            4 | 6 | 9 | 11 => 30,
+            _ => 31,
+        }
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Date;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Date::parse("2019-02-28"), Date::new(2019, 2, 28));
+        assert_eq!(Date::parse("2020-02-29").unwrap().day, 29);
+        assert_eq!(Date::parse("2019-02-29"), None);
+        assert_eq!(Date::parse("2019-13-01"), None);
+        assert_eq!(Date::parse("2019-1"), None);
+        assert_eq!(Date::parse("asd"), None);
+    }
+
+    #[test]
+    fn test_days() {
+        assert_eq!(Date::from_days(0), Date::new(1970, 1, 1).unwrap());
+        assert_eq!(Date::new(2000, 3, 1).unwrap().to_days(), 11017);
+        assert_eq!(Date::from_days(11017), Date::new(2000, 3, 1).unwrap());
+        assert_eq!(Date::new(1969, 12, 31).unwrap().to_days(), -1);
+
+        let date = Date::new(2019, 12, 30).unwrap();
+        assert_eq!(date.add_days(3), Date::new(2020, 1, 2).unwrap());
+        assert_eq!(date.add_days(-30), Date::new(2019, 11, 30).unwrap());
+        assert_eq!(date.days_until(&Date::new(2020, 3, 1).unwrap()), 62);
+    }
+
+    #[test]
+    fn test_weekday() {
+        assert_eq!(Date::new(1970, 1, 1).unwrap().weekday(), 3);
+        assert_eq!(Date::new(2019, 4, 1).unwrap().weekday(), 0);
+        assert_eq!(Date::new(2019, 4, 7).unwrap().weekday(), 6);
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(format!("{}", Date::new(2019, 4, 1).unwrap()), "2019-04-01");
+    }
+}
@@ -2,6 +2,8 @@ use self::Group::*;
 use std::fmt;
 use std::slice::Iter;
 
+use crate::cooking_book::locale::Locale;
+
 /// The groups for ingredients
 #[derive(Eq, Ord, PartialEq, PartialOrd, Clone, Debug, Copy, Hash)]
 pub enum Group {
@@ -27,24 +29,57 @@ impl Group {
         GROUPS.into_iter()
     }
 
-    pub fn all_as_json() -> String {
+    /// Export all groups with their ids and labels to JSON.
+    ///
+    /// #Arguments
+    /// * `locale` The language of the labels.
+    pub fn all_as_json(locale: Locale) -> String {
         let mut json = String::new();
         json.push_str("{\"groups\": [");
 
         let mut is_first: bool = true;
         for group in Group::get_group_iterator() {
-            if is_first {
-                json.push_str(&format!("\"{}\"", group));
-                is_first = false;
-            } else {
-                json.push_str(&format!(", \"{}\"", group));
+            if !is_first {
+                json.push_str(", ");
             }
+            json.push_str(&format!(
+                "{{\"id\": \"{}\", \"label\": \"{}\"}}",
+                group.id(),
+                group.label(locale)
+            ));
+            is_first = false;
         }
         json.push_str("]}");
 
         return json;
     }
 
+    /// Returns the stable id of the group like `vegetable`, which doesn't depend on the language.
+    pub fn id(&self) -> &'static str {
+        return match *self {
+            Group::Vegetable => "vegetable",
+            Group::Fruit => "fruit",
+            Group::Freezer => "freezer",
+            Group::Conserved => "conserved",
+            Group::Beverage => "beverage",
+            Group::Baking => "baking",
+            Group::Pasta => "pasta",
+            Group::Legume => "legume",
+            Group::Spice => "spice",
+            Group::Snacks => "snacks",
+            Group::Sweets => "sweets",
+            Group::Other => "other",
+        };
+    }
+
+    /// Returns the name of the group shown to the user.
+    ///
+    /// #Arguments
+    /// * `locale` The language of the name.
+    pub fn label(&self, locale: Locale) -> &'static str {
+        return locale.text(&format!("group.{}", self.id()));
+    }
+
     /// Returns the decoded Group.
     ///
     /// #Arguments
@@ -65,30 +100,36 @@ impl Group {
             _ => Group::Other,
         }
     }
+
+    /// Returns the group by its number, its id or its name in any language like `Gemüse`,
+    /// ignoring the case.
+    ///
+    /// #Arguments
+    /// * `name` The number, id or name of the group.
+    pub fn lookup_group_name(name: &str) -> Option<Group> {
+        if let Ok(number) = name.parse::<usize>() {
+            return Some(Group::lookup_group_number(number));
+        }
+        let name = name.trim().to_lowercase();
+        return (0..12).map(Group::lookup_group_number).find(|g| {
+            return g.id() == name
+                || [Locale::De, Locale::En]
+                    .iter()
+                    .any(|l| g.label(*l).to_lowercase() == name);
+        });
+    }
 }
 
 impl fmt::Display for Group {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match *self {
-            Group::Vegetable => write!(f, "Gemüse"),
-            Group::Fruit => write!(f, "Obst"),
-            Group::Freezer => write!(f, "Kühlung"),
-            Group::Conserved => write!(f, "Konserve"),
-            Group::Beverage => write!(f, "Getränk"),
-            Group::Baking => write!(f, "Backzutat"),
-            Group::Pasta => write!(f, "Nudeln"),
-            Group::Legume => write!(f, "Hülsenfrüchte"),
-            Group::Spice => write!(f, "Gewürz"),
-            Group::Snacks => write!(f, "Knabberkram"),
-            Group::Sweets => write!(f, "Süßigkeiten"),
-            Group::Other => write!(f, "Anderes"),
-        }
+        write!(f, "{}", self.label(Locale::De))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::Group;
+    use crate::cooking_book::locale::Locale;
     #[test]
     fn test_group_size() {
         let it = Group::get_group_iterator();
@@ -96,6 +137,13 @@ mod tests {
         assert_eq!(size, 8);
     }
 
+    #[test]
+    fn test_all_as_json() {
+        assert!(Group::all_as_json(Locale::En).starts_with(
+            "{\"groups\": [{\"id\": \"vegetable\", \"label\": \"Vegetables\"}, {\"id\": \"fruit\""
+        ));
+    }
+
     #[test]
     fn test_group_display() {
         assert_eq!(&format!("{}", Group::Other), "Anderes");
@@ -116,4 +164,18 @@ mod tests {
         assert_eq!(Group::lookup_group_number(10), Group::Sweets);
         assert_eq!(Group::lookup_group_number(11), Group::Other);
     }
+
+    #[test]
+    fn test_lookup_group_name() {
+        assert_eq!(Group::lookup_group_name("gemüse"), Some(Group::Vegetable));
+        assert_eq!(Group::lookup_group_name("Süßigkeiten"), Some(Group::Sweets));
+        assert_eq!(Group::lookup_group_name("5"), Some(Group::Baking));
+        assert_eq!(Group::lookup_group_name("Anderes"), Some(Group::Other));
+        assert_eq!(Group::lookup_group_name("legume"), Some(Group::Legume));
+        assert_eq!(
+            Group::lookup_group_name("Canned goods"),
+            Some(Group::Conserved)
+        );
+        assert_eq!(Group::lookup_group_name("Fleisch"), None);
+    }
 }
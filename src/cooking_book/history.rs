@@ -0,0 +1,172 @@
+use crate::cooking_book::date::Date;
+use crate::file_access::json::Json;
+
+/// A recipe which was cooked on a day.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct CookingEntry {
+    pub date: Date,
+    pub recipe: String,
+    pub rating: Option<u8>,
+    pub notes: String,
+}
+
+impl CookingEntry {
+    /// Export this entry to JSON.
+    pub fn to_json(&self) -> String {
+        let mut json: String = String::new();
+        json.push_str(&format!(
+            "{{\"date\": \"{}\", \"recipe\": \"{}\"",
+            self.date,
+            Json::escape(&self.recipe)
+        ));
+        if let Some(rating) = self.rating {
+            json.push_str(&format!(", \"rating\": {}", rating));
+        }
+        json.push_str(&format!(", \"notes\": \"{}\"}}", Json::escape(&self.notes)));
+        return json;
+    }
+}
+
+/// The recipes which were cooked, ordered by date.
+#[derive(PartialEq, Eq)]
+pub struct History {
+    pub entries: Vec<CookingEntry>,
+}
+
+impl History {
+    pub fn new() -> History {
+        let entries: Vec<CookingEntry> = Vec::new();
+        return History { entries };
+    }
+
+    /// Records that a recipe was cooked. Entries of the same day keep the order they were recorded in.
+    ///
+    /// #Arguments
+    ///
+    /// * `date` The day the recipe was cooked.
+    /// * `recipe` The name of the recipe.
+    /// * `rating` The rating from 1 to 5, if any.
+    /// * `notes` Notes on how it turned out.
+    pub fn record(
+        &mut self,
+        date: Date,
+        recipe: &str,
+        rating: Option<u8>,
+        notes: &str,
+    ) -> Result<(), String> {
+        if let Some(rating) = rating {
+            if rating < 1 || rating > 5 {
+                return Err(format!("Rating must be between 1 and 5, got {}", rating));
+            }
+        }
+
+        let index = self
+            .entries
+            .iter()
+            .position(|e| e.date > date)
+            .unwrap_or(self.entries.len());
+        self.entries.insert(
+            index,
+            CookingEntry {
+                date,
+                recipe: recipe.to_string(),
+                rating,
+                notes: notes.trim().to_string(),
+            },
+        );
+        return Ok(());
+    }
+
+    /// Returns the entries of a recipe, or all entries if no recipe is given.
+    ///
+    /// #Arguments
+    ///
+    /// * `recipe` The name of the recipe.
+    pub fn get_entries(&self, recipe: Option<&str>) -> Vec<&CookingEntry> {
+        return self
+            .entries
+            .iter()
+            .filter(|e| match recipe {
+                Some(recipe) => e.recipe == recipe,
+                None => true,
+            })
+            .collect();
+    }
+
+    /// Returns whether a recipe was recorded as cooked on a day.
+    ///
+    /// #Arguments
+    ///
+    /// * `date` The day.
+    /// * `recipe` The name of the recipe.
+    pub fn contains(&self, date: Date, recipe: &str) -> bool {
+        return self
+            .entries
+            .iter()
+            .any(|e| e.date == date && e.recipe == recipe);
+    }
+
+    /// Export a list of entries to JSON.
+    ///
+    /// #Arguments
+    ///
+    /// * `entries` The entries to export.
+    pub fn entries_to_json(entries: &Vec<&CookingEntry>) -> String {
+        let mut json: String = String::new();
+        json.push('[');
+
+        let mut is_first: bool = true;
+        for entry in entries {
+            if !is_first {
+                json.push_str(", ");
+            }
+            json.push_str(&entry.to_json());
+            is_first = false;
+        }
+
+        json.push(']');
+        return json;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::History;
+    use crate::cooking_book::date::Date;
+
+    #[test]
+    fn test_record() {
+        let mut history = History::new();
+        let monday = Date::new(2019, 4, 1).unwrap();
+        assert!(history
+            .record(monday.add_days(1), "Suppe", None, "")
+            .is_ok());
+        assert!(history
+            .record(monday, "Waffel", Some(4), "mit \"Puderzucker\";\nlecker")
+            .is_ok());
+        assert!(history.record(monday, "Suppe", Some(6), "").is_err());
+
+        let recipes: Vec<&str> = history.entries.iter().map(|e| e.recipe.as_str()).collect();
+        assert_eq!(recipes, vec!["Waffel", "Suppe"]);
+        assert_eq!(history.entries[0].notes, "mit \"Puderzucker\";\nlecker");
+        assert!(history.contains(monday, "Waffel"));
+        assert!(!history.contains(monday, "Suppe"));
+    }
+
+    #[test]
+    fn test_to_json() {
+        let mut history = History::new();
+        let monday = Date::new(2019, 4, 1).unwrap();
+        let _ = history.record(monday, "Waffel", Some(4), "mit \"Puderzucker\"\n");
+        let _ = history.record(monday, "Suppe", None, "zu salzig");
+
+        assert_eq!(
+            History::entries_to_json(&history.get_entries(Some("Suppe"))),
+            "[{\"date\": \"2019-04-01\", \"recipe\": \"Suppe\", \"notes\": \"zu salzig\"}]"
+        );
+        assert_eq!(
+            History::entries_to_json(&history.get_entries(None)),
+            "[{\"date\": \"2019-04-01\", \"recipe\": \"Waffel\", \"rating\": 4, \"notes\": \"mit \\\"Puderzucker\\\"\"}, {\"date\": \"2019-04-01\", \"recipe\": \"Suppe\", \"notes\": \"zu salzig\"}]"
+        );
+    }
+}
@@ -1,17 +1,26 @@
 use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::fmt;
+use std::hash::{Hash, Hasher};
 
 use crate::cooking_book::group::Group;
+use crate::cooking_book::locale::Locale;
+use crate::cooking_book::search;
 use crate::cooking_book::store::Store;
 use crate::file_access::persistency;
 
-/// An ingredient which is in a specific group and has a preferred store
-#[derive(Clone, PartialEq, Eq, Hash)]
+/// An ingredient which is in a specific group and has a preferred store.
+/// It can be referred to by its aliases as well, like a misspelling or the plural form.
+/// The price is in cent for one package, if known.
+/// An ingredient is identified by its name alone, so editing its aliases or price keeps it
+/// the same key on the shopping list and in the pantry.
+#[derive(Clone)]
 pub struct Ingredient {
     pub name: String,
     pub group: Group,
     pub preferred_store: Store,
+    pub aliases: Vec<String>,
+    pub price: Option<u32>,
 }
 
 impl Ingredient {
@@ -37,13 +46,177 @@ impl Ingredient {
             Err(_) => Store::Any,
         };
 
+        let aliases: Vec<String> = match values.next() {
+            Some(aliases) => aliases
+                .split(',')
+                .map(|a| a.trim())
+                .filter(|a| !a.is_empty())
+                .map(|a| a.to_string())
+                .collect(),
+            None => Vec::new(),
+        };
+
+        let price: Option<u32> = match values.next() {
+            Some(price) => price.trim().parse::<u32>().ok(),
+            None => None,
+        };
+
         Ingredient {
             name,
             group,
             preferred_store: store,
+            aliases,
+            price,
         }
     }
 
+    /// Returns whether the ingredient is called like this, either by its name or one of its aliases.
+    /// Aliases are compared ignoring case.
+    ///
+    /// #Arguments
+    ///
+    /// * `name` The name to compare.
+    pub fn is_called(&self, name: &str) -> bool {
+        if self.name == name {
+            return true;
+        }
+        let name = name.to_lowercase();
+        return self.aliases.iter().any(|a| a.to_lowercase() == name);
+    }
+
+    /// Returns the ingredient with the name or alias.
+    ///
+    /// #Arguments
+    ///
+    /// * `all_ingredients` The collection of all ingredients.
+    /// * `name` The name or alias to look up.
+    pub fn resolve<'a>(
+        all_ingredients: &'a HashMap<String, Ingredient>,
+        name: &str,
+    ) -> Option<&'a Ingredient> {
+        if let Some(ingredient) = all_ingredients.get(name) {
+            return Some(ingredient);
+        }
+        return all_ingredients.values().find(|i| i.is_called(name));
+    }
+
+    /// Returns the ingredients matching the query by name or alias, best match first.
+    /// Case, umlauts and small typos are ignored.
+    ///
+    /// #Arguments
+    ///
+    /// * `all_ingredients` The collection of all ingredients.
+    /// * `query` The text searched for.
+    /// * `limit` The maximum number of ingredients to return.
+    pub fn suggest<'a>(
+        all_ingredients: &'a HashMap<String, Ingredient>,
+        query: &str,
+        limit: usize,
+    ) -> Vec<&'a Ingredient> {
+        let mut candidates: Vec<(&str, &Ingredient)> = Vec::new();
+        for ingredient in all_ingredients.values() {
+            candidates.push((&ingredient.name, ingredient));
+            for alias in &ingredient.aliases {
+                candidates.push((alias, ingredient));
+            }
+        }
+
+        let mut suggestions: Vec<&Ingredient> = Vec::new();
+        for ingredient in search::rank(query, candidates) {
+            if !suggestions.contains(&ingredient) {
+                suggestions.push(ingredient);
+            }
+        }
+        suggestions.truncate(limit);
+        return suggestions;
+    }
+
+    /// Returns the ingredient which is probably meant by the name, even if it is misspelled.
+    ///
+    /// #Arguments
+    ///
+    /// * `all_ingredients` The collection of all ingredients.
+    /// * `name` The name to look up.
+    pub fn find_similar<'a>(
+        all_ingredients: &'a HashMap<String, Ingredient>,
+        name: &str,
+    ) -> Option<&'a Ingredient> {
+        if let Some(ingredient) = Ingredient::resolve(all_ingredients, name) {
+            return Some(ingredient);
+        }
+
+        let mut keys: Vec<&String> = all_ingredients.keys().collect();
+        keys.sort();
+        for k in keys {
+            let ingredient = all_ingredients.get(k).unwrap();
+            if search::is_near_duplicate(name, &ingredient.name)
+                || ingredient
+                    .aliases
+                    .iter()
+                    .any(|a| search::is_near_duplicate(name, a))
+            {
+                return Some(ingredient);
+            }
+        }
+        return None;
+    }
+
+    /// Add an alias to an ingredient and persist it.
+    /// The alias must neither be the name nor an alias of another ingredient.
+    ///
+    /// #Arguments
+    ///
+    /// * `name` The name of the ingredient.
+    /// * `alias` The alias to add.
+    pub fn add_alias(name: &str, alias: &str) -> Result<(), String> {
+        let mut all_ingredients = persistency::load_ingredients();
+        let alias = alias.trim();
+        if !Ingredient::is_valid_name(alias) {
+            return Err(format!("Invalid alias '{}'", alias));
+        }
+        if let Some(existing) = Ingredient::resolve(&all_ingredients, alias) {
+            if existing.name != name {
+                return Err(format!("'{}' already refers to '{}'", alias, existing.name));
+            }
+            return Ok(());
+        }
+
+        match all_ingredients.get_mut(name) {
+            Some(ingredient) => ingredient.aliases.push(alias.to_string()),
+            None => return Err(format!("Unknown ingredient '{}'", name)),
+        }
+        return persistency::write_all_ingredients(&all_ingredients);
+    }
+
+    /// Returns whether the text can be stored as name or alias of an ingredient. Separators of
+    /// the files and the line prefixes of tags, sub-recipes and sections are not allowed.
+    ///
+    /// #Arguments
+    ///
+    /// * `name` The trimmed name or alias.
+    pub fn is_valid_name(name: &str) -> bool {
+        return !name.is_empty()
+            && !name.contains(|c: char| c == ';' || c == ',' || c.is_control())
+            && !name.starts_with(|c: char| c == '#' || c == '@' || c == '[');
+    }
+
+    /// Remove an alias from an ingredient and persist it.
+    ///
+    /// #Arguments
+    ///
+    /// * `name` The name of the ingredient.
+    /// * `alias` The alias to remove.
+    pub fn remove_alias(name: &str, alias: &str) -> Result<(), String> {
+        let mut all_ingredients = persistency::load_ingredients();
+        match all_ingredients.get_mut(name) {
+            Some(ingredient) => ingredient
+                .aliases
+                .retain(|a| a.to_lowercase() != alias.to_lowercase()),
+            None => return Err(format!("Unknown ingredient '{}'", name)),
+        }
+        return persistency::write_all_ingredients(&all_ingredients);
+    }
+
     /// Change the assigned group
     /// 
     /// #Arguments
@@ -62,6 +235,15 @@ impl Ingredient {
         self.preferred_store = Store::lookup_store_number(store);
     }
 
+    /// Change the price of one package
+    ///
+    /// #Arguments
+    ///
+    /// * `price` the price in cent
+    pub fn set_price(&mut self, price: u32) {
+        self.price = Some(price);
+    }
+
     /// Create a new ingredient with only a name. Store and Group will fallback to their defaults.
     ///
     /// #Arguments
@@ -72,6 +254,8 @@ impl Ingredient {
             name: name.to_string(),
             group: Group::Other,
             preferred_store: Store::Any,
+            aliases: Vec::new(),
+            price: None,
         };
     }
     /// Create a new ingredient with only a name. Store and Group will fallback to their defaults.
@@ -93,12 +277,115 @@ impl Ingredient {
         return Ok(());
     }
 
+    /// Rename an ingredient. Every reference in the recipes and the shopping list is updated
+    /// and all files are written together.
+    ///
+    /// #Arguments
+    ///
+    /// * `name` The current name of the ingredient.
+    /// * `new_name` The new name of the ingredient, which must not exist yet.
+    pub fn rename(name: &str, new_name: &str) -> Result<(), String> {
+        let all_ingredients = persistency::load_ingredients();
+        let new_name = new_name.trim();
+        if new_name.is_empty() {
+            return Err("The new name must not be empty".to_string());
+        }
+        if !Ingredient::is_valid_name(new_name) {
+            return Err(format!("Invalid name '{}'", new_name));
+        }
+        if all_ingredients.contains_key(new_name) {
+            return Err(format!(
+                "Ingredient '{}' already exists, merge instead",
+                new_name
+            ));
+        }
+
+        let mut renamed = match all_ingredients.get(name) {
+            Some(ingredient) => ingredient.clone(),
+            None => return Err(format!("Unknown ingredient '{}'", name)),
+        };
+        renamed.name = new_name.to_string();
+
+        return Ingredient::replace_everywhere(name, renamed, all_ingredients);
+    }
+
+    /// Merge an ingredient into another one. Every reference in the recipes and the shopping list
+    /// is replaced by the target, amounts on the shopping list are summed up.
+    /// The name and aliases of the merged ingredient become aliases of the target.
+    ///
+    /// #Arguments
+    ///
+    /// * `name` The name of the ingredient to merge and remove.
+    /// * `target` The name of the ingredient to keep.
+    pub fn merge_into(name: &str, target: &str) -> Result<(), String> {
+        let all_ingredients = persistency::load_ingredients();
+        if name == target {
+            return Err("Can't merge an ingredient into itself".to_string());
+        }
+        if !all_ingredients.contains_key(name) {
+            return Err(format!("Unknown ingredient '{}'", name));
+        }
+
+        let mut target = match all_ingredients.get(target) {
+            Some(ingredient) => ingredient.clone(),
+            None => return Err(format!("Unknown ingredient '{}'", target)),
+        };
+        let merged = all_ingredients.get(name).unwrap();
+        target.aliases.push(merged.name.to_string());
+        target.aliases.extend(merged.aliases.iter().cloned());
+
+        return Ingredient::replace_everywhere(name, target, all_ingredients);
+    }
+
+    /// Delete an ingredient from the catalog. It is removed from all recipes and the shopping list
+    /// as well and all files are written together.
+    ///
+    /// #Arguments
+    ///
+    /// * `name` The name of the ingredient to delete.
+    pub fn delete(name: &str) -> Result<(), String> {
+        let mut all_ingredients = persistency::load_ingredients();
+        if all_ingredients.remove(name).is_none() {
+            return Err(format!("Unknown ingredient '{}'", name));
+        }
+
+        let mut all_recipes = persistency::load_recipes();
+        for (_n, recipe) in all_recipes.iter_mut() {
+            recipe.remove_ingredient(name);
+        }
+
+        let mut shopping_list = persistency::load_shopping_list();
+        shopping_list.remove_by_name(name);
+
+        return persistency::write_all(&all_ingredients, &all_recipes, &shopping_list);
+    }
+
+    fn replace_everywhere(
+        name: &str,
+        replacement: Ingredient,
+        mut all_ingredients: HashMap<String, Ingredient>,
+    ) -> Result<(), String> {
+        let mut all_recipes = persistency::load_recipes();
+        for (_n, recipe) in all_recipes.iter_mut() {
+            recipe.replace_ingredient(name, &replacement)?;
+        }
+
+        let mut shopping_list = persistency::load_shopping_list();
+        shopping_list.replace_ingredient(name, &replacement);
+
+        all_ingredients.remove(name);
+        all_ingredients.insert(replacement.name.to_string(), replacement);
+
+        return persistency::write_all(&all_ingredients, &all_recipes, &shopping_list);
+    }
+
     /// Export the list of all ingredients to JSON.
     ///
     /// #Arguments
     ///
     /// ' `all_ingredients` The collection of all ingredients.
-    pub fn all_to_json(all_ingredients: &HashMap<String, Ingredient>) -> String {
+    /// * `locale` The language of the labels of groups and stores.
+    pub fn all_to_json(all_ingredients: &HashMap<String, Ingredient>, locale: Locale) -> String {
         let mut json: String = String::new();
         json.push('[');
 
@@ -111,7 +398,7 @@ impl Ingredient {
                 json.push_str(", ");
             }
 
-            json.push_str(&all_ingredients.get(k).unwrap().to_json());
+            json.push_str(&all_ingredients.get(k).unwrap().to_json(locale));
             is_first = false;
         }
 
@@ -119,8 +406,12 @@ impl Ingredient {
         return json;
     }
 
-    /// Export this ingredient to JSON.
-    pub fn to_json(&self) -> String {
+    /// Export this ingredient to JSON. Group and store are exported by their id and label.
+    ///
+    /// #Arguments
+    ///
+    /// * `locale` The language of the labels.
+    pub fn to_json(&self, locale: Locale) -> String {
         let mut json: String = String::new();
         json.push('{');
 
@@ -128,13 +419,32 @@ impl Ingredient {
         json.push_str(&self.name);
         json.push_str("\", ");
 
-        json.push_str("\"group\": \"");
-        json.push_str(&format!("{}", &self.group));
-        json.push_str("\", ");
+        json.push_str(&format!(
+            "\"group\": \"{}\", \"group_label\": \"{}\", ",
+            self.group.id(),
+            self.group.label(locale)
+        ));
+
+        json.push_str(&format!(
+            "\"store\": \"{}\", \"store_label\": \"{}\", ",
+            self.preferred_store.id(),
+            self.preferred_store.label(locale)
+        ));
+
+        json.push_str("\"aliases\": [");
+        let mut is_first: bool = true;
+        for alias in &self.aliases {
+            if !is_first {
+                json.push_str(", ");
+            }
+            json.push_str(&format!("\"{}\"", alias));
+            is_first = false;
+        }
+        json.push(']');
 
-        json.push_str("\"store\": \"");
-        json.push_str(&format!("{}", &self.preferred_store));
-        json.push_str("\"");
+        if let Some(price) = self.price {
+            json.push_str(&format!(", \"price\": {}", price));
+        }
 
         json.push('}');
 
@@ -155,6 +465,19 @@ impl Ingredient {
     }
 }
 
+impl PartialEq for Ingredient {
+    fn eq(&self, other: &Self) -> bool {
+        return self.name == other.name;
+    }
+}
+impl Eq for Ingredient {}
+
+impl Hash for Ingredient {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.name.hash(state);
+    }
+}
+
 impl PartialOrd for Ingredient {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
@@ -185,8 +508,10 @@ impl fmt::Display for Ingredient {
 mod tests {
     use super::Ingredient;
     use crate::cooking_book::group::Group;
+    use crate::cooking_book::locale::Locale;
     use crate::cooking_book::store::Store;
     use std::cmp::Ordering;
+    use std::collections::HashMap;
 
     #[test]
     fn test_to_json() {
@@ -194,11 +519,127 @@ mod tests {
             name: "Gurke".to_string(),
             group: Group::Vegetable,
             preferred_store: Store::Any,
+            aliases: Vec::new(),
+            price: None,
         };
         assert_eq!(
-            ingredient.to_json(),
-            "{\"name\": \"Gurke\", \"group\": \"Gemüse\", \"store\": \"Überall\"}"
+            ingredient.to_json(Locale::De),
+            "{\"name\": \"Gurke\", \"group\": \"vegetable\", \"group_label\": \"Gemüse\", \"store\": \"any\", \"store_label\": \"Überall\", \"aliases\": []}"
+        );
+        assert_eq!(
+            ingredient.to_json(Locale::En),
+            "{\"name\": \"Gurke\", \"group\": \"vegetable\", \"group_label\": \"Vegetables\", \"store\": \"any\", \"store_label\": \"Anywhere\", \"aliases\": []}"
+        );
+    }
+
+    #[test]
+    fn test_to_json_aliases() {
+        let ingredient = Ingredient::new_by_line("Zwiebel;0;-1;Zwiebeln,Zwibel");
+        assert_eq!(
+            ingredient.to_json(Locale::De),
+            "{\"name\": \"Zwiebel\", \"group\": \"vegetable\", \"group_label\": \"Gemüse\", \"store\": \"any\", \"store_label\": \"Überall\", \"aliases\": [\"Zwiebeln\", \"Zwibel\"]}"
+        );
+    }
+
+    #[test]
+    fn test_suggest() {
+        let mut all_ingredients: HashMap<String, Ingredient> = HashMap::new();
+        for line in &["Zwiebel;0;-1;Zwiebeln", "Zucchini;0;-1", "Käse;2;-1", "Pasta;7;-1"] {
+            let ingredient = Ingredient::new_by_line(line);
+            all_ingredients.insert(ingredient.name.to_string(), ingredient);
+        }
+
+        let names = |query: &str, limit: usize| -> Vec<String> {
+            Ingredient::suggest(&all_ingredients, query, limit)
+                .iter()
+                .map(|i| i.name.to_string())
+                .collect()
+        };
+        assert_eq!(names("z", 10), vec!["Zucchini", "Zwiebel"]);
+        assert_eq!(names("z", 1), vec!["Zucchini"]);
+        assert_eq!(names("zwiebeln", 10), vec!["Zwiebel"]);
+        assert_eq!(names("kase", 10), vec!["Käse"]);
+        assert_eq!(names("Paste", 10), vec!["Pasta"]);
+    }
+
+    #[test]
+    fn test_is_valid_name() {
+        assert!(Ingredient::is_valid_name("Crème fraîche"));
+        assert!(Ingredient::is_valid_name("Salz #1"));
+        assert!(!Ingredient::is_valid_name(""));
+        assert!(!Ingredient::is_valid_name("Salz;Pfeffer"));
+        assert!(!Ingredient::is_valid_name("Salz,Pfeffer"));
+        assert!(!Ingredient::is_valid_name("#vegan"));
+        assert!(!Ingredient::is_valid_name("@Bolognese"));
+        assert!(!Ingredient::is_valid_name("[Teig]"));
+    }
+
+    #[test]
+    fn test_identity_by_name() {
+        let mut list = HashMap::new();
+        let mut ingredient = Ingredient::new_by_name("Zwiebel".to_string());
+        list.insert(ingredient.clone(), 2);
+        ingredient.aliases.push("Zwiebeln".to_string());
+        ingredient.set_price(49);
+        assert!(ingredient == Ingredient::new_by_name("Zwiebel".to_string()));
+        assert_eq!(list.get(&ingredient), Some(&2));
+        assert!(ingredient != Ingredient::new_by_name("Zwiebeln".to_string()));
+    }
+
+    #[test]
+    fn test_find_similar() {
+        let mut all_ingredients: HashMap<String, Ingredient> = HashMap::new();
+        for line in &["Zwiebel;0;-1", "Käse;2;-1", "Mais;3;-1"] {
+            let ingredient = Ingredient::new_by_line(line);
+            all_ingredients.insert(ingredient.name.to_string(), ingredient);
+        }
+
+        let similar =
+            |name: &str| Ingredient::find_similar(&all_ingredients, name).map(|i| i.name.as_str());
+        assert_eq!(similar("Zwibel"), Some("Zwiebel"));
+        assert_eq!(similar("kase"), Some("Käse"));
+        assert_eq!(similar("Reis"), None);
+    }
+
+    #[test]
+    fn test_new_by_line_price() {
+        let ingredient = Ingredient::new_by_line("Butter;2;0;;199");
+        assert!(ingredient.aliases.is_empty());
+        assert_eq!(ingredient.price, Some(199));
+        assert!(ingredient.to_json(Locale::De).ends_with("\"aliases\": [], \"price\": 199}"));
+
+        let ingredient = Ingredient::new_by_line("Butter;2;0;;x");
+        assert_eq!(ingredient.price, None);
+    }
+
+    #[test]
+    fn test_new_by_line_aliases() {
+        let ingredient = Ingredient::new_by_line("Zwiebel;0;-1; Zwiebeln ,,Zwibel");
+        assert_eq!(ingredient.aliases, vec!["Zwiebeln", "Zwibel"]);
+
+        let ingredient = Ingredient::new_by_line("Zwiebel;0;-1;");
+        assert!(ingredient.aliases.is_empty());
+    }
+
+    #[test]
+    fn test_resolve() {
+        let mut all_ingredients: HashMap<String, Ingredient> = HashMap::new();
+        all_ingredients.insert(
+            "Zwiebel".to_string(),
+            Ingredient::new_by_line("Zwiebel;0;-1;Zwiebeln"),
         );
+        all_ingredients.insert(
+            "Margarine".to_string(),
+            Ingredient::new_by_line("Margarine;5;-1;Magarine"),
+        );
+
+        let resolve =
+            |name: &str| Ingredient::resolve(&all_ingredients, name).map(|i| i.name.as_str());
+        assert_eq!(resolve("Zwiebel"), Some("Zwiebel"));
+        assert_eq!(resolve("Zwiebeln"), Some("Zwiebel"));
+        assert_eq!(resolve("zwiebeln"), Some("Zwiebel"));
+        assert_eq!(resolve("Magarine"), Some("Margarine"));
+        assert_eq!(resolve("Butter"), None);
     }
 
     #[test]
@@ -263,11 +704,15 @@ mod tests {
             name: String::from("asd"),
             group: Group::Other,
             preferred_store: Store::Any,
+            aliases: Vec::new(),
+            price: None,
         };
         let i2 = Ingredient {
             name: String::from("asd"),
             group: Group::Other,
             preferred_store: Store::Any,
+            aliases: Vec::new(),
+            price: None,
         };
 
         assert_eq!(i1.cmp(&i2), Ordering::Equal);
@@ -279,11 +724,15 @@ mod tests {
             name: String::from("asd"),
             group: Group::Vegetable,
             preferred_store: Store::Any,
+            aliases: Vec::new(),
+            price: None,
         };
         let i2 = Ingredient {
             name: String::from("asd"),
             group: Group::Other,
             preferred_store: Store::Any,
+            aliases: Vec::new(),
+            price: None,
         };
 
         assert_eq!(i1.cmp(&i2), Ordering::Greater);
@@ -295,11 +744,15 @@ mod tests {
             name: String::from("asd"),
             group: Group::Vegetable,
             preferred_store: Store::Any,
+            aliases: Vec::new(),
+            price: None,
         };
         let i2 = Ingredient {
             name: String::from("asc"),
             group: Group::Vegetable,
             preferred_store: Store::Any,
+            aliases: Vec::new(),
+            price: None,
         };
 
         assert_eq!(i1.cmp(&i2), Ordering::Greater);
@@ -311,11 +764,15 @@ mod tests {
             name: String::from("asd"),
             group: Group::Vegetable,
             preferred_store: Store::Any,
+            aliases: Vec::new(),
+            price: None,
         };
         let i2 = Ingredient {
             name: String::from("asd"),
             group: Group::Vegetable,
             preferred_store: Store::DM,
+            aliases: Vec::new(),
+            price: None,
         };
 
         assert_eq!(i1.cmp(&i2), Ordering::Less);
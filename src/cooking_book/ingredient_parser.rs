@@ -0,0 +1,536 @@
+/// An ingredient parsed from free text like `200 g Mehl, gesiebt`.
+/// Without an amount, the amount is 0 and the unit is empty.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct ParsedIngredient {
+    pub amount: u16,
+    pub unit: String,
+    pub name: String,
+    pub note: String,
+}
+
+/// Words used for units in German and English with the unit they stand for
+/// and the factor to that unit.
+static UNIT_WORDS: [(&str, &str, f64); 65] = [
+    ("g", "G", 1.0),
+    ("gr", "G", 1.0),
+    ("gramm", "G", 1.0),
+    ("gram", "G", 1.0),
+    ("grams", "G", 1.0),
+    ("kg", "KG", 1.0),
+    ("kilo", "KG", 1.0),
+    ("kilogramm", "KG", 1.0),
+    ("kilogram", "KG", 1.0),
+    ("kilograms", "KG", 1.0),
+    ("oz", "G", 28.35),
+    ("ounce", "G", 28.35),
+    ("ounces", "G", 28.35),
+    ("lb", "G", 453.6),
+    ("lbs", "G", 453.6),
+    ("pound", "G", 453.6),
+    ("pounds", "G", 453.6),
+    ("ml", "ML", 1.0),
+    ("milliliter", "ML", 1.0),
+    ("milliliters", "ML", 1.0),
+    ("millilitre", "ML", 1.0),
+    ("millilitres", "ML", 1.0),
+    ("cl", "ML", 10.0),
+    ("dl", "ML", 100.0),
+    ("l", "L", 1.0),
+    ("liter", "L", 1.0),
+    ("liters", "L", 1.0),
+    ("litre", "L", 1.0),
+    ("litres", "L", 1.0),
+    ("tasse", "ML", 250.0),
+    ("tassen", "ML", 250.0),
+    ("cup", "ML", 240.0),
+    ("cups", "ML", 240.0),
+    ("el", "EL", 1.0),
+    ("essl", "EL", 1.0),
+    ("esslöffel", "EL", 1.0),
+    ("tbsp", "EL", 1.0),
+    ("tablespoon", "EL", 1.0),
+    ("tablespoons", "EL", 1.0),
+    ("tl", "TL", 1.0),
+    ("teel", "TL", 1.0),
+    ("teelöffel", "TL", 1.0),
+    ("tsp", "TL", 1.0),
+    ("teaspoon", "TL", 1.0),
+    ("teaspoons", "TL", 1.0),
+    ("prise", "Prise", 1.0),
+    ("prisen", "Prise", 1.0),
+    ("pinch", "Prise", 1.0),
+    ("stück", "Stück", 1.0),
+    ("stk", "Stück", 1.0),
+    ("piece", "Stück", 1.0),
+    ("pieces", "Stück", 1.0),
+    ("dose", "Dose", 1.0),
+    ("dosen", "Dose", 1.0),
+    ("can", "Dose", 1.0),
+    ("cans", "Dose", 1.0),
+    ("packung", "Packung", 1.0),
+    ("packungen", "Packung", 1.0),
+    ("pck", "Packung", 1.0),
+    ("pkg", "Packung", 1.0),
+    ("päckchen", "Packung", 1.0),
+    ("package", "Packung", 1.0),
+    ("bund", "Bund", 1.0),
+    ("bunch", "Bund", 1.0),
+    ("becher", "Becher", 1.0),
+];
+
+/// Words for one, used like `eine Prise Salz` or `a pinch of salt`.
+static ONE_WORDS: [&str; 7] = ["ein", "eine", "einen", "einem", "a", "an", "one"];
+
+/// Words before the amount which only say that it is approximate.
+static APPROXIMATE_WORDS: [&str; 7] = ["ca.", "ca", "circa", "etwa", "about", "approx.", "~"];
+
+/// Words instead of an amount, kept in the note.
+static VAGUE_WORDS: [&str; 3] = ["etwas", "wenig", "some"];
+
+/// Words describing the size or how full a spoon is, kept in the note.
+static SIZE_WORDS: [&str; 13] = [
+    "gestrichen",
+    "gehäuft",
+    "groß",
+    "klein",
+    "mittelgroß",
+    "frisch",
+    "level",
+    "heaped",
+    "heaping",
+    "large",
+    "small",
+    "medium",
+    "fresh",
+];
+
+/// Phrases at the end of an ingredient which are kept in the note.
+static NOTE_PHRASES: [&str; 12] = [
+    "nach geschmack",
+    "nach belieben",
+    "nach bedarf",
+    "zum bestreuen",
+    "zum braten",
+    "zum garnieren",
+    "zum servieren",
+    "to taste",
+    "as needed",
+    "for garnish",
+    "for serving",
+    "optional",
+];
+
+/// Parses an ingredient like `200 g Mehl`, `1/2 TL Salz`, `2-3 Eier`, `eine Prise Salz`
+/// or `2 cups of milk`. Units are mapped to the known units, fractions of KG and L
+/// are converted to G and ML, other fractions and ranges are rounded up.
+/// Text in parentheses, after a comma and phrases like `nach Geschmack` end up in the note.
+/// Returns `None` if there is no name.
+///
+/// #Arguments
+///
+/// * `text` The text to parse.
+pub fn parse(text: &str) -> Option<ParsedIngredient> {
+    let mut notes: Vec<String> = Vec::new();
+    let text = split_notes(text, &mut notes);
+
+    let mut words: Vec<&str> = text.split_whitespace().collect();
+    words.reverse();
+    while let Some(word) = words.last() {
+        if APPROXIMATE_WORDS.contains(&word.to_lowercase().as_str()) {
+            words.pop();
+        } else {
+            break;
+        }
+    }
+
+    let mut amount: Option<f64> = None;
+    if let Some((number, rest)) = words.last().and_then(|w| split_amount(*w)) {
+        words.pop();
+        amount = Some(number);
+        if !rest.is_empty() {
+            // the unit or name written directly after the amount like `200g`
+            words.push(rest);
+        } else {
+            amount = Some(number + parse_fraction_or_range(&mut words, number));
+        }
+    } else if words.len() > 1 {
+        let word = words.last().unwrap().to_lowercase();
+        if ONE_WORDS.contains(&word.as_str()) {
+            words.pop();
+            amount = Some(1.0);
+        } else if VAGUE_WORDS.contains(&word.as_str()) {
+            notes.insert(0, words.pop().unwrap().to_string());
+        }
+    }
+
+    let mut unit = "";
+    let mut factor = 1.0;
+    if amount.is_some() {
+        let mut sizes: Vec<&str> = Vec::new();
+        while words.len() > 1 && is_size_word(words.last().unwrap()) {
+            sizes.push(words.pop().unwrap());
+        }
+        if let Some((u, f)) = words.last().and_then(|w| lookup_unit(w)) {
+            unit = u;
+            factor = f;
+            words.pop();
+            if words.len() > 1 && words.last() == Some(&"of") {
+                words.pop();
+            }
+        }
+        if !sizes.is_empty() {
+            notes.insert(0, sizes.join(" "));
+        }
+    }
+
+    words.reverse();
+    let name = words.join(" ");
+    if name.is_empty() {
+        return None;
+    }
+
+    let mut amount = amount.unwrap_or(0.0) * factor;
+    if amount.fract() != 0.0 && (unit == "KG" || unit == "L") {
+        amount *= 1000.0;
+        unit = if unit == "KG" { "G" } else { "ML" };
+    }
+    let amount = if amount.ceil() > u16::max_value() as f64 {
+        u16::max_value()
+    } else {
+        amount.ceil() as u16
+    };
+
+    return Some(ParsedIngredient {
+        amount,
+        unit: unit.to_string(),
+        name,
+        note: notes.join(", "),
+    });
+}
+
+/// Parses a text which may name several ingredients without amount like
+/// `Salz und Pfeffer nach Geschmack`. The note applies to all of them.
+/// Texts with an amount are parsed as one ingredient.
+///
+/// #Arguments
+///
+/// * `text` The text to parse.
+pub fn parse_all(text: &str) -> Vec<ParsedIngredient> {
+    let parsed = match parse(text) {
+        Some(parsed) => parsed,
+        None => return Vec::new(),
+    };
+    if parsed.amount != 0 {
+        return vec![parsed];
+    }
+
+    let mut all: Vec<ParsedIngredient> = Vec::new();
+    for name in split_names(&parsed.name) {
+        all.push(ParsedIngredient {
+            amount: 0,
+            unit: String::new(),
+            name: name.to_string(),
+            note: parsed.note.to_string(),
+        });
+    }
+    return all;
+}
+
+/// Returns the unit for a word like `Esslöffel` or `kg` and the factor to that unit.
+///
+/// #Arguments
+///
+/// * `word` The word to look up, case and a trailing dot are ignored.
+pub fn lookup_unit(word: &str) -> Option<(&'static str, f64)> {
+    let word = word.to_lowercase();
+    let word = word.trim_end_matches('.');
+    return UNIT_WORDS
+        .iter()
+        .find(|(w, _u, _f)| *w == word)
+        .map(|(_w, u, f)| (*u, *f));
+}
+
+/// Removes the notes from the text: parentheses, anything after a comma
+/// and known phrases at the end.
+fn split_notes(text: &str, notes: &mut Vec<String>) -> String {
+    let mut text = text
+        .trim()
+        .trim_start_matches(|c| c == '-' || c == '*' || c == '•')
+        .to_string();
+
+    while let Some(start) = text.find('(') {
+        let end = match text[start..].find(')') {
+            Some(end) => start + end,
+            None => text.len() - 1,
+        };
+        let note = text[start + 1..=end]
+            .trim_end_matches(')')
+            .trim()
+            .to_string();
+        if !note.is_empty() {
+            notes.push(note);
+        }
+        text.replace_range(start..=end, " ");
+    }
+
+    let comma = text
+        .char_indices()
+        .find(|(i, c)| *c == ',' && !starts_with_amount(&text[i + 1..]))
+        .map(|(i, _c)| i);
+    if let Some(comma) = comma {
+        let note = text[comma + 1..].trim().to_string();
+        if !note.is_empty() {
+            notes.push(note);
+        }
+        text.truncate(comma);
+    }
+
+    loop {
+        let trimmed = text.trim_end().len();
+        let lower = text[..trimmed].to_lowercase();
+        let phrase = NOTE_PHRASES.iter().find(|p| {
+            lower.ends_with(*p)
+                && (lower.len() == p.len() || lower[..lower.len() - p.len()].ends_with(' '))
+        });
+        let start = match phrase {
+            Some(phrase) if trimmed >= phrase.len() => trimmed - phrase.len(),
+            _ => break,
+        };
+        if !text.is_char_boundary(start) {
+            break;
+        }
+        notes.insert(0, text[start..trimmed].to_string());
+        text.truncate(start);
+    }
+    return text;
+}
+
+/// Parses the rest of an amount after the first number, like the `1/2` of `1 1/2`
+/// or the `- 3` of `2 - 3`. Returns what to add to the first number.
+fn parse_fraction_or_range(words: &mut Vec<&str>, number: f64) -> f64 {
+    let next = match words.last() {
+        Some(next) => *next,
+        None => return 0.0,
+    };
+
+    if let Some((fraction, "")) = split_amount(next) {
+        if fraction < 1.0 {
+            words.pop();
+            return fraction;
+        }
+    }
+
+    let is_range_word = next == "-" || next == "–" || next == "bis" || next == "to";
+    if is_range_word && words.len() > 2 {
+        if let Some((upper, "")) = split_amount(words[words.len() - 2]) {
+            words.pop();
+            words.pop();
+            return (upper - number).max(0.0);
+        }
+    }
+    for separator in &['-', '–'] {
+        if next.starts_with(*separator) {
+            if let Some((upper, "")) = split_amount(&next[separator.len_utf8()..]) {
+                words.pop();
+                return (upper - number).max(0.0);
+            }
+        }
+    }
+    return 0.0;
+}
+
+fn is_size_word(word: &str) -> bool {
+    let word = word.to_lowercase();
+    return SIZE_WORDS.iter().any(|s| word.starts_with(s));
+}
+
+/// Splits names like `Salz und Pfeffer` or `salt & pepper`.
+fn split_names(name: &str) -> Vec<&str> {
+    let mut names: Vec<&str> = vec![name];
+    for separator in &[" und ", " and ", " & ", " oder ", " or "] {
+        names = names
+            .iter()
+            .flat_map(|n| n.split(separator))
+            .map(|n| n.trim())
+            .filter(|n| !n.is_empty())
+            .collect();
+    }
+    return names;
+}
+
+fn starts_with_amount(text: &str) -> bool {
+    return text.chars().next().map_or(false, |c| c.is_ascii_digit());
+}
+
+/// Splits a word like `200g`, `1,5`, `1/2`, `½` or `2-3` into the amount and the rest.
+/// Ranges are rounded to their upper end.
+fn split_amount(word: &str) -> Option<(f64, &str)> {
+    let end = word
+        .char_indices()
+        .find(|(_i, c)| !(c.is_ascii_digit() || *c == '.' || *c == ',' || *c == '/'))
+        .map_or(word.len(), |(i, _c)| i);
+    let (number, rest) = word.split_at(end);
+
+    let mut rest_chars = rest.chars();
+    let vulgar = match rest_chars.next() {
+        Some('½') => Some(0.5),
+        Some('¼') => Some(0.25),
+        Some('¾') => Some(0.75),
+        Some('⅓') => Some(1.0 / 3.0),
+        Some('⅔') => Some(2.0 / 3.0),
+        _ => None,
+    };
+    if let Some(vulgar) = vulgar {
+        let whole = if number.is_empty() {
+            0.0
+        } else {
+            number.parse::<f64>().ok()?
+        };
+        return Some((whole + vulgar, rest_chars.as_str()));
+    }
+
+    if number.is_empty() {
+        return None;
+    }
+    let amount = match number.find('/') {
+        Some(slash) => {
+            let numerator = number[..slash].parse::<f64>().ok()?;
+            let denominator = number[slash + 1..].parse::<f64>().ok()?;
+            if denominator == 0.0 {
+                return None;
+            }
+            numerator / denominator
+        }
+        None => number.replace(',', ".").parse::<f64>().ok()?,
+    };
+
+    for separator in &['-', '–'] {
+        if rest.starts_with(*separator) {
+            if let Some((upper, upper_rest)) = split_amount(&rest[separator.len_utf8()..]) {
+                return Some((amount.max(upper), upper_rest));
+            }
+        }
+    }
+    return Some((amount, rest));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{lookup_unit, parse, parse_all, ParsedIngredient};
+
+    fn parsed(amount: u16, unit: &str, name: &str, note: &str) -> Option<ParsedIngredient> {
+        return Some(ParsedIngredient {
+            amount,
+            unit: unit.to_string(),
+            name: name.to_string(),
+            note: note.to_string(),
+        });
+    }
+
+    #[test]
+    fn test_parse_german() {
+        assert_eq!(parse("200 g Mehl"), parsed(200, "G", "Mehl", ""));
+        assert_eq!(parse("200g Mehl"), parsed(200, "G", "Mehl", ""));
+        assert_eq!(parse("2 EL Olivenöl"), parsed(2, "EL", "Olivenöl", ""));
+        assert_eq!(parse("1/2 TL Salz"), parsed(1, "TL", "Salz", ""));
+        assert_eq!(parse("3 Eier"), parsed(3, "", "Eier", ""));
+        assert_eq!(parse("2 Liter Milch"), parsed(2, "L", "Milch", ""));
+        assert_eq!(
+            parse("1,5 kg Kartoffeln"),
+            parsed(1500, "G", "Kartoffeln", "")
+        );
+        assert_eq!(parse("1/2 l Milch"), parsed(500, "ML", "Milch", ""));
+        assert_eq!(parse("1 ½ EL Öl"), parsed(2, "EL", "Öl", ""));
+        assert_eq!(parse("½ Zitrone"), parsed(1, "", "Zitrone", ""));
+        assert_eq!(parse("2 cl Rum"), parsed(20, "ML", "Rum", ""));
+        assert_eq!(parse("1 Tasse Reis"), parsed(250, "ML", "Reis", ""));
+        assert_eq!(parse("eine Prise Salz"), parsed(1, "Prise", "Salz", ""));
+        assert_eq!(parse("ca. 100 g Zucker"), parsed(100, "G", "Zucker", ""));
+        assert_eq!(parse("2-3 Eier"), parsed(3, "", "Eier", ""));
+        assert_eq!(parse("2 bis 3 Tomaten"), parsed(3, "", "Tomaten", ""));
+        assert_eq!(parse("etwas Butter"), parsed(0, "", "Butter", "etwas"));
+        assert_eq!(
+            parse("2 Zwiebeln, fein gehackt"),
+            parsed(2, "", "Zwiebeln", "fein gehackt")
+        );
+        assert_eq!(
+            parse("1 Dose (400 g) Tomaten"),
+            parsed(1, "Dose", "Tomaten", "400 g")
+        );
+        assert_eq!(
+            parse("1 gehäufter TL Backpulver"),
+            parsed(1, "TL", "Backpulver", "gehäufter")
+        );
+        assert_eq!(
+            parse("2 große Zwiebeln"),
+            parsed(2, "", "Zwiebeln", "große")
+        );
+        assert_eq!(
+            parse("Petersilie zum Garnieren"),
+            parsed(0, "", "Petersilie", "zum Garnieren")
+        );
+        assert_eq!(parse("- 1 Bund Dill"), parsed(1, "Bund", "Dill", ""));
+        assert_eq!(parse("3Eier"), parsed(3, "", "Eier", ""));
+        assert_eq!(parse("Salz"), parsed(0, "", "Salz", ""));
+        assert_eq!(parse("Ei"), parsed(0, "", "Ei", ""));
+    }
+
+    #[test]
+    fn test_parse_english() {
+        assert_eq!(
+            parse("2 tablespoons olive oil"),
+            parsed(2, "EL", "olive oil", "")
+        );
+        assert_eq!(parse("1 tsp. salt"), parsed(1, "TL", "salt", ""));
+        assert_eq!(parse("2 cups of milk"), parsed(480, "ML", "milk", ""));
+        assert_eq!(parse("a pinch of salt"), parsed(1, "Prise", "salt", ""));
+        assert_eq!(parse("1 lb potatoes"), parsed(454, "G", "potatoes", ""));
+        assert_eq!(
+            parse("1 can (14 oz) tomatoes, drained"),
+            parsed(1, "Dose", "tomatoes", "14 oz, drained")
+        );
+        assert_eq!(parse("2 large eggs"), parsed(2, "", "eggs", "large"));
+        assert_eq!(
+            parse("pepper to taste"),
+            parsed(0, "", "pepper", "to taste")
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert_eq!(parse("200 g"), None);
+        assert_eq!(parse(""), None);
+        assert_eq!(parse("nach Geschmack"), None);
+        assert_eq!(parse("(optional)"), None);
+    }
+
+    #[test]
+    fn test_parse_all() {
+        assert_eq!(
+            parse_all("Salz und Pfeffer nach Geschmack"),
+            vec![
+                parsed(0, "", "Salz", "nach Geschmack").unwrap(),
+                parsed(0, "", "Pfeffer", "nach Geschmack").unwrap()
+            ]
+        );
+        assert_eq!(
+            parse_all("salt & pepper"),
+            vec![
+                parsed(0, "", "salt", "").unwrap(),
+                parsed(0, "", "pepper", "").unwrap()
+            ]
+        );
+        assert_eq!(
+            parse_all("200 g Mehl und Zucker"),
+            vec![parsed(200, "G", "Mehl und Zucker", "").unwrap()]
+        );
+        assert!(parse_all("").is_empty());
+    }
+
+    #[test]
+    fn test_lookup_unit() {
+        assert_eq!(lookup_unit("Esslöffel"), Some(("EL", 1.0)));
+        assert_eq!(lookup_unit("Stk."), Some(("Stück", 1.0)));
+        assert_eq!(lookup_unit("Zehe"), None);
+    }
+}
@@ -0,0 +1,265 @@
+/// The languages of labels shown to the user. Ids like `group.vegetable` stay the same in
+/// every language, so clients can rely on them while showing the localised label.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Locale {
+    De,
+    En,
+}
+
+/// The German labels, which are the default and the fallback for missing translations.
+static DE: &[(&str, &str)] = &[
+    ("group.vegetable", "Gemüse"),
+    ("group.fruit", "Obst"),
+    ("group.freezer", "Kühlung"),
+    ("group.conserved", "Konserve"),
+    ("group.beverage", "Getränk"),
+    ("group.baking", "Backzutat"),
+    ("group.pasta", "Nudeln"),
+    ("group.legume", "Hülsenfrüchte"),
+    ("group.spice", "Gewürz"),
+    ("group.snacks", "Knabberkram"),
+    ("group.sweets", "Süßigkeiten"),
+    ("group.other", "Anderes"),
+    ("store.rewe", "Rewe"),
+    ("store.dm", "DM"),
+    ("store.denz", "Denz"),
+    ("store.any", "Überall"),
+    ("slot.breakfast", "Frühstück"),
+    ("slot.lunch", "Mittagessen"),
+    ("slot.dinner", "Abendessen"),
+    ("difficulty.easy", "Einfach"),
+    ("difficulty.medium", "Mittel"),
+    ("difficulty.hard", "Schwer"),
+    ("label.shopping_list", "Einkaufsliste"),
+    ("label.nothing_to_buy", "Nichts zu kaufen."),
+    ("label.shopping", "Einkaufen"),
+    ("label.recipes", "Rezepte"),
+    ("label.recipe", "Rezept"),
+    ("label.ingredients", "Zutaten"),
+    ("label.preparation", "Zubereitung"),
+    ("label.equipment", "Ausstattung"),
+    ("label.notes", "Notizen"),
+    ("label.source", "Quelle"),
+    ("label.servings", "Portionen"),
+    ("label.prep_time", "Vorbereitung"),
+    ("label.cook_time", "Kochen"),
+    ("label.total_time", "Gesamt"),
+    ("label.difficulty", "Schwierigkeit"),
+    ("label.meal_plan", "Essensplan"),
+    ("label.offline", "Offline"),
+    ("label.name", "Name"),
+    ("label.group", "Gruppe"),
+    ("label.store", "Laden"),
+    ("label.aliases", "Aliase"),
+    ("label.time", "Zeit"),
+    ("label.tags", "Tags"),
+    ("label.part", "Teil"),
+    ("label.entries", "Einträge"),
+    ("label.user", "Benutzer"),
+    ("label.password", "Passwort"),
+    ("label.household", "Haushalt"),
+    ("label.login", "Anmelden"),
+    ("label.logout", "Abmelden"),
+];
+
+static EN: &[(&str, &str)] = &[
+    ("group.vegetable", "Vegetables"),
+    ("group.fruit", "Fruit"),
+    ("group.freezer", "Chilled"),
+    ("group.conserved", "Canned goods"),
+    ("group.beverage", "Beverages"),
+    ("group.baking", "Baking"),
+    ("group.pasta", "Pasta"),
+    ("group.legume", "Legumes"),
+    ("group.spice", "Spices"),
+    ("group.snacks", "Snacks"),
+    ("group.sweets", "Sweets"),
+    ("group.other", "Other"),
+    ("store.rewe", "Rewe"),
+    ("store.dm", "DM"),
+    ("store.denz", "Denz"),
+    ("store.any", "Anywhere"),
+    ("slot.breakfast", "Breakfast"),
+    ("slot.lunch", "Lunch"),
+    ("slot.dinner", "Dinner"),
+    ("difficulty.easy", "Easy"),
+    ("difficulty.medium", "Medium"),
+    ("difficulty.hard", "Hard"),
+    ("label.shopping_list", "Shopping list"),
+    ("label.nothing_to_buy", "Nothing to buy."),
+    ("label.shopping", "Shopping"),
+    ("label.recipes", "Recipes"),
+    ("label.recipe", "recipe"),
+    ("label.ingredients", "Ingredients"),
+    ("label.preparation", "Preparation"),
+    ("label.equipment", "Equipment"),
+    ("label.notes", "Notes"),
+    ("label.source", "Source"),
+    ("label.servings", "servings"),
+    ("label.prep_time", "Preparation"),
+    ("label.cook_time", "Cooking"),
+    ("label.total_time", "Total"),
+    ("label.difficulty", "Difficulty"),
+    ("label.meal_plan", "Meal plan"),
+    ("label.offline", "Offline"),
+    ("label.name", "Name"),
+    ("label.group", "Group"),
+    ("label.store", "Store"),
+    ("label.aliases", "Aliases"),
+    ("label.time", "Time"),
+    ("label.tags", "Tags"),
+    ("label.part", "Part"),
+    ("label.entries", "Entries"),
+    ("label.user", "User"),
+    ("label.password", "Password"),
+    ("label.household", "Household"),
+    ("label.login", "Log in"),
+    ("label.logout", "Log out"),
+];
+
+impl Locale {
+    /// Returns the locale for a language tag like `en`, `en-US` or `de_DE.UTF-8`,
+    /// `None` for an unsupported language.
+    ///
+    /// #Arguments
+    ///
+    /// * `tag` The language tag.
+    pub fn lookup_locale(tag: &str) -> Option<Locale> {
+        let language = tag
+            .trim()
+            .split(|c| c == '-' || c == '_' || c == '.')
+            .next()
+            .unwrap_or("");
+        return match language.to_lowercase().as_str() {
+            "de" => Some(Locale::De),
+            "en" => Some(Locale::En),
+            _ => None,
+        };
+    }
+
+    /// Returns the supported locale preferred by an `Accept-Language` header like
+    /// `fr-CH, fr;q=0.9, en;q=0.8`, `None` if no language is supported.
+    ///
+    /// #Arguments
+    ///
+    /// * `header` The value of the header.
+    pub fn from_accept_language(header: &str) -> Option<Locale> {
+        let mut best: Option<(Locale, f32)> = None;
+        for range in header.split(',') {
+            let mut parts = range.split(';');
+            let locale = match Locale::lookup_locale(parts.next().unwrap_or("")) {
+                Some(locale) => locale,
+                None => continue,
+            };
+            let quality = parts
+                .filter_map(|p| {
+                    let p = p.trim();
+                    if p.starts_with("q=") {
+                        return p[2..].parse::<f32>().ok();
+                    }
+                    return None;
+                })
+                .next()
+                .unwrap_or(1.0);
+            if quality > 0.0 && best.map_or(true, |(_l, q)| quality > q) {
+                best = Some((locale, quality));
+            }
+        }
+        return best.map(|(locale, _q)| locale);
+    }
+
+    /// Returns the language code like `de`.
+    pub fn code(&self) -> &'static str {
+        return match self {
+            Locale::De => "de",
+            Locale::En => "en",
+        };
+    }
+
+    /// Returns the label for a message id like `group.vegetable`. Missing translations
+    /// fall back to German, unknown ids to an empty label.
+    ///
+    /// #Arguments
+    ///
+    /// * `id` The id of the message.
+    pub fn text(&self, id: &str) -> &'static str {
+        let find = |catalog: &[(&'static str, &'static str)]| {
+            return catalog.iter().find(|(i, _t)| *i == id).map(|(_i, t)| *t);
+        };
+        let text = match self {
+            Locale::De => None,
+            Locale::En => find(EN),
+        };
+        return text.or_else(|| find(DE)).unwrap_or("");
+    }
+
+    /// Export all labels of the locale to JSON, for the web interface.
+    pub fn catalog_to_json(&self) -> String {
+        let mut json: String = String::new();
+        json.push_str(&format!(
+            "{{\"locale\": \"{}\", \"messages\": {{",
+            self.code()
+        ));
+
+        let mut is_first: bool = true;
+        for (id, _text) in DE {
+            if !is_first {
+                json.push_str(", ");
+            }
+            json.push_str(&format!("\"{}\": \"{}\"", id, self.text(id)));
+            is_first = false;
+        }
+
+        json.push_str("}}");
+        return json;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Locale, DE, EN};
+
+    #[test]
+    fn test_catalogs_complete() {
+        let ids = |catalog: &[(&str, &str)]| -> Vec<String> {
+            let mut ids: Vec<String> = catalog.iter().map(|(i, _t)| i.to_string()).collect();
+            ids.sort();
+            return ids;
+        };
+        assert_eq!(ids(DE), ids(EN));
+        assert!(EN.iter().all(|(_i, t)| !t.is_empty()));
+    }
+
+    #[test]
+    fn test_lookup_locale() {
+        assert_eq!(Locale::lookup_locale("en-US"), Some(Locale::En));
+        assert_eq!(Locale::lookup_locale("de_DE.UTF-8"), Some(Locale::De));
+        assert_eq!(Locale::lookup_locale("DE"), Some(Locale::De));
+        assert_eq!(Locale::lookup_locale("fr"), None);
+        assert_eq!(Locale::lookup_locale(""), None);
+    }
+
+    #[test]
+    fn test_from_accept_language() {
+        assert_eq!(
+            Locale::from_accept_language("fr-CH, fr;q=0.9, en;q=0.8, de;q=0.7, *;q=0.5"),
+            Some(Locale::En)
+        );
+        assert_eq!(
+            Locale::from_accept_language("en;q=0.5, de-AT"),
+            Some(Locale::De)
+        );
+        assert_eq!(Locale::from_accept_language("en;q=0, fr"), None);
+        assert_eq!(Locale::from_accept_language(""), None);
+    }
+
+    #[test]
+    fn test_text() {
+        assert_eq!(Locale::De.text("group.freezer"), "Kühlung");
+        assert_eq!(Locale::En.text("group.freezer"), "Chilled");
+        assert_eq!(Locale::En.text("unknown"), "");
+        assert!(Locale::En.catalog_to_json().starts_with(
+            "{\"locale\": \"en\", \"messages\": {\"group.vegetable\": \"Vegetables\", "
+        ));
+    }
+}
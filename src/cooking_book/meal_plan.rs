@@ -0,0 +1,214 @@
+use self::Slot::*;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::slice::Iter;
+
+use crate::cooking_book::date::Date;
+use crate::cooking_book::locale::Locale;
+
+/// The meals of a day.
+#[derive(Eq, Ord, PartialEq, PartialOrd, Clone, Debug, Copy, Hash)]
+pub enum Slot {
+    Breakfast = 0,
+    Lunch = 1,
+    Dinner = 2,
+}
+
+impl Slot {
+    pub fn get_slot_iterator() -> Iter<'static, Slot> {
+        static SLOTS: [Slot; 3] = [Breakfast, Lunch, Dinner];
+        SLOTS.iter()
+    }
+
+    /// Export all meals of a day with their ids, numbers and labels to JSON.
+    ///
+    /// #Arguments
+    /// * `locale` The language of the labels.
+    pub fn all_as_json(locale: Locale) -> String {
+        let mut json = String::new();
+        json.push_str("{\"slots\": [");
+
+        let mut is_first: bool = true;
+        for slot in Slot::get_slot_iterator() {
+            if !is_first {
+                json.push_str(", ");
+            }
+            json.push_str(&format!(
+                "{{\"id\": \"{}\", \"number\": {}, \"label\": \"{}\"}}",
+                slot.id(),
+                *slot as u8,
+                slot.label(locale)
+            ));
+            is_first = false;
+        }
+        json.push_str("]}");
+
+        return json;
+    }
+
+    /// Returns the stable id of the meal like `lunch`, which doesn't depend on the language.
+    pub fn id(&self) -> &'static str {
+        return match *self {
+            Slot::Breakfast => "breakfast",
+            Slot::Lunch => "lunch",
+            Slot::Dinner => "dinner",
+        };
+    }
+
+    /// Returns the name of the meal shown to the user.
+    ///
+    /// #Arguments
+    /// * `locale` The language of the name.
+    pub fn label(&self, locale: Locale) -> &'static str {
+        return locale.text(&format!("slot.{}", self.id()));
+    }
+
+    /// Returns the decoded Slot, `None` for an unknown number.
+    ///
+    /// #Arguments
+    /// * `number` The encoded slot.
+    pub fn lookup_slot_number(number: usize) -> Option<Slot> {
+        return match number {
+            0 => Some(Slot::Breakfast),
+            1 => Some(Slot::Lunch),
+            2 => Some(Slot::Dinner),
+            _ => None,
+        };
+    }
+}
+
+impl fmt::Display for Slot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.label(Locale::De))
+    }
+}
+
+/// The recipes planned for the meals of some days.
+#[derive(PartialEq, Eq)]
+pub struct MealPlan {
+    pub meals: BTreeMap<(Date, Slot), String>,
+}
+
+impl MealPlan {
+    pub fn new() -> MealPlan {
+        let meals: BTreeMap<(Date, Slot), String> = BTreeMap::new();
+        return MealPlan { meals };
+    }
+
+    /// Plan a recipe for a meal, replacing the recipe planned before.
+    ///
+    /// #Arguments
+    ///
+    /// * `date` The day of the meal.
+    /// * `slot` The meal of the day.
+    /// * `recipe` The name of the recipe.
+    pub fn plan(&mut self, date: Date, slot: Slot, recipe: &str) {
+        self.meals.insert((date, slot), recipe.to_string());
+    }
+
+    /// Remove the recipe planned for a meal.
+    ///
+    /// #Arguments
+    ///
+    /// * `date` The day of the meal.
+    /// * `slot` The meal of the day.
+    pub fn remove(&mut self, date: Date, slot: Slot) {
+        self.meals.remove(&(date, slot));
+    }
+
+    /// Returns the names of the recipes planned between two days, both included.
+    /// A recipe planned twice is returned twice.
+    ///
+    /// #Arguments
+    ///
+    /// * `from` The first day.
+    /// * `to` The last day.
+    pub fn get_recipe_names(&self, from: Date, to: Date) -> Vec<&String> {
+        return self
+            .meals
+            .iter()
+            .filter(|((date, _slot), _recipe)| *date >= from && *date <= to)
+            .map(|(_key, recipe)| recipe)
+            .collect();
+    }
+
+    /// Exports the meal plan to JSON, sorted by day and meal.
+    ///
+    /// #Arguments
+    ///
+    /// * `locale` The language of the names of the meals.
+    pub fn to_json(&self, locale: Locale) -> String {
+        let mut json: String = String::new();
+        json.push('[');
+
+        let mut is_first: bool = true;
+        for ((date, slot), recipe) in &self.meals {
+            if !is_first {
+                json.push_str(", ");
+            }
+            json.push_str(&format!(
+                "{{\"date\": \"{}\", \"slot\": {}, \"slot_id\": \"{}\", \"slot_name\": \"{}\", \"recipe\": \"{}\"}}",
+                date,
+                *slot as u8,
+                slot.id(),
+                slot.label(locale),
+                recipe
+            ));
+            is_first = false;
+        }
+
+        json.push(']');
+        return json;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MealPlan, Slot};
+    use crate::cooking_book::date::Date;
+    use crate::cooking_book::locale::Locale;
+
+    #[test]
+    fn test_lookup_slot() {
+        assert_eq!(Slot::lookup_slot_number(0), Some(Slot::Breakfast));
+        assert_eq!(Slot::lookup_slot_number(1), Some(Slot::Lunch));
+        assert_eq!(Slot::lookup_slot_number(2), Some(Slot::Dinner));
+        assert_eq!(Slot::lookup_slot_number(3), None);
+    }
+
+    #[test]
+    fn test_get_recipe_names() {
+        let monday = Date::new(2019, 4, 1).unwrap();
+        let mut meal_plan = MealPlan::new();
+        meal_plan.plan(monday, Slot::Dinner, "Pfannkuchen");
+        meal_plan.plan(monday, Slot::Breakfast, "Waffel");
+        meal_plan.plan(monday.add_days(1), Slot::Lunch, "Pfannkuchen");
+        meal_plan.plan(monday.add_days(7), Slot::Lunch, "Suppe");
+
+        assert_eq!(
+            meal_plan.get_recipe_names(monday, monday.add_days(6)),
+            vec!["Waffel", "Pfannkuchen", "Pfannkuchen"]
+        );
+
+        meal_plan.remove(monday, Slot::Breakfast);
+        assert_eq!(
+            meal_plan.get_recipe_names(monday, monday),
+            vec!["Pfannkuchen"]
+        );
+    }
+
+    #[test]
+    fn test_to_json() {
+        let mut meal_plan = MealPlan::new();
+        meal_plan.plan(Date::new(2019, 4, 1).unwrap(), Slot::Lunch, "Suppe");
+
+        assert_eq!(
+            meal_plan.to_json(Locale::De),
+            "[{\"date\": \"2019-04-01\", \"slot\": 1, \"slot_id\": \"lunch\", \"slot_name\": \"Mittagessen\", \"recipe\": \"Suppe\"}]"
+        );
+        assert_eq!(
+            Slot::all_as_json(Locale::En),
+            "{\"slots\": [{\"id\": \"breakfast\", \"number\": 0, \"label\": \"Breakfast\"}, {\"id\": \"lunch\", \"number\": 1, \"label\": \"Lunch\"}, {\"id\": \"dinner\", \"number\": 2, \"label\": \"Dinner\"}]}"
+        );
+    }
+}
@@ -0,0 +1,446 @@
+use std::cmp;
+use std::collections::HashMap;
+
+use crate::cooking_book::date::Date;
+use crate::cooking_book::ingredient::Ingredient;
+use crate::cooking_book::recipe::Recipe;
+use crate::cooking_book::unit;
+
+/// The amount of an ingredient at home.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct PantryItem {
+    pub amount: u16,
+    pub unit: String,
+    pub best_before: Option<Date>,
+}
+
+/// The ingredients at home.
+#[derive(PartialEq, Eq)]
+pub struct Pantry {
+    pub stock: HashMap<Ingredient, PantryItem>,
+}
+
+impl Pantry {
+    pub fn new() -> Pantry {
+        let stock: HashMap<Ingredient, PantryItem> = HashMap::new();
+        return Pantry { stock };
+    }
+
+    /// Set the amount of an ingredient, replacing what was there before.
+    ///
+    /// #Arguments
+    ///
+    /// * `ingredient` The ingredient.
+    /// * `amount` The amount at home.
+    /// * `unit` The unit of the amount.
+    /// * `best_before` The date until which the ingredient should be used, if known.
+    pub fn set(
+        &mut self,
+        ingredient: Ingredient,
+        amount: u16,
+        unit: &str,
+        best_before: Option<Date>,
+    ) {
+        self.remove(&ingredient.name);
+        if amount > 0 {
+            self.stock.insert(
+                ingredient,
+                PantryItem {
+                    amount,
+                    unit: unit.to_string(),
+                    best_before,
+                },
+            );
+        }
+    }
+
+    /// Add an amount of an ingredient. If the ingredient is stored in another unit of the same kind,
+    /// both are converted to the smaller unit and the earlier best before date is kept.
+    /// Otherwise the stored amount is replaced.
+    ///
+    /// #Arguments
+    ///
+    /// * `ingredient` The ingredient.
+    /// * `amount` The amount to add.
+    /// * `unit` The unit of the amount.
+    /// * `best_before` The date until which the added amount should be used, if known.
+    pub fn add(
+        &mut self,
+        ingredient: Ingredient,
+        amount: u16,
+        unit: &str,
+        best_before: Option<Date>,
+    ) {
+        if let Some(item) = self.get_mut(&ingredient.name) {
+            if let Some((stored, added, unit)) = in_smaller_unit(item, amount, unit) {
+                item.amount = clamp(stored + added);
+                item.unit = unit;
+                item.best_before = match (item.best_before, best_before) {
+                    (Some(a), Some(b)) => Some(cmp::min(a, b)),
+                    (a, b) => a.or(b),
+                };
+                return;
+            }
+        }
+        self.set(ingredient, amount, unit, best_before);
+    }
+
+    /// Take an amount of an ingredient. The ingredient is removed once nothing is left.
+    /// Amounts in units which can't be converted are ignored.
+    ///
+    /// #Arguments
+    ///
+    /// * `name` The name of the ingredient.
+    /// * `amount` The amount to take.
+    /// * `unit` The unit of the amount.
+    pub fn take(&mut self, name: &str, amount: u16, unit: &str) {
+        let is_empty = match self.get_mut(name) {
+            Some(item) => match in_smaller_unit(item, amount, unit) {
+                Some((stored, taken, unit)) => {
+                    item.amount = clamp(stored.saturating_sub(taken));
+                    item.unit = unit;
+                    item.amount == 0
+                }
+                None => false,
+            },
+            None => false,
+        };
+        if is_empty {
+            self.remove(name);
+        }
+    }
+
+    /// Take the ingredients of a recipe.
+    ///
+    /// #Arguments
+    ///
+    /// * `recipe` The recipe which was cooked.
+    pub fn cook(&mut self, recipe: &Recipe) {
+        for line in &recipe.ingredients {
+            self.take(&line.ingredient.name, line.amount, &line.unit);
+        }
+    }
+
+    /// Remove an ingredient.
+    ///
+    /// #Arguments
+    ///
+    /// * `name` The name of the ingredient.
+    pub fn remove(&mut self, name: &str) {
+        let ingredient = self.stock.keys().find(|i| i.name == name).cloned();
+        if let Some(ingredient) = ingredient {
+            self.stock.remove(&ingredient);
+        }
+    }
+
+    /// Returns how much of an amount is missing at home, in the unit of the amount.
+    /// If the ingredient is stored in a unit which can't be converted, everything is missing.
+    ///
+    /// #Arguments
+    ///
+    /// * `name` The name of the ingredient.
+    /// * `amount` The amount needed.
+    /// * `unit` The unit of the amount.
+    pub fn shortfall(&self, name: &str, amount: u16, unit: &str) -> u16 {
+        let item = match self.stock.iter().find(|(i, _item)| i.name == name) {
+            Some((_i, item)) => item,
+            None => return amount,
+        };
+        return match in_smaller_unit(item, amount, unit) {
+            Some((stored, needed, smaller)) => {
+                let missing = needed.saturating_sub(stored);
+                clamp(unit::convert(missing, &smaller, unit).unwrap_or(missing))
+            }
+            None => amount,
+        };
+    }
+
+    /// Returns the ingredients which should be used until the given day, including expired ones.
+    /// The ingredients are sorted by their best before date and name.
+    ///
+    /// #Arguments
+    ///
+    /// * `until` The last day to include.
+    pub fn get_expiring(&self, until: Date) -> Vec<(&Ingredient, &PantryItem)> {
+        let mut expiring: Vec<(&Ingredient, &PantryItem)> = self
+            .stock
+            .iter()
+            .filter(|(_i, item)| match item.best_before {
+                Some(date) => date <= until,
+                None => false,
+            })
+            .collect();
+        expiring.sort_by(|a, b| {
+            a.1.best_before
+                .cmp(&b.1.best_before)
+                .then_with(|| a.0.name.cmp(&b.0.name))
+        });
+        return expiring;
+    }
+
+    /// Returns the recipes which use ingredients expiring until the given day, together with
+    /// those ingredients. Recipes using more of them come first, then the ones using the
+    /// ingredient expiring first, then by name.
+    ///
+    /// #Arguments
+    ///
+    /// * `recipes` The recipes to choose from.
+    /// * `until` The last day to include.
+    pub fn get_recipes_using_up<'a>(
+        &'a self,
+        recipes: &'a HashMap<String, Recipe>,
+        until: Date,
+    ) -> Vec<(&'a Recipe, Vec<(&'a Ingredient, &'a PantryItem)>)> {
+        let expiring = self.get_expiring(until);
+
+        let mut result: Vec<(&'a Recipe, Vec<(&'a Ingredient, &'a PantryItem)>)> = Vec::new();
+        for recipe in recipes.values() {
+            // keeps the order of `expiring`, so the first one expires first
+            let used: Vec<(&Ingredient, &PantryItem)> = expiring
+                .iter()
+                .filter(|(i, _item)| recipe.contains_ingredient(&i.name))
+                .cloned()
+                .collect();
+            if !used.is_empty() {
+                result.push((recipe, used));
+            }
+        }
+
+        result.sort_by(|a, b| {
+            b.1.len()
+                .cmp(&a.1.len())
+                .then_with(|| a.1[0].1.best_before.cmp(&b.1[0].1.best_before))
+                .then_with(|| a.0.name.cmp(&b.0.name))
+        });
+        return result;
+    }
+
+    fn get_mut(&mut self, name: &str) -> Option<&mut PantryItem> {
+        return self
+            .stock
+            .iter_mut()
+            .find(|(i, _item)| i.name == name)
+            .map(|(_i, item)| item);
+    }
+
+    /// Exports the pantry to JSON, sorted by name.
+    pub fn to_json(&self) -> String {
+        let mut items: Vec<(&Ingredient, &PantryItem)> = self.stock.iter().collect();
+        items.sort_by(|a, b| a.0.name.cmp(&b.0.name));
+        return Pantry::items_to_json(&items, None);
+    }
+
+    /// Exports pantry items to JSON.
+    ///
+    /// #Arguments
+    ///
+    /// * `items` The items to export.
+    /// * `today` If given, the days left until the best before date are added.
+    pub fn items_to_json(items: &Vec<(&Ingredient, &PantryItem)>, today: Option<Date>) -> String {
+        let mut json: String = String::new();
+        json.push('[');
+
+        let mut is_first: bool = true;
+        for (i, item) in items {
+            if !is_first {
+                json.push_str(", ");
+            }
+            json.push_str(&format!(
+                "{{\"name\": \"{}\", \"amount\": {}, \"unit\": \"{}\"",
+                i.name, item.amount, item.unit
+            ));
+            if let Some(best_before) = item.best_before {
+                json.push_str(&format!(", \"best_before\": \"{}\"", best_before));
+                if let Some(today) = today {
+                    json.push_str(&format!(
+                        ", \"days_left\": {}",
+                        today.days_until(&best_before)
+                    ));
+                }
+            }
+            json.push('}');
+            is_first = false;
+        }
+
+        json.push(']');
+        return json;
+    }
+
+    /// Exports recipes with the expiring ingredients they use up to JSON.
+    ///
+    /// #Arguments
+    ///
+    /// * `recipes` The recipes with the ingredients they use up.
+    /// * `today` The day to count the days left from.
+    pub fn using_up_to_json(
+        recipes: &Vec<(&Recipe, Vec<(&Ingredient, &PantryItem)>)>,
+        today: Date,
+    ) -> String {
+        let mut json: String = String::new();
+        json.push('[');
+
+        let mut is_first: bool = true;
+        for (recipe, used) in recipes {
+            if !is_first {
+                json.push_str(", ");
+            }
+            json.push_str(&format!(
+                "{{\"name\": \"{}\", \"expiring\": {}}}",
+                recipe.name,
+                Pantry::items_to_json(used, Some(today))
+            ));
+            is_first = false;
+        }
+
+        json.push(']');
+        return json;
+    }
+}
+
+/// Returns the stored amount and another amount converted to the smaller of both units,
+/// together with that unit. Returns `None` if the units can't be converted.
+fn in_smaller_unit(item: &PantryItem, amount: u16, unit: &str) -> Option<(u32, u32, String)> {
+    let smaller = unit::smaller_unit(&item.unit, unit);
+    let stored = unit::convert(item.amount as u32, &item.unit, smaller)?;
+    let amount = unit::convert(amount as u32, unit, smaller)?;
+    return Some((stored, amount, smaller.to_string()));
+}
+
+fn clamp(amount: u32) -> u16 {
+    return cmp::min(amount, u16::max_value() as u32) as u16;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Pantry;
+    use crate::cooking_book::date::Date;
+    use crate::cooking_book::ingredient::Ingredient;
+    use crate::cooking_book::recipe::Recipe;
+    use std::collections::HashMap;
+
+    fn get_mock() -> Pantry {
+        let mut pantry = Pantry::new();
+        pantry.set(Ingredient::new_by_name("Mehl".to_string()), 1, "KG", None);
+        pantry.set(
+            Ingredient::new_by_name("Milch".to_string()),
+            100,
+            "ML",
+            None,
+        );
+        return pantry;
+    }
+
+    #[test]
+    fn test_add() {
+        let mut pantry = get_mock();
+        pantry.add(Ingredient::new_by_name("Mehl".to_string()), 500, "G", None);
+        pantry.add(Ingredient::new_by_name("Ei".to_string()), 6, "Stück", None);
+        pantry.add(
+            Ingredient::new_by_name("Milch".to_string()),
+            2,
+            "Packung",
+            None,
+        );
+
+        assert_eq!(
+            pantry.to_json(),
+            "[{\"name\": \"Ei\", \"amount\": 6, \"unit\": \"Stück\"}, {\"name\": \"Mehl\", \"amount\": 1500, \"unit\": \"G\"}, {\"name\": \"Milch\", \"amount\": 2, \"unit\": \"Packung\"}]"
+        );
+    }
+
+    #[test]
+    fn test_take() {
+        let mut pantry = get_mock();
+        pantry.take("Mehl", 300, "EL");
+        assert_eq!(pantry.shortfall("Mehl", 1, "KG"), 0);
+        pantry.take("Mehl", 300, "G");
+        assert_eq!(pantry.shortfall("Mehl", 1, "KG"), 1);
+        assert_eq!(pantry.shortfall("Mehl", 800, "G"), 100);
+
+        pantry.take("Milch", 150, "ML");
+        assert_eq!(pantry.stock.len(), 1);
+
+        pantry.take("Ei", 1, "");
+        assert_eq!(pantry.stock.len(), 1);
+    }
+
+    #[test]
+    fn test_shortfall() {
+        let pantry = get_mock();
+        assert_eq!(pantry.shortfall("Mehl", 300, "G"), 0);
+        assert_eq!(pantry.shortfall("Mehl", 1200, "G"), 200);
+        assert_eq!(pantry.shortfall("Milch", 150, "ML"), 50);
+        assert_eq!(pantry.shortfall("Milch", 2, "EL"), 2);
+        assert_eq!(pantry.shortfall("Ei", 2, "Stück"), 2);
+    }
+
+    #[test]
+    fn test_cook() {
+        let all_ingredients: HashMap<String, Ingredient> = HashMap::new();
+        let recipe = Recipe::new_by_line("Pfannkuchen;Mehl,300,G;Milch,150,ML", &all_ingredients);
+
+        let mut pantry = get_mock();
+        pantry.cook(&recipe);
+        assert_eq!(
+            pantry.to_json(),
+            "[{\"name\": \"Mehl\", \"amount\": 700, \"unit\": \"G\"}]"
+        );
+    }
+
+    #[test]
+    fn test_add_best_before() {
+        let mut pantry = Pantry::new();
+        let mehl = Ingredient::new_by_name("Mehl".to_string());
+        pantry.add(mehl.clone(), 500, "G", None);
+        pantry.add(mehl.clone(), 1, "KG", Date::new(2019, 6, 1));
+        pantry.add(mehl.clone(), 200, "G", Date::new(2019, 7, 1));
+        assert_eq!(pantry.stock[&mehl].amount, 1700);
+        assert_eq!(pantry.stock[&mehl].best_before, Date::new(2019, 6, 1));
+    }
+
+    #[test]
+    fn test_expiring() {
+        let mut pantry = get_mock();
+        pantry.set(
+            Ingredient::new_by_name("Spinat".to_string()),
+            1,
+            "Packung",
+            Date::new(2019, 5, 3),
+        );
+        pantry.set(
+            Ingredient::new_by_name("Sahne".to_string()),
+            200,
+            "ML",
+            Date::new(2019, 5, 1),
+        );
+        pantry.set(
+            Ingredient::new_by_name("Eis".to_string()),
+            1,
+            "Packung",
+            Date::new(2019, 9, 1),
+        );
+
+        let today = Date::new(2019, 5, 2).unwrap();
+        let expiring = pantry.get_expiring(today.add_days(3));
+        assert_eq!(
+            Pantry::items_to_json(&expiring, Some(today)),
+            "[{\"name\": \"Sahne\", \"amount\": 200, \"unit\": \"ML\", \"best_before\": \"2019-05-01\", \"days_left\": -1}, {\"name\": \"Spinat\", \"amount\": 1, \"unit\": \"Packung\", \"best_before\": \"2019-05-03\", \"days_left\": 1}]"
+        );
+
+        let all_ingredients: HashMap<String, Ingredient> = HashMap::new();
+        let mut recipes: HashMap<String, Recipe> = HashMap::new();
+        for line in &[
+            "Pfannkuchen;Mehl,300,G;Milch,150,ML",
+            "Spinatpfanne;Spinat,1,Packung",
+            "Auflauf;Spinat,1,Packung;Sahne,200,ML",
+            "Rahmsuppe;Sahne,200,ML",
+        ] {
+            let recipe = Recipe::new_by_line(line, &all_ingredients);
+            recipes.insert(recipe.name.clone(), recipe);
+        }
+
+        let using_up = pantry.get_recipes_using_up(&recipes, today.add_days(3));
+        let names: Vec<&str> = using_up.iter().map(|(r, _used)| r.name.as_str()).collect();
+        assert_eq!(names, vec!["Auflauf", "Rahmsuppe", "Spinatpfanne"]);
+    }
+}
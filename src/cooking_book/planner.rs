@@ -0,0 +1,356 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::cooking_book::date::Date;
+use crate::cooking_book::history::History;
+use crate::cooking_book::meal_plan::{MealPlan, Slot};
+use crate::cooking_book::recipe::Recipe;
+
+/// A small pseudo random number generator (SplitMix64).
+/// The same seed always produces the same numbers, so suggestions can be reproduced.
+pub struct Random {
+    state: u64,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Random {
+        return Random { state: seed };
+    }
+
+    /// Returns the next pseudo random number.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        return z ^ (z >> 31);
+    }
+
+    /// Returns a pseudo random number from 0 to `max` (exclusive).
+    ///
+    /// #Arguments
+    ///
+    /// * `max` The upper bound, must be greater than 0.
+    pub fn below(&mut self, max: usize) -> usize {
+        return (self.next_u64() % max as u64) as usize;
+    }
+}
+
+/// The rules for suggesting recipes.
+pub struct Constraints {
+    /// The slots to fill.
+    pub slots: Vec<Slot>,
+    /// Per slot, the recipes need at least one of these tags. No tags allow any recipe.
+    pub slot_tags: HashMap<Slot, Vec<String>>,
+    /// A recipe is not suggested if it is cooked or planned less than this many days apart.
+    pub no_repeat_days: u16,
+    /// The maximum price in cent of all meals planned in a week from Monday to Sunday.
+    /// Only ingredients with a known price are counted.
+    pub max_weekly_budget: Option<u32>,
+}
+
+impl Constraints {
+    /// Returns the default constraints: all slots, `#Breakfast` recipes in the morning
+    /// and no repeat within a week.
+    pub fn new() -> Constraints {
+        let mut slot_tags: HashMap<Slot, Vec<String>> = HashMap::new();
+        slot_tags.insert(Slot::Breakfast, vec!["#Breakfast".to_string()]);
+        return Constraints {
+            slots: Slot::get_slot_iterator().cloned().collect(),
+            slot_tags,
+            no_repeat_days: 7,
+            max_weekly_budget: None,
+        };
+    }
+}
+
+/// Suggests recipes for the empty slots of the meal plan between two days.
+/// Recipes sharing the most ingredients with the other meals of the week are preferred,
+/// so fewer packages are opened. Among equally good recipes one is picked by the seeded random
+/// number generator. Slots without a recipe matching the constraints stay empty.
+/// Returns only the suggested meals.
+///
+/// #Arguments
+///
+/// * `meal_plan` The current meal plan.
+/// * `recipes` The recipes to choose from.
+/// * `history` The cooking history, to avoid repeating recently cooked recipes.
+/// * `from` The first day to fill.
+/// * `to` The last day to fill.
+/// * `constraints` The rules for the suggestions.
+/// * `seed` The seed of the random number generator.
+pub fn suggest(
+    meal_plan: &MealPlan,
+    recipes: &HashMap<String, Recipe>,
+    history: &History,
+    from: Date,
+    to: Date,
+    constraints: &Constraints,
+    seed: u64,
+) -> MealPlan {
+    let mut random = Random::new(seed);
+    let mut suggestions = MealPlan::new();
+
+    // sorted, as the order of the map is not deterministic
+    let mut candidates: Vec<&Recipe> = recipes.values().collect();
+    candidates.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut date = from;
+    while date <= to {
+        for slot in Slot::get_slot_iterator() {
+            if !constraints.slots.contains(slot) || meal_plan.meals.contains_key(&(date, *slot)) {
+                continue;
+            }
+
+            let week_start = date.add_days(-(date.weekday() as i64));
+            let week_end = week_start.add_days(6);
+            let week: Vec<&str> = meal_plan
+                .get_recipe_names(week_start, week_end)
+                .into_iter()
+                .chain(suggestions.get_recipe_names(week_start, week_end))
+                .map(|r| r.as_str())
+                .collect();
+            let week_cost: u32 = week
+                .iter()
+                .filter_map(|r| recipes.get(*r))
+                .map(|r| get_cost(r, recipes))
+                .sum();
+            let week_ingredients: HashSet<&str> = week
+                .iter()
+                .filter_map(|r| recipes.get(*r))
+                .flat_map(|r| r.get_ingredients().into_iter().map(|i| i.name.as_str()))
+                .collect();
+
+            let mut best: Vec<&Recipe> = Vec::new();
+            let mut best_score: usize = 0;
+            for recipe in &candidates {
+                if !has_slot_tag(recipe, constraints.slot_tags.get(slot))
+                    || is_repeated(recipe, date, meal_plan, &suggestions, history, constraints)
+                {
+                    continue;
+                }
+                if let Some(budget) = constraints.max_weekly_budget {
+                    if week_cost + get_cost(recipe, recipes) > budget {
+                        continue;
+                    }
+                }
+
+                let score = recipe
+                    .get_ingredients()
+                    .iter()
+                    .filter(|i| week_ingredients.contains(i.name.as_str()))
+                    .count();
+                if best.is_empty() || score > best_score {
+                    best.clear();
+                    best_score = score;
+                }
+                if score == best_score {
+                    best.push(recipe);
+                }
+            }
+
+            if !best.is_empty() {
+                let recipe = best[random.below(best.len())];
+                suggestions.plan(date, *slot, &recipe.name);
+            }
+        }
+        date = date.add_days(1);
+    }
+
+    return suggestions;
+}
+
+/// Returns the price in cent of the ingredients of a recipe and its sub recipes with known price.
+/// If the sub recipes can't be expanded, only the own ingredients are counted.
+///
+/// #Arguments
+///
+/// * `recipe` The recipe.
+/// * `all_recipes` All recipes, to look up the sub recipes.
+pub fn get_cost(recipe: &Recipe, all_recipes: &HashMap<String, Recipe>) -> u32 {
+    return match recipe.expand(all_recipes) {
+        Ok(expanded) => expanded
+            .get_ingredients()
+            .iter()
+            .filter_map(|i| i.price)
+            .sum(),
+        Err(_) => recipe
+            .get_ingredients()
+            .iter()
+            .filter_map(|i| i.price)
+            .sum(),
+    };
+}
+
+fn has_slot_tag(recipe: &Recipe, tags: Option<&Vec<String>>) -> bool {
+    return match tags {
+        Some(tags) if !tags.is_empty() => tags.iter().any(|t| recipe.tags.contains(t)),
+        _ => true,
+    };
+}
+
+fn is_repeated(
+    recipe: &Recipe,
+    date: Date,
+    meal_plan: &MealPlan,
+    suggestions: &MealPlan,
+    history: &History,
+    constraints: &Constraints,
+) -> bool {
+    let days = constraints.no_repeat_days as i64;
+    if days == 0 {
+        return false;
+    }
+    let is_near = |other: &Date| date.days_until(other).abs() < days;
+
+    let is_planned = |plan: &MealPlan| {
+        plan.meals
+            .iter()
+            .any(|((d, _slot), r)| *r == recipe.name && is_near(d))
+    };
+    return is_planned(meal_plan)
+        || is_planned(suggestions)
+        || history
+            .get_entries(Some(&recipe.name))
+            .iter()
+            .any(|e| is_near(&e.date));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{suggest, Constraints, Random};
+    use crate::cooking_book::date::Date;
+    use crate::cooking_book::history::History;
+    use crate::cooking_book::ingredient::Ingredient;
+    use crate::cooking_book::meal_plan::{MealPlan, Slot};
+    use crate::cooking_book::recipe::Recipe;
+    use std::collections::HashMap;
+
+    fn get_recipes() -> HashMap<String, Recipe> {
+        let mut all_ingredients: HashMap<String, Ingredient> = HashMap::new();
+        let mut sahne = Ingredient::new_by_name("Sahne".to_string());
+        sahne.price = Some(150);
+        all_ingredients.insert(sahne.name.clone(), sahne);
+
+        let mut recipes: HashMap<String, Recipe> = HashMap::new();
+        for line in &[
+            "Pfannkuchen;Mehl,300,G;Milch,150,ML;#Breakfast",
+            "Müsli;Haferflocken,50,G;Milch,150,ML;#Breakfast",
+            "Suppe;Karotte,3,Stück;Sahne,100,ML",
+            "Auflauf;Kartoffel,500,G;Sahne,100,ML",
+            "Salat;Gurke,1,Stück",
+        ] {
+            let recipe = Recipe::new_by_line(line, &all_ingredients);
+            recipes.insert(recipe.name.clone(), recipe);
+        }
+        return recipes;
+    }
+
+    #[test]
+    fn test_random() {
+        let mut a = Random::new(42);
+        let mut b = Random::new(42);
+        let mut c = Random::new(43);
+        let first = a.next_u64();
+        assert_eq!(first, b.next_u64());
+        assert_ne!(first, c.next_u64());
+        for _ in 0..100 {
+            assert!(a.below(3) < 3);
+        }
+    }
+
+    #[test]
+    fn test_suggest_is_deterministic() {
+        let recipes = get_recipes();
+        let monday = Date::new(2019, 4, 1).unwrap();
+        let constraints = Constraints::new();
+
+        let first = suggest(
+            &MealPlan::new(),
+            &recipes,
+            &History::new(),
+            monday,
+            monday.add_days(1),
+            &constraints,
+            7,
+        );
+        let second = suggest(
+            &MealPlan::new(),
+            &recipes,
+            &History::new(),
+            monday,
+            monday.add_days(1),
+            &constraints,
+            7,
+        );
+        assert!(first == second);
+    }
+
+    #[test]
+    fn test_suggest_constraints() {
+        let recipes = get_recipes();
+        let monday = Date::new(2019, 4, 1).unwrap();
+        let mut meal_plan = MealPlan::new();
+        meal_plan.plan(monday, Slot::Lunch, "Suppe");
+
+        let mut history = History::new();
+        let _ = history.record(monday.add_days(-1), "Salat", None, "");
+
+        let mut constraints = Constraints::new();
+        constraints.slots = vec![Slot::Breakfast];
+        let suggestions = suggest(
+            &meal_plan,
+            &recipes,
+            &history,
+            monday,
+            monday.add_days(2),
+            &constraints,
+            1,
+        );
+        let mut breakfasts: Vec<&String> = suggestions.meals.values().collect();
+        breakfasts.sort();
+        assert_eq!(breakfasts, vec!["Müsli", "Pfannkuchen"]);
+
+        constraints.slots = vec![Slot::Dinner];
+        let suggestions = suggest(
+            &meal_plan,
+            &recipes,
+            &history,
+            monday,
+            monday,
+            &constraints,
+            1,
+        );
+        // shares the cream with the soup, the salad was eaten yesterday
+        assert_eq!(
+            suggestions.meals.get(&(monday, Slot::Dinner)),
+            Some(&"Auflauf".to_string())
+        );
+    }
+
+    #[test]
+    fn test_suggest_budget() {
+        let recipes = get_recipes();
+        let monday = Date::new(2019, 4, 1).unwrap();
+        let mut meal_plan = MealPlan::new();
+        meal_plan.plan(monday, Slot::Lunch, "Suppe");
+        meal_plan.plan(monday.add_days(1), Slot::Breakfast, "Müsli");
+        meal_plan.plan(monday.add_days(1), Slot::Lunch, "Pfannkuchen");
+
+        let mut constraints = Constraints::new();
+        constraints.slots = vec![Slot::Dinner];
+        constraints.max_weekly_budget = Some(200);
+        let suggestions = suggest(
+            &meal_plan,
+            &recipes,
+            &History::new(),
+            monday,
+            monday,
+            &constraints,
+            1,
+        );
+        assert_eq!(
+            suggestions.meals.get(&(monday, Slot::Dinner)),
+            Some(&"Salat".to_string())
+        );
+    }
+}
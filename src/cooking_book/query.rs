@@ -0,0 +1,431 @@
+use std::fmt;
+
+use crate::cooking_book::recipe::Recipe;
+use crate::cooking_book::search;
+
+/// A parsed recipe query like `#Breakfast AND (#vegan OR #vegetarian) AND NOT Milch`.
+///
+/// Terms are `tag:<tag>` or `#<tag>`, `ingredient:<name>` or just `<name>` and `name:<text>`.
+/// Values with spaces can be quoted. `NOT` (or `!`) binds stronger than `AND`, which binds
+/// stronger than `OR`. Terms without operator in between are combined with `AND`.
+#[derive(PartialEq, Eq, Debug)]
+pub enum Query {
+    Tag(String),
+    Ingredient(String),
+    Name(String),
+    Not(Box<Query>),
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+}
+
+/// An error while parsing a query at a 1-based character position.
+#[derive(PartialEq, Eq, Debug)]
+pub struct ParseError {
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "position {}: {}", self.position, &self.message)
+    }
+}
+
+impl ParseError {
+    /// Export this error to JSON.
+    pub fn to_json(&self) -> String {
+        return format!(
+            "{{\"position\": {}, \"message\": \"{}\"}}",
+            self.position,
+            self.message.replace('"', "'")
+        );
+    }
+}
+
+/// The most parentheses and `NOT` nested in each other.
+const MAX_DEPTH: usize = 32;
+/// The most terms and operators of a query, which also limits the depth of the parsed query.
+const MAX_TOKENS: usize = 256;
+
+#[derive(PartialEq, Eq, Debug)]
+enum Token {
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+    Word(String),
+}
+
+impl Query {
+    /// Parses a query.
+    ///
+    /// #Arguments
+    ///
+    /// * `input` The query to parse.
+    pub fn parse(input: &str) -> Result<Query, ParseError> {
+        let tokens = tokenize(input)?;
+        if let Some((position, _token)) = tokens.get(MAX_TOKENS) {
+            return Err(ParseError {
+                position: *position,
+                message: format!("more than {} terms and operators", MAX_TOKENS),
+            });
+        }
+        let end = input.chars().count() + 1;
+        let mut parser = Parser {
+            tokens,
+            current: 0,
+            end,
+            depth: 0,
+        };
+
+        let query = parser.parse_or()?;
+        if let Some((position, _token)) = parser.tokens.get(parser.current) {
+            return Err(ParseError {
+                position: *position,
+                message: "unexpected ')'".to_string(),
+            });
+        }
+        return Ok(query);
+    }
+
+    /// Returns whether the recipe matches this query.
+    /// Tags and ingredients are compared ignoring case, ingredients match by alias too.
+    ///
+    /// #Arguments
+    ///
+    /// * `recipe` The recipe to check.
+    pub fn matches(&self, recipe: &Recipe) -> bool {
+        return match self {
+            Query::Tag(tag) => {
+                let tag = search::normalize(tag);
+                recipe
+                    .tags
+                    .iter()
+                    .any(|t| search::normalize(t.trim_start_matches('#')) == tag)
+            }
+            Query::Ingredient(name) => {
+                let normalized = search::normalize(name);
+                recipe
+                    .get_ingredients()
+                    .iter()
+                    .any(|i| i.is_called(name) || search::normalize(&i.name) == normalized)
+            }
+            Query::Name(name) => search::score(name, &recipe.name).is_some(),
+            Query::Not(query) => !query.matches(recipe),
+            Query::And(left, right) => left.matches(recipe) && right.matches(recipe),
+            Query::Or(left, right) => left.matches(recipe) || right.matches(recipe),
+        };
+    }
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    current: usize,
+    end: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        return self.tokens.get(self.current).map(|(_p, t)| t);
+    }
+
+    fn position(&self) -> usize {
+        return match self.tokens.get(self.current) {
+            Some((position, _token)) => *position,
+            None => self.end,
+        };
+    }
+
+    fn parse_or(&mut self) -> Result<Query, ParseError> {
+        let mut query = self.parse_and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.current += 1;
+            let right = self.parse_and()?;
+            query = Query::Or(Box::new(query), Box::new(right));
+        }
+        return Ok(query);
+    }
+
+    fn parse_and(&mut self) -> Result<Query, ParseError> {
+        let mut query = self.parse_unary()?;
+        loop {
+            match self.peek() {
+                Some(Token::And) => self.current += 1,
+                Some(Token::Not) | Some(Token::Open) | Some(Token::Word(_)) => {}
+                _ => break,
+            }
+            let right = self.parse_unary()?;
+            query = Query::And(Box::new(query), Box::new(right));
+        }
+        return Ok(query);
+    }
+
+    fn parse_unary(&mut self) -> Result<Query, ParseError> {
+        let position = self.position();
+        let token = match self.tokens.get(self.current) {
+            Some((_p, token)) => token,
+            None => {
+                return Err(ParseError {
+                    position,
+                    message: "unexpected end of query".to_string(),
+                })
+            }
+        };
+
+        let is_nested = *token == Token::Not || *token == Token::Open;
+        if is_nested && self.depth >= MAX_DEPTH {
+            return Err(ParseError {
+                position,
+                message: "nested too deeply".to_string(),
+            });
+        }
+
+        match token {
+            Token::Not => {
+                self.current += 1;
+                self.depth += 1;
+                let query = self.parse_unary()?;
+                self.depth -= 1;
+                return Ok(Query::Not(Box::new(query)));
+            }
+            Token::Open => {
+                self.current += 1;
+                self.depth += 1;
+                let query = self.parse_or()?;
+                self.depth -= 1;
+                if self.peek() != Some(&Token::Close) {
+                    return Err(ParseError {
+                        position: self.position(),
+                        message: format!("missing ')' for '(' at position {}", position),
+                    });
+                }
+                self.current += 1;
+                return Ok(query);
+            }
+            Token::Word(word) => {
+                let query = parse_term(word, position)?;
+                self.current += 1;
+                return Ok(query);
+            }
+            Token::And | Token::Or | Token::Close => {
+                return Err(ParseError {
+                    position,
+                    message: "expected a term".to_string(),
+                });
+            }
+        }
+    }
+}
+
+fn parse_term(word: &str, position: usize) -> Result<Query, ParseError> {
+    if word.starts_with('#') {
+        return non_empty(&word[1..], position).map(Query::Tag);
+    }
+
+    let separator = match word.find(':') {
+        Some(separator) => separator,
+        None => return Ok(Query::Ingredient(word.to_string())),
+    };
+    let value = non_empty(&word[separator + 1..], position)?;
+    return match &word[..separator] {
+        "tag" => Ok(Query::Tag(value.trim_start_matches('#').to_string())),
+        "ingredient" => Ok(Query::Ingredient(value)),
+        "name" => Ok(Query::Name(value)),
+        prefix => Err(ParseError {
+            position,
+            message: format!("unknown prefix '{}:'", prefix),
+        }),
+    };
+}
+
+fn non_empty(value: &str, position: usize) -> Result<String, ParseError> {
+    if value.trim().is_empty() {
+        return Err(ParseError {
+            position,
+            message: "missing value".to_string(),
+        });
+    }
+    return Ok(value.to_string());
+}
+
+fn tokenize(input: &str) -> Result<Vec<(usize, Token)>, ParseError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens: Vec<(usize, Token)> = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let position = i + 1;
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        match c {
+            '(' => {
+                tokens.push((position, Token::Open));
+                i += 1;
+            }
+            ')' => {
+                tokens.push((position, Token::Close));
+                i += 1;
+            }
+            '!' => {
+                tokens.push((position, Token::Not));
+                i += 1;
+            }
+            _ => {
+                let mut word = String::new();
+                while i < chars.len()
+                    && !chars[i].is_whitespace()
+                    && chars[i] != '('
+                    && chars[i] != ')'
+                {
+                    if chars[i] == '"' {
+                        let quote = i + 1;
+                        i += 1;
+                        while i < chars.len() && chars[i] != '"' {
+                            word.push(chars[i]);
+                            i += 1;
+                        }
+                        if i == chars.len() {
+                            return Err(ParseError {
+                                position: quote,
+                                message: "unterminated quote".to_string(),
+                            });
+                        }
+                    } else {
+                        word.push(chars[i]);
+                    }
+                    i += 1;
+                }
+
+                let token = match word.to_uppercase().as_str() {
+                    "AND" => Token::And,
+                    "OR" => Token::Or,
+                    "NOT" => Token::Not,
+                    _ => Token::Word(word),
+                };
+                tokens.push((position, token));
+            }
+        }
+    }
+    return Ok(tokens);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ParseError, Query};
+    use crate::cooking_book::ingredient::Ingredient;
+    use crate::cooking_book::recipe::Recipe;
+    use std::collections::HashMap;
+
+    fn tag(t: &str) -> Box<Query> {
+        return Box::new(Query::Tag(t.to_string()));
+    }
+
+    fn ingredient(i: &str) -> Box<Query> {
+        return Box::new(Query::Ingredient(i.to_string()));
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            Query::parse("#Breakfast AND (#vegan OR tag:vegetarian) AND NOT Milch").unwrap(),
+            Query::And(
+                Box::new(Query::And(
+                    tag("Breakfast"),
+                    Box::new(Query::Or(tag("vegan"), tag("vegetarian")))
+                )),
+                Box::new(Query::Not(ingredient("Milch")))
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_precedence() {
+        assert_eq!(
+            Query::parse("a OR b c").unwrap(),
+            Query::Or(
+                ingredient("a"),
+                Box::new(Query::And(ingredient("b"), ingredient("c")))
+            )
+        );
+        assert_eq!(
+            Query::parse("!a and name:\"Pfann kuchen\"").unwrap(),
+            Query::And(
+                Box::new(Query::Not(ingredient("a"))),
+                Box::new(Query::Name("Pfann kuchen".to_string()))
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |input: &str| Query::parse(input).unwrap_err();
+        assert_eq!(
+            error("#a AND"),
+            ParseError {
+                position: 7,
+                message: "unexpected end of query".to_string()
+            }
+        );
+        assert_eq!(error("(#a OR #b").position, 10);
+        assert_eq!(error("#a)").position, 3);
+        assert_eq!(error("#a OR OR #b").position, 7);
+        assert_eq!(error("#a foo:b").message, "unknown prefix 'foo:'");
+        assert_eq!(error("name:\"Pfann").position, 6);
+        assert_eq!(error("tag:").message, "missing value");
+        assert_eq!(
+            format!("{}", error("")),
+            "position 1: unexpected end of query"
+        );
+        assert_eq!(
+            error(&"(".repeat(30_000)),
+            ParseError {
+                position: 257,
+                message: "more than 256 terms and operators".to_string()
+            }
+        );
+        assert_eq!(
+            error(&format!("{}#a{}", "(".repeat(40), ")".repeat(40))),
+            ParseError {
+                position: 33,
+                message: "nested too deeply".to_string()
+            }
+        );
+        assert_eq!(error(&"NOT ".repeat(40)).message, "nested too deeply");
+        assert!(Query::parse(&format!("{}#a{}", "(".repeat(32), ")".repeat(32))).is_ok());
+        assert!(Query::parse(&vec!["#a"; 256].join(" ")).is_ok());
+    }
+
+    #[test]
+    fn test_matches() {
+        let mut all_ingredients: HashMap<String, Ingredient> = HashMap::new();
+        all_ingredients.insert(
+            "Milch".to_string(),
+            Ingredient::new_by_line("Milch;2;-1;Kuhmilch"),
+        );
+        let pancake = Recipe::new_by_line(
+            "Pfannkuchen;Mehl,300,G;Milch,150,ML;#Breakfast;#vegetarian",
+            &all_ingredients,
+        );
+        let porridge = Recipe::new_by_line(
+            "Porridge;Haferflocken,50,G;Hafermilch,200,ML;#Breakfast;#vegan",
+            &all_ingredients,
+        );
+
+        let query = Query::parse("#breakfast AND (#vegan OR #vegetarian) AND NOT Milch").unwrap();
+        assert!(!query.matches(&pancake));
+        assert!(query.matches(&porridge));
+
+        let query = Query::parse("ingredient:kuhmilch OR name:porridge").unwrap();
+        assert!(query.matches(&pancake));
+        assert!(query.matches(&porridge));
+
+        let query = Query::parse("name:pfannkuchen !#vegan").unwrap();
+        assert!(query.matches(&pancake));
+        assert!(!query.matches(&porridge));
+    }
+}
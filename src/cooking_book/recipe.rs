@@ -1,30 +1,117 @@
 use crate::cooking_book::ingredient::Ingredient;
-use crate::file_access::persistency;
+use crate::cooking_book::locale::Locale;
+use crate::cooking_book::query::Query;
+use crate::cooking_book::recipe_details::RecipeDetails;
+use crate::cooking_book::search;
+use crate::cooking_book::unit;
+use crate::file_access::json::Json;
 use std::collections::HashMap;
 use std::collections::HashSet;
 
+/// An ingredient of a recipe with amount and unit.
+/// The section groups the ingredients of a recipe, like "Für den Teig". It is empty for none.
+/// The note describes the preparation, like "fein gehackt". It is empty for none.
+#[derive(PartialEq, Eq, Clone)]
+pub struct IngredientLine {
+    pub ingredient: Ingredient,
+    pub amount: u16,
+    pub unit: String,
+    pub section: String,
+    pub note: String,
+}
+
+impl IngredientLine {
+    pub fn new(ingredient: Ingredient, amount: u16, unit: &str, section: &str) -> IngredientLine {
+        return IngredientLine {
+            ingredient,
+            amount,
+            unit: unit.to_string(),
+            section: section.to_string(),
+            note: String::new(),
+        };
+    }
+
+    /// Export the ingredient line to JSON. The section and note are only exported if set.
+    pub fn to_json(&self) -> String {
+        let mut json: String = String::new();
+        json.push('{');
+        json.push_str("\"name\": \"");
+        json.push_str(&Json::escape(&self.ingredient.name));
+        json.push_str("\", ");
+
+        json.push_str("\"amount\": ");
+        json.push_str(&format!("{}", &self.amount));
+        json.push_str(", ");
+
+        json.push_str("\"unit\": \"");
+        json.push_str(&Json::escape(&self.unit));
+        json.push_str("\"");
+
+        if !self.section.is_empty() {
+            json.push_str(", \"section\": \"");
+            json.push_str(&Json::escape(&self.section));
+            json.push_str("\"");
+        }
+
+        if !self.note.is_empty() {
+            json.push_str(", \"note\": \"");
+            json.push_str(&Json::escape(&self.note));
+            json.push_str("\"");
+        }
+
+        json.push('}');
+        return json;
+    }
+}
+
 /// A recipe containing of ingredients with amount and units and some tags.
-#[derive(PartialEq, Eq)]
+/// The ingredients keep the order of the recipes file. A section like `[Für den Teig]`
+/// applies to the following ingredients, `[]` ends it. The same ingredient may be used
+/// in several sections. An ingredient is written as `name,amount,unit` with an optional note.
+/// Other recipes can be used as sub recipes with a share like 1/2, written as `@name,1/2`.
+#[derive(PartialEq, Eq, Clone)]
 pub struct Recipe {
     pub name: String,
-    pub ingredients: HashMap<Ingredient, (u16, String)>,
+    pub ingredients: Vec<IngredientLine>,
+    pub sub_recipes: HashMap<String, (u16, u16)>,
     pub tags: HashSet<String>,
+    pub details: RecipeDetails,
 }
 
 impl Recipe {
-    pub fn new_by_line(line: &str) -> Recipe {
+    /// Create a recipe from a line of the recipes file. Aliases are resolved to their ingredient.
+    /// Ingredients missing in the catalog are kept with default group and store, but not persisted.
+    ///
+    /// #Arguments
+    ///
+    /// * `line` The line to parse.
+    /// * `all_ingredients` The collection of all ingredients.
+    pub fn new_by_line(line: &str, all_ingredients: &HashMap<String, Ingredient>) -> Recipe {
         let mut values = line.split(';');
         let name = String::from(values.next().unwrap());
 
-        let mut all_ingredients = persistency::load_ingredients();
-        let mut ingredients: HashMap<Ingredient, (u16, String)> = HashMap::new();
+        let mut ingredients: Vec<IngredientLine> = Vec::new();
+        let mut sub_recipes: HashMap<String, (u16, u16)> = HashMap::new();
         let mut tags: HashSet<String> = HashSet::new();
+        let mut section = "";
 
         for s in values {
             if s.starts_with('#') {
                 tags.insert(s.to_string());
                 continue;
             }
+            if s.starts_with('[') && s.ends_with(']') {
+                section = s[1..s.len() - 1].trim();
+                continue;
+            }
+            if s.starts_with('@') {
+                let mut recipe_share = s[1..].split(',');
+                let name = recipe_share.next().unwrap().to_string();
+                let share =
+                    Recipe::parse_share(recipe_share.next().unwrap_or("")).unwrap_or((1, 1));
+                sub_recipes.insert(name, share);
+                continue;
+            }
 
             let mut ingre_amount = s.split(',');
             let name = ingre_amount.next().unwrap().to_string();
@@ -42,72 +129,416 @@ impl Recipe {
                 None => "",
             };
 
-            if !all_ingredients.contains_key(&name) {
-                Ingredient::persist_new_ingredient(&name, &mut all_ingredients)
-                    .unwrap_or_else(|e| eprintln!("{}", e));
-            }
+            let ingredient = match Ingredient::resolve(all_ingredients, &name) {
+                Some(ingredient) => ingredient.clone(),
+                None => Ingredient::new_by_name(name),
+            };
 
-            ingredients.insert(
-                all_ingredients.get(&name).unwrap().clone(),
-                (amount, String::from(unit)),
-            );
+            let mut ingredient = IngredientLine::new(ingredient, amount, unit, section);
+            if let Some(note) = ingre_amount.next() {
+                ingredient.note = note.to_string();
+            }
+            ingredients.push(ingredient);
         }
 
         return Recipe {
             name,
             ingredients,
+            sub_recipes,
             tags,
+            details: RecipeDetails::new(),
+        };
+    }
+
+    /// Parses the share of a sub recipe like `1/2` or `2`. An empty share is the whole recipe.
+    /// Returns `None` for invalid or zero shares.
+    ///
+    /// #Arguments
+    ///
+    /// * `text` The share to parse.
+    pub fn parse_share(text: &str) -> Option<(u16, u16)> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Some((1, 1));
+        }
+        let mut parts = text.split('/');
+        let numerator = parts.next()?.trim().parse::<u16>().ok()?;
+        let denominator = match parts.next() {
+            Some(d) => d.trim().parse::<u16>().ok()?,
+            None => 1,
+        };
+        if parts.next().is_some() || numerator == 0 || denominator == 0 {
+            return None;
+        }
+        return Some((numerator, denominator));
+    }
+
+    /// Formats the share of a sub recipe like `1/2`, see `parse_share`.
+    ///
+    /// #Arguments
+    ///
+    /// * `share` The share to format.
+    pub fn share_to_string(share: &(u16, u16)) -> String {
+        return match share {
+            (numerator, 1) => numerator.to_string(),
+            (numerator, denominator) => format!("{}/{}", numerator, denominator),
+        };
+    }
+
+    /// Returns the distinct ingredients of the recipe in order of their first use.
+    pub fn get_ingredients(&self) -> Vec<&Ingredient> {
+        let mut ingredients: Vec<&Ingredient> = Vec::with_capacity(self.ingredients.len());
+        for line in &self.ingredients {
+            if !ingredients.iter().any(|i| i.name == line.ingredient.name) {
+                ingredients.push(&line.ingredient);
+            }
+        }
+        return ingredients;
+    }
+
+    /// Returns whether the recipe uses an ingredient in any section.
+    ///
+    /// #Arguments
+    ///
+    /// * `name` The name of the ingredient.
+    pub fn contains_ingredient(&self, name: &str) -> bool {
+        return self.ingredients.iter().any(|l| l.ingredient.name == name);
+    }
+
+    /// Returns a copy of the recipe with all sub recipes replaced by their base ingredients,
+    /// scaled by their share and rounded up. Amounts of the same ingredient are summed up,
+    /// converted to the smaller unit if necessary. Amounts in units which can't be converted
+    /// stay separate lines. The expanded recipe has no sections.
+    /// Fails if a sub recipe is unknown or a recipe references itself.
+    ///
+    /// #Arguments
+    ///
+    /// * `all_recipes` All recipes, to look up the sub recipes.
+    pub fn expand(&self, all_recipes: &HashMap<String, Recipe>) -> Result<Recipe, String> {
+        let mut ingredients: Vec<IngredientLine> = Vec::new();
+        let mut path: Vec<&str> = Vec::new();
+        self.expand_into(all_recipes, (1, 1), &mut path, &mut ingredients)?;
+        return Ok(Recipe {
+            name: self.name.to_string(),
+            ingredients,
+            sub_recipes: HashMap::new(),
+            tags: self.tags.clone(),
+            details: self.details.clone(),
+        });
+    }
+
+    fn expand_into<'a>(
+        &'a self,
+        all_recipes: &'a HashMap<String, Recipe>,
+        share: (u64, u64),
+        path: &mut Vec<&'a str>,
+        ingredients: &mut Vec<IngredientLine>,
+    ) -> Result<(), String> {
+        if path.contains(&self.name.as_str()) {
+            path.push(&self.name);
+            return Err(format!(
+                "Recipe '{}' references itself: {}",
+                self.name,
+                path.join(" -> ")
+            ));
+        }
+        path.push(&self.name);
+
+        for line in &self.ingredients {
+            let amount = (line.amount as u64 * share.0 + share.1 - 1) / share.1;
+            let mut is_merged = false;
+            for existing in ingredients
+                .iter_mut()
+                .filter(|e| e.ingredient.name == line.ingredient.name)
+            {
+                let smaller = unit::smaller_unit(&existing.unit, &line.unit);
+                let sum =
+                    unit::convert(existing.amount as u32, &existing.unit, smaller).and_then(|e| {
+                        unit::convert(amount as u32, &line.unit, smaller)
+                            .map(|a| e as u64 + a as u64)
+                    });
+                if let Some(sum) = sum {
+                    existing.amount = Recipe::limit_amount(sum);
+                    existing.unit = smaller.to_string();
+                    is_merged = true;
+                    break;
+                }
+            }
+            if !is_merged {
+                ingredients.push(IngredientLine::new(
+                    line.ingredient.clone(),
+                    Recipe::limit_amount(amount),
+                    &line.unit,
+                    "",
+                ));
+            }
+        }
+
+        // sorted, so errors are reported deterministically
+        let mut names: Vec<&String> = self.sub_recipes.keys().collect();
+        names.sort();
+        for name in names {
+            let sub_recipe = match all_recipes.get(name) {
+                Some(sub_recipe) => sub_recipe,
+                None => {
+                    return Err(format!(
+                        "Recipe '{}' uses unknown recipe '{}'",
+                        self.name, name
+                    ))
+                }
+            };
+            let (numerator, denominator) = self.sub_recipes[name];
+            sub_recipe.expand_into(
+                all_recipes,
+                (share.0 * numerator as u64, share.1 * denominator as u64),
+                path,
+                ingredients,
+            )?;
+        }
+
+        path.pop();
+        return Ok(());
+    }
+
+    fn limit_amount(amount: u64) -> u16 {
+        if amount > u16::max_value() as u64 {
+            return u16::max_value();
+        }
+        return amount as u16;
+    }
+
+    /// Export the recipe to a line of the recipes file.
+    /// Ingredients keep their order, sub recipes and tags are sorted by name.
+    pub fn to_line(&self) -> String {
+        let mut line = self.name.to_string();
+
+        let mut section = "";
+        for i in &self.ingredients {
+            if i.section != section {
+                section = &i.section;
+                line.push_str(&format!(";[{}]", section));
+            }
+            line.push_str(&format!(";{},{},{}", i.ingredient.name, i.amount, i.unit));
+            if !i.note.is_empty() {
+                line.push(',');
+                line.push_str(&i.note);
+            }
+        }
+
+        let mut sub_recipes: Vec<(&String, &(u16, u16))> = self.sub_recipes.iter().collect();
+        sub_recipes.sort();
+        for (name, share) in sub_recipes {
+            line.push_str(&format!(";@{},{}", name, Recipe::share_to_string(share)));
+        }
+
+        let mut tags: Vec<&String> = self.tags.iter().collect();
+        tags.sort();
+        for tag in tags {
+            line.push(';');
+            line.push_str(tag);
+        }
+        return line;
+    }
+
+    /// Replace an ingredient by another one. If a section already contains the replacement,
+    /// the amounts are summed up. This fails if the units differ, the recipe stays unchanged.
+    ///
+    /// #Arguments
+    ///
+    /// * `name` The name of the ingredient to replace.
+    /// * `replacement` The ingredient to use instead.
+    pub fn replace_ingredient(
+        &mut self,
+        name: &str,
+        replacement: &Ingredient,
+    ) -> Result<(), String> {
+        if !self.contains_ingredient(name) {
+            return Ok(());
+        }
+        for old in self
+            .ingredients
+            .iter()
+            .filter(|l| l.ingredient.name == name)
+        {
+            let existing = self
+                .ingredients
+                .iter()
+                .find(|l| l.ingredient.name == replacement.name && l.section == old.section);
+            if let Some(existing) = existing {
+                if existing.unit != old.unit {
+                    return Err(format!(
+                        "Recipe '{}' uses '{}' in {} and '{}' in {}",
+                        self.name, name, old.unit, replacement.name, existing.unit
+                    ));
+                }
+            }
+        }
+
+        let mut ingredients: Vec<IngredientLine> = Vec::with_capacity(self.ingredients.len());
+        for line in self.ingredients.drain(..) {
+            if line.ingredient.name != name && line.ingredient.name != replacement.name {
+                ingredients.push(line);
+                continue;
+            }
+            match ingredients
+                .iter_mut()
+                .find(|l| l.ingredient.name == replacement.name && l.section == line.section)
+            {
+                Some(existing) => existing.amount += line.amount,
+                None => ingredients.push(IngredientLine {
+                    ingredient: replacement.clone(),
+                    ..line
+                }),
+            }
+        }
+        self.ingredients = ingredients;
+        self.details.rename_ingredient(name, &replacement.name);
+        return Ok(());
+    }
+
+    /// Remove an ingredient from all sections of the recipe.
+    /// Returns whether the recipe contained it.
+    ///
+    /// #Arguments
+    ///
+    /// * `name` The name of the ingredient to remove.
+    pub fn remove_ingredient(&mut self, name: &str) -> bool {
+        let count = self.ingredients.len();
+        self.ingredients.retain(|l| l.ingredient.name != name);
+        return self.ingredients.len() != count;
+    }
+
+    /// Returns whether the recipe is known to be done within the given minutes.
+    ///
+    /// #Arguments
+    ///
+    /// * `minutes` The maximum total time in minutes.
+    pub fn is_done_within(&self, minutes: u16) -> bool {
+        return match self.details.total_time() {
+            Some(total_time) => total_time <= minutes,
+            None => false,
         };
     }
 
+    /// Returns the sorted names of all recipes using an ingredient.
+    ///
+    /// #Arguments
+    ///
+    /// * `recipes` The recipes to search.
+    /// * `name` The name of the ingredient.
+    pub fn get_recipe_names_by_ingredient(
+        recipes: &HashMap<String, Recipe>,
+        name: &str,
+    ) -> Vec<String> {
+        let mut names: Vec<String> = recipes
+            .values()
+            .filter(|r| r.contains_ingredient(name))
+            .map(|r| r.name.to_string())
+            .collect();
+        names.sort();
+        return names;
+    }
+
     /// Export the recipe to JSON.
-    fn to_json(&self) -> String {
+    ///
+    /// #Arguments
+    ///
+    /// * `locale` The language of the labels, like the name of the difficulty.
+    pub fn to_json(&self, locale: Locale) -> String {
         let mut json: String = String::new();
         json.push('{');
         json.push_str("\"name\": \"");
-        json.push_str(&self.name);
+        json.push_str(&Json::escape(&self.name));
         json.push_str("\", ");
         json.push_str("\"ingredients\": [");
 
-        for (i, (a, u)) in &self.ingredients {
-            json.push_str(Recipe::ingredient_to_json(i, a, u).as_str());
+        let mut is_first: bool = true;
+        for line in &self.ingredients {
+            if !is_first {
+                json.push_str(", ");
+            }
+            json.push_str(line.to_json().as_str());
+            is_first = false;
         }
-
         json.push_str("]");
+
+        if !self.sub_recipes.is_empty() {
+            let mut sub_recipes: Vec<(&String, &(u16, u16))> = self.sub_recipes.iter().collect();
+            sub_recipes.sort();
+            json.push_str(", \"sub_recipes\": [");
+            let mut is_first: bool = true;
+            for (name, share) in sub_recipes {
+                if !is_first {
+                    json.push_str(", ");
+                }
+                json.push_str(&format!(
+                    "{{\"name\": \"{}\", \"share\": \"{}\"}}",
+                    Json::escape(name),
+                    Recipe::share_to_string(share)
+                ));
+                is_first = false;
+            }
+            json.push(']');
+        }
+
+        json.push_str(&self.details.to_json_fields(locale));
         json.push('}');
         return json;
     }
 
-    fn ingredient_to_json(i: &Ingredient, a: &u16, u: &String) -> String {
+    /// Export a list of recipes to JSON.
+    ///
+    /// #Arguments
+    ///
+    /// * `recipes` The recipes to export.
+    /// * `locale` The language of the labels.
+    pub fn all_to_json(recipes: &Vec<&Recipe>, locale: Locale) -> String {
         let mut json: String = String::new();
-        json.push('{');
-        json.push_str("\"name\": \"");
-        json.push_str(&i.name);
-        json.push_str("\", ");
+        json.push('[');
 
-        json.push_str("\"amount\": ");
-        json.push_str(&format!("{}", &a));
-        json.push_str(", ");
-
-        json.push_str("\"unit\": \"");
-        json.push_str(&u);
-        json.push_str("\"");
+        let mut is_first: bool = true;
+        for recipe in recipes {
+            if !is_first {
+                json.push_str(", ");
+            }
+            json.push_str(&recipe.to_json(locale));
+            is_first = false;
+        }
 
-        json.push('}');
+        json.push(']');
         return json;
     }
 
-    fn get_recipes_by_name<'a>(
+    /// Returns the recipes matching the name, best match first.
+    /// Case, umlauts and small typos are ignored.
+    ///
+    /// #Arguments
+    ///
+    /// * `recipes` The recipes to search.
+    /// * `name` The name searched for.
+    pub fn get_recipes_by_name<'a>(
         recipes: &'a HashMap<String, Recipe>,
         name: &str,
     ) -> Vec<&'a Recipe> {
-        let mut recipes_by_name: Vec<&'a Recipe> = Vec::new();
-
-        for (_n, recipe) in recipes.iter().filter(|(k, _v)| k.contains(name)) {
-            recipes_by_name.push(recipe);
-        }
+        let candidates: Vec<(&str, &'a Recipe)> =
+            recipes.iter().map(|(k, v)| (k.as_str(), v)).collect();
+        return search::rank(name, candidates);
+    }
 
-        return recipes_by_name;
+    /// Returns the recipes matching the query sorted by name.
+    ///
+    /// #Arguments
+    ///
+    /// * `recipes` The recipes to search.
+    /// * `query` The parsed query.
+    pub fn get_recipes_by_query<'a>(
+        recipes: &'a HashMap<String, Recipe>,
+        query: &Query,
+    ) -> Vec<&'a Recipe> {
+        let mut recipes_by_query: Vec<&'a Recipe> =
+            recipes.values().filter(|r| query.matches(r)).collect();
+        recipes_by_query.sort_by(|a, b| a.name.cmp(&b.name));
+        return recipes_by_query;
     }
 
     fn get_recipes_by_ingredients<'a>(
@@ -120,11 +551,11 @@ impl Recipe {
         for (_n, recipe) in recipes {
             let mut is_included = ingredient_included.is_empty();
             let mut is_excluded = false;
-            for (i, (_a, _u)) in &recipe.ingredients {
-                if is_included == false && ingredient_included.contains(&i.name) {
+            for i in recipe.get_ingredients() {
+                if is_included == false && ingredient_included.iter().any(|n| i.is_called(n)) {
                     is_included = true;
                 }
-                if ingredient_excluding.contains(&i.name) {
+                if ingredient_excluding.iter().any(|n| i.is_called(n)) {
                     is_excluded = true;
                     break;
                 }
@@ -169,7 +600,12 @@ impl Recipe {
         return (including, excluding);
     }
 
-    fn unify_tags(input: &str) -> Vec<String> {
+    /// Splits comma separated tags and prefixes them with `#` where missing.
+    ///
+    /// #Arguments
+    ///
+    /// * `input` The comma separated tags.
+    pub fn unify_tags(input: &str) -> Vec<String> {
         let mut tags: Vec<String> = Vec::new();
 
         let inputs = input.trim().split(',');
@@ -189,18 +625,20 @@ impl Recipe {
 
 #[cfg(test)]
 mod tests {
-    use super::Recipe;
+    use super::{IngredientLine, Recipe};
     use crate::cooking_book::group::Group;
     use crate::cooking_book::ingredient::Ingredient;
+    use crate::cooking_book::locale::Locale;
+    use crate::cooking_book::query::Query;
+    use crate::cooking_book::recipe_details::RecipeDetails;
     use crate::cooking_book::store::Store;
     use std::collections::HashMap;
     use std::collections::HashSet;
 
     #[test]
     fn test_json() {
-        let mut ingredients1: HashMap<Ingredient, (u16, String)> = HashMap::with_capacity(1);
         let in1 = Ingredient::new_by_name("Ei".to_string());
-        ingredients1.insert(in1, (1, "Stück".to_string()));
+        let ingredients1 = vec![IngredientLine::new(in1, 1, "Stück", "")];
 
         let mut tags1: HashSet<String> = HashSet::with_capacity(1);
         tags1.insert("Frühstück".to_string());
@@ -208,10 +646,185 @@ mod tests {
         let waffels = Recipe {
             name: "Waffeln".to_string(),
             ingredients: ingredients1,
+            sub_recipes: HashMap::new(),
             tags: tags1,
+            details: RecipeDetails::new(),
+        };
+
+        assert_eq!(waffels.to_json(Locale::De), "{\"name\": \"Waffeln\", \"ingredients\": [{\"name\": \"Ei\", \"amount\": 1, \"unit\": \"Stück\"}]}");
+
+        let mut line = IngredientLine::new(
+            Ingredient::new_by_name("Ei".to_string()),
+            1,
+            "Stück",
+            "Für \"oben\"",
+        );
+        line.note = "nicht \\ roh".to_string();
+        let mut sub_recipes: HashMap<String, (u16, u16)> = HashMap::new();
+        sub_recipes.insert("Soße \"fein\"".to_string(), (1, 2));
+        let toast = Recipe {
+            name: "Toast \"Hawaii\"".to_string(),
+            ingredients: vec![line],
+            sub_recipes,
+            tags: HashSet::new(),
+            details: RecipeDetails::new(),
+        };
+        assert_eq!(
+            toast.to_json(Locale::De),
+            "{\"name\": \"Toast \\\"Hawaii\\\"\", \"ingredients\": [{\"name\": \"Ei\", \"amount\": 1, \"unit\": \"Stück\", \"section\": \"Für \\\"oben\\\"\", \"note\": \"nicht \\\\ roh\"}], \"sub_recipes\": [{\"name\": \"Soße \\\"fein\\\"\", \"share\": \"1/2\"}]}"
+        );
+    }
+
+    #[test]
+    fn test_new_by_line() {
+        let mut all_ingredients: HashMap<String, Ingredient> = HashMap::new();
+        let mehl = Ingredient {
+            name: "Mehl".to_string(),
+            group: Group::Baking,
+            preferred_store: Store::Any,
+            aliases: Vec::new(),
+            price: None,
+        };
+        all_ingredients.insert("Mehl".to_string(), mehl.clone());
+
+        let recipe = Recipe::new_by_line(
+            "Waffel;Mehl,300,G;Magarine,2,EL;#Breakfast",
+            &all_ingredients,
+        );
+        assert_eq!(recipe.name, "Waffel");
+        assert!(recipe.ingredients[0] == IngredientLine::new(mehl, 300, "G", ""));
+        assert!(
+            recipe.ingredients[1]
+                == IngredientLine::new(
+                    Ingredient::new_by_name("Magarine".to_string()),
+                    2,
+                    "EL",
+                    ""
+                )
+        );
+        assert!(recipe.tags.contains("#Breakfast"));
+        assert_eq!(all_ingredients.len(), 1);
+    }
+
+    #[test]
+    fn test_json_order_and_sections() {
+        let all_ingredients: HashMap<String, Ingredient> = HashMap::new();
+        let recipe = Recipe::new_by_line(
+            "Pizza;[Für den Teig];Mehl,500,G;Wasser,300,ML;Salz,1,TL;[Für die Soße];Tomate,1,Dose;Salz,1,Prise",
+            &all_ingredients,
+        );
+
+        let names: Vec<&str> = recipe
+            .ingredients
+            .iter()
+            .map(|l| l.ingredient.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["Mehl", "Wasser", "Salz", "Tomate", "Salz"]);
+        assert_eq!(recipe.get_ingredients().len(), 4);
+        assert_eq!(recipe.to_json(Locale::De), recipe.to_json(Locale::De));
+        assert!(recipe.to_json(Locale::De).starts_with(
+            "{\"name\": \"Pizza\", \"ingredients\": [{\"name\": \"Mehl\", \"amount\": 500, \"unit\": \"G\", \"section\": \"Für den Teig\"}, {\"name\": \"Wasser\""
+        ));
+        assert!(recipe.to_json(Locale::De).contains(
+            "{\"name\": \"Salz\", \"amount\": 1, \"unit\": \"Prise\", \"section\": \"Für die Soße\"}]"
+        ));
+    }
+
+    #[test]
+    fn test_json_separator() {
+        let all_ingredients: HashMap<String, Ingredient> = HashMap::new();
+        let recipe = Recipe::new_by_line("Waffel;Mehl,300,G;Milch,150,ML", &all_ingredients);
+        let json = Recipe::all_to_json(&vec![&recipe], Locale::De);
+
+        assert!(json.starts_with("[{\"name\": \"Waffel\", \"ingredients\": [{"));
+        assert!(json.contains("}, {\"name\": \"M"));
+    }
+
+    #[test]
+    fn test_by_query() {
+        let recipes = self::get_mocks();
+        let names = |query: &str| -> Vec<String> {
+            let query = Query::parse(query).unwrap();
+            Recipe::get_recipes_by_query(&recipes, &query)
+                .iter()
+                .map(|r| r.name.to_string())
+                .collect()
         };
 
-        assert_eq!(waffels.to_json(), "{\"name\": \"Waffeln\", \"ingredients\": [{\"name\": \"Ei\", \"amount\": 1, \"unit\": \"Stück\"}]}")
+        assert_eq!(names("tag:3 AND NOT C"), vec!["R1"]);
+        assert_eq!(names("#1 OR #2"), vec!["R1", "R2"]);
+        assert!(names("#1 #2").is_empty());
+    }
+
+    #[test]
+    fn test_to_line() {
+        let all_ingredients: HashMap<String, Ingredient> = HashMap::new();
+        let line = "Waffel;Mehl,300,G;Magarine,2,EL;#Breakfast;#Sweet";
+        let recipe = Recipe::new_by_line(line, &all_ingredients);
+        assert_eq!(recipe.to_line(), line);
+
+        let line =
+            "Pizza;Öl,1,EL;[Teig];Mehl,500,G;Öl,2,EL;[Soße];Öl,1,EL;[];Basilikum,1,Bund,gezupft";
+        let recipe = Recipe::new_by_line(line, &all_ingredients);
+        assert_eq!(recipe.to_line(), line);
+    }
+
+    #[test]
+    fn test_replace_ingredient() {
+        let all_ingredients: HashMap<String, Ingredient> = HashMap::new();
+        let margarine = Ingredient::new_by_name("Margarine".to_string());
+
+        let mut recipe = Recipe::new_by_line("Waffel;Magarine,2,EL;Mehl,300,G", &all_ingredients);
+        assert!(recipe.replace_ingredient("Magarine", &margarine).is_ok());
+        assert_eq!(recipe.to_line(), "Waffel;Margarine,2,EL;Mehl,300,G");
+
+        let mut recipe =
+            Recipe::new_by_line("Waffel;Magarine,2,EL;Margarine,1,EL", &all_ingredients);
+        assert!(recipe.replace_ingredient("Magarine", &margarine).is_ok());
+        assert_eq!(recipe.to_line(), "Waffel;Margarine,3,EL");
+
+        let mut recipe =
+            Recipe::new_by_line("Waffel;Magarine,2,EL;Margarine,10,G", &all_ingredients);
+        assert!(recipe.replace_ingredient("Magarine", &margarine).is_err());
+        assert_eq!(recipe.to_line(), "Waffel;Magarine,2,EL;Margarine,10,G");
+
+        let mut recipe = Recipe::new_by_line(
+            "Kuchen;[Teig];Magarine,200,G;[Guss];Margarine,10,G;Magarine,20,G",
+            &all_ingredients,
+        );
+        assert!(recipe.replace_ingredient("Magarine", &margarine).is_ok());
+        assert_eq!(
+            recipe.to_line(),
+            "Kuchen;[Teig];Margarine,200,G;[Guss];Margarine,30,G"
+        );
+    }
+
+    #[test]
+    fn test_remove_ingredient() {
+        let all_ingredients: HashMap<String, Ingredient> = HashMap::new();
+        let mut recipe = Recipe::new_by_line(
+            "Waffel;[Teig];Magarine,2,EL;Mehl,300,G;[Form];Magarine,1,TL",
+            &all_ingredients,
+        );
+
+        assert!(recipe.remove_ingredient("Magarine"));
+        assert!(!recipe.remove_ingredient("Magarine"));
+        assert_eq!(recipe.to_line(), "Waffel;[Teig];Mehl,300,G");
+    }
+
+    #[test]
+    fn test_get_recipe_names_by_ingredient() {
+        let recipes = self::get_mocks();
+
+        assert_eq!(
+            Recipe::get_recipe_names_by_ingredient(&recipes, "A"),
+            vec!["R1".to_string(), "R2".to_string()]
+        );
+        assert_eq!(
+            Recipe::get_recipe_names_by_ingredient(&recipes, "C"),
+            vec!["R2".to_string()]
+        );
+        assert!(Recipe::get_recipe_names_by_ingredient(&recipes, "D").is_empty());
     }
 
     #[test]
@@ -244,20 +857,24 @@ mod tests {
         let mut recipes: HashMap<String, Recipe> = HashMap::with_capacity(2);
 
         let name1 = "R1".to_string();
-        let mut ingredients1: HashMap<Ingredient, (u16, String)> = HashMap::new();
+        let mut ingredients1: Vec<IngredientLine> = Vec::new();
         let in1 = Ingredient {
             name: "A".to_string(),
             group: Group::Other,
             preferred_store: Store::Any,
+            aliases: Vec::new(),
+            price: None,
         };
-        ingredients1.insert(in1, (1, "unit".to_string()));
+        ingredients1.push(IngredientLine::new(in1, 1, "unit", ""));
 
         let in2 = Ingredient {
             name: "B".to_string(),
             group: Group::Other,
             preferred_store: Store::Any,
+            aliases: Vec::new(),
+            price: None,
         };
-        ingredients1.insert(in2, (1, "unit".to_string()));
+        ingredients1.push(IngredientLine::new(in2, 1, "unit", ""));
 
         let mut tags1: HashSet<String> = HashSet::new();
         tags1.insert("1".to_string());
@@ -266,24 +883,30 @@ mod tests {
         let r1 = Recipe {
             name: name1,
             ingredients: ingredients1,
+            sub_recipes: HashMap::new(),
             tags: tags1,
+            details: RecipeDetails::new(),
         };
         recipes.insert("R1".to_string(), r1);
 
         let name2 = "R2".to_string();
-        let mut ingredients2: HashMap<Ingredient, (u16, String)> = HashMap::new();
+        let mut ingredients2: Vec<IngredientLine> = Vec::new();
         let in12 = Ingredient {
             name: "A".to_string(),
             group: Group::Other,
             preferred_store: Store::Any,
+            aliases: Vec::new(),
+            price: None,
         };
-        ingredients2.insert(in12, (1, "unit".to_string()));
+        ingredients2.push(IngredientLine::new(in12, 1, "unit", ""));
         let in22 = Ingredient {
             name: "C".to_string(),
             group: Group::Other,
             preferred_store: Store::Any,
+            aliases: Vec::new(),
+            price: None,
         };
-        ingredients2.insert(in22, (1, "unit".to_string()));
+        ingredients2.push(IngredientLine::new(in22, 1, "unit", ""));
         let mut tags2: HashSet<String> = HashSet::new();
         tags2.insert("2".to_string());
         tags2.insert("3".to_string());
@@ -291,7 +914,9 @@ mod tests {
         let r2 = Recipe {
             name: name2,
             ingredients: ingredients2,
+            sub_recipes: HashMap::new(),
             tags: tags2,
+            details: RecipeDetails::new(),
         };
 
         recipes.insert("R2".to_string(), r2);
@@ -348,6 +973,27 @@ mod tests {
         assert!(!filtered.contains(&recipes.get("R2").unwrap()));
     }
 
+    #[test]
+    fn test_by_ingredient_alias() {
+        let mut all_ingredients: HashMap<String, Ingredient> = HashMap::new();
+        all_ingredients.insert(
+            "Zwiebel".to_string(),
+            Ingredient::new_by_line("Zwiebel;0;-1;Zwiebeln"),
+        );
+        let mut recipes: HashMap<String, Recipe> = HashMap::new();
+        recipes.insert(
+            "Suppe".to_string(),
+            Recipe::new_by_line("Suppe;Zwiebeln,2,", &all_ingredients),
+        );
+        let recipe = recipes.get("Suppe").unwrap();
+        assert_eq!(recipe.to_line(), "Suppe;Zwiebel,2,");
+
+        let including: Vec<String> = vec!["zwiebeln".to_string()];
+        let excluding: Vec<String> = Vec::with_capacity(0);
+        let filtered = Recipe::get_recipes_by_ingredients(&recipes, &including, &excluding);
+        assert!(filtered.contains(&recipe));
+    }
+
     #[test]
     fn test_by_name() {
         let recipes = self::get_mocks();
@@ -365,6 +1011,31 @@ mod tests {
         assert!(recipes_2.contains(&recipes.get("R2").unwrap()));
     }
 
+    #[test]
+    fn test_by_name_fuzzy() {
+        let all_ingredients: HashMap<String, Ingredient> = HashMap::new();
+        let mut recipes: HashMap<String, Recipe> = HashMap::new();
+        for line in &[
+            "Pfannkuchen;Mehl,300,G",
+            "Käsekuchen;Mehl,200,G",
+            "Suppe;Wasser,1,L",
+        ] {
+            let recipe = Recipe::new_by_line(line, &all_ingredients);
+            recipes.insert(recipe.name.to_string(), recipe);
+        }
+
+        let names = |name: &str| -> Vec<String> {
+            Recipe::get_recipes_by_name(&recipes, name)
+                .iter()
+                .map(|r| r.name.to_string())
+                .collect()
+        };
+        assert_eq!(names("pfannkuchen"), vec!["Pfannkuchen"]);
+        assert_eq!(names("kasekuchen"), vec!["Käsekuchen"]);
+        assert_eq!(names("kuchen"), vec!["Käsekuchen", "Pfannkuchen"]);
+        assert_eq!(names("Pfanekuchen"), vec!["Pfannkuchen"]);
+    }
+
     #[test]
     fn test_by_tag() {
         let recipes = self::get_mocks();
@@ -388,4 +1059,78 @@ mod tests {
         assert!(recipes_2.contains(&recipes.get("R1").unwrap()));
         assert!(recipes_2.contains(&recipes.get("R2").unwrap()));
     }
+
+    #[test]
+    fn test_sub_recipes() {
+        let all_ingredients: HashMap<String, Ingredient> = HashMap::new();
+        let mut recipes: HashMap<String, Recipe> = HashMap::new();
+        for line in &[
+            "Lasagne;Nudelplatten,250,G;Käse,100,G;@Bolognese,1;@Bechamel,1/2",
+            "Bolognese;Hackfleisch,500,G;Tomate,1,Dose",
+            "Bechamel;Milch,1,L;Butter,50,G;Käse,50,G",
+        ] {
+            let recipe = Recipe::new_by_line(line, &all_ingredients);
+            recipes.insert(recipe.name.clone(), recipe);
+        }
+
+        let lasagne = &recipes["Lasagne"];
+        assert_eq!(
+            lasagne.to_line(),
+            "Lasagne;Nudelplatten,250,G;Käse,100,G;@Bechamel,1/2;@Bolognese,1"
+        );
+
+        let expanded = lasagne.expand(&recipes).unwrap();
+        let amount = |name: &str| {
+            expanded
+                .ingredients
+                .iter()
+                .find(|l| l.ingredient.name == name)
+                .map(|l| (l.amount, l.unit.to_string()))
+        };
+        assert_eq!(amount("Käse"), Some((125, "G".to_string())));
+        assert_eq!(amount("Milch"), Some((1, "L".to_string())));
+        assert_eq!(amount("Butter"), Some((25, "G".to_string())));
+        assert_eq!(amount("Tomate"), Some((1, "Dose".to_string())));
+        assert_eq!(expanded.ingredients.len(), 6);
+        assert!(expanded.sub_recipes.is_empty());
+    }
+
+    #[test]
+    fn test_sub_recipe_errors() {
+        let all_ingredients: HashMap<String, Ingredient> = HashMap::new();
+        let mut recipes: HashMap<String, Recipe> = HashMap::new();
+        for line in &[
+            "A;@B,2",
+            "B;@C",
+            "C;@A,1/3",
+            "D;@E",
+            "F;Käse,1,Stück;@G",
+            "G;Käse,100,G",
+        ] {
+            let recipe = Recipe::new_by_line(line, &all_ingredients);
+            recipes.insert(recipe.name.clone(), recipe);
+        }
+
+        assert_eq!(
+            recipes["A"].expand(&recipes).err(),
+            Some("Recipe 'A' references itself: A -> B -> C -> A".to_string())
+        );
+        assert_eq!(
+            recipes["D"].expand(&recipes).err(),
+            Some("Recipe 'D' uses unknown recipe 'E'".to_string())
+        );
+
+        let expanded = recipes["F"].expand(&recipes).unwrap();
+        assert_eq!(expanded.to_line(), "F;Käse,1,Stück;Käse,100,G");
+    }
+
+    #[test]
+    fn test_parse_share() {
+        assert_eq!(Recipe::parse_share(""), Some((1, 1)));
+        assert_eq!(Recipe::parse_share("2"), Some((2, 1)));
+        assert_eq!(Recipe::parse_share("1/2"), Some((1, 2)));
+        assert_eq!(Recipe::parse_share("1/0"), None);
+        assert_eq!(Recipe::parse_share("0"), None);
+        assert_eq!(Recipe::parse_share("a"), None);
+    }
 }
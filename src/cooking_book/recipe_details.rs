@@ -0,0 +1,508 @@
+use crate::cooking_book::locale::Locale;
+use crate::file_access::json::Json;
+use std::fmt;
+
+/// How hard a recipe is to cook.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Difficulty {
+    Easy = 0,
+    Medium = 1,
+    Hard = 2,
+}
+
+impl Difficulty {
+    pub fn lookup_difficulty_number(number: usize) -> Difficulty {
+        match number {
+            0 => Difficulty::Easy,
+            1 => Difficulty::Medium,
+            _ => Difficulty::Hard,
+        }
+    }
+
+    /// Returns the stable id of the difficulty like `easy`, which doesn't depend on the language.
+    pub fn id(&self) -> &'static str {
+        return match *self {
+            Difficulty::Easy => "easy",
+            Difficulty::Medium => "medium",
+            Difficulty::Hard => "hard",
+        };
+    }
+
+    /// Returns the name of the difficulty shown to the user.
+    ///
+    /// #Arguments
+    ///
+    /// * `locale` The language of the name.
+    pub fn label(&self, locale: Locale) -> &'static str {
+        return locale.text(&format!("difficulty.{}", self.id()));
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.label(Locale::De))
+    }
+}
+
+/// An instruction step. Ingredients are referenced as `{Mehl}` and timers as `{10 min}`.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Step {
+    pub text: String,
+}
+
+impl Step {
+    /// Returns the text without the braces of references and timers.
+    pub fn plain_text(&self) -> String {
+        return self.text.replace('{', "").replace('}', "");
+    }
+
+    /// Returns the names of the referenced ingredients in order of appearance.
+    pub fn get_ingredients(&self) -> Vec<&str> {
+        return self
+            .get_references()
+            .into_iter()
+            .filter(|r| Step::parse_timer(r).is_none())
+            .collect();
+    }
+
+    /// Returns the sum of all timers of the step in minutes, if there are any.
+    pub fn get_timer(&self) -> Option<u16> {
+        let timers: Vec<u16> = self
+            .get_references()
+            .into_iter()
+            .filter_map(|r| Step::parse_timer(r))
+            .collect();
+        if timers.is_empty() {
+            return None;
+        }
+        return Some(timers.iter().sum());
+    }
+
+    fn get_references(&self) -> Vec<&str> {
+        let mut references: Vec<&str> = Vec::new();
+        let mut rest = self.text.as_str();
+        while let Some(start) = rest.find('{') {
+            let end = match rest[start..].find('}') {
+                Some(end) => start + end,
+                None => break,
+            };
+            references.push(rest[start + 1..end].trim());
+            rest = &rest[end + 1..];
+        }
+        return references;
+    }
+
+    fn parse_timer(reference: &str) -> Option<u16> {
+        let mut parts = reference.split_whitespace();
+        let minutes = parts.next()?.parse::<u16>().ok()?;
+        if parts.next()? != "min" || parts.next().is_some() {
+            return None;
+        }
+        return Some(minutes);
+    }
+
+    /// Export this step to JSON.
+    pub fn to_json(&self) -> String {
+        let mut json: String = String::new();
+        json.push_str(&format!(
+            "{{\"text\": \"{}\", \"ingredients\": [",
+            Json::escape(&self.plain_text())
+        ));
+        let ingredients: Vec<String> = self
+            .get_ingredients()
+            .iter()
+            .map(|i| format!("\"{}\"", Json::escape(i)))
+            .collect();
+        json.push_str(&ingredients.join(", "));
+        json.push(']');
+        if let Some(timer) = self.get_timer() {
+            json.push_str(&format!(", \"timer\": {}", timer));
+        }
+        json.push('}');
+        return json;
+    }
+}
+
+/// Everything needed to actually cook a recipe besides its ingredients.
+/// Times are in minutes. The equipment lists the needed cookware like a pot.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct RecipeDetails {
+    pub steps: Vec<Step>,
+    pub equipment: Vec<String>,
+    pub servings: Option<u16>,
+    pub prep_time: Option<u16>,
+    pub cook_time: Option<u16>,
+    pub difficulty: Option<Difficulty>,
+    pub source: String,
+    pub notes: String,
+}
+
+impl RecipeDetails {
+    pub fn new() -> RecipeDetails {
+        return RecipeDetails {
+            steps: Vec::new(),
+            equipment: Vec::new(),
+            servings: None,
+            prep_time: None,
+            cook_time: None,
+            difficulty: None,
+            source: String::new(),
+            notes: String::new(),
+        };
+    }
+
+    /// Returns the preparation and cooking time together, if any of them is known.
+    pub fn total_time(&self) -> Option<u16> {
+        return match (self.prep_time, self.cook_time) {
+            (None, None) => None,
+            (prep, cook) => Some(prep.unwrap_or(0).saturating_add(cook.unwrap_or(0))),
+        };
+    }
+
+    /// Create details from the text of one recipe in the details file.
+    /// Each value starts with its key like `servings: 2`, lines starting with two spaces continue
+    /// the previous value. Steps are given in order with the key `step`, each piece of
+    /// equipment with the key `equipment`.
+    /// Returns the details and the problems found as line index and message.
+    ///
+    /// #Arguments
+    ///
+    /// * `text` The text to parse.
+    pub fn new_by_text(text: &str) -> (RecipeDetails, Vec<(usize, String)>) {
+        let mut details = RecipeDetails::new();
+        let mut problems: Vec<(usize, String)> = Vec::new();
+
+        let mut entries: Vec<(usize, &str, String)> = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            if line.starts_with("  ") {
+                match entries.last_mut() {
+                    Some((_i, _key, value)) => {
+                        value.push('\n');
+                        value.push_str(&line[2..]);
+                    }
+                    None => problems.push((i, "continuation without value".to_string())),
+                }
+                continue;
+            }
+            if line.trim().is_empty() {
+                continue;
+            }
+            match line.find(':') {
+                Some(colon) => entries.push((
+                    i,
+                    line[..colon].trim(),
+                    line[colon + 1..].trim().to_string(),
+                )),
+                None => problems.push((i, format!("missing key in '{}'", line))),
+            }
+        }
+
+        for (i, key, value) in entries {
+            let number = value.parse::<u16>().ok();
+            match key {
+                "step" => details.steps.push(Step { text: value }),
+                "equipment" => details.equipment.push(value),
+                "source" => details.source = value,
+                "notes" => details.notes = value,
+                "servings" | "prep_time" | "cook_time" | "difficulty" if number.is_none() => {
+                    problems.push((i, format!("invalid number '{}' for '{}'", value, key)))
+                }
+                "servings" => details.servings = number,
+                "prep_time" => details.prep_time = number,
+                "cook_time" => details.cook_time = number,
+                "difficulty" => {
+                    details.difficulty = Some(Difficulty::lookup_difficulty_number(
+                        number.unwrap() as usize
+                    ))
+                }
+                _ => problems.push((i, format!("unknown key '{}'", key))),
+            }
+        }
+        problems.sort_by_key(|p| p.0);
+
+        return (details, problems);
+    }
+
+    /// Create the details of all recipes from the details file.
+    /// Each recipe starts with a line like `== Pfannkuchen`, followed by its text, see `new_by_text`.
+    /// Lines starting with `#` are comments.
+    /// Returns the line index of each name, the recipe names with their details and the problems
+    /// found as line index and message.
+    ///
+    /// #Arguments
+    ///
+    /// * `content` The content of the details file.
+    pub fn all_by_text(content: &str) -> Vec<(usize, String, RecipeDetails, Vec<(usize, String)>)> {
+        let mut sections: Vec<(usize, String, String)> = Vec::new();
+        for (i, line) in content.lines().enumerate() {
+            if line.starts_with('#') {
+                continue;
+            }
+            if line.starts_with("== ") {
+                sections.push((i, line[3..].trim().to_string(), String::new()));
+                continue;
+            }
+            match sections.last_mut() {
+                Some((_i, _name, text)) => {
+                    text.push_str(line);
+                    text.push('\n');
+                }
+                None => {
+                    if !line.trim().is_empty() {
+                        sections.push((i, String::new(), line.to_string()));
+                    }
+                }
+            }
+        }
+
+        let mut all_details = Vec::with_capacity(sections.len());
+        for (start, name, text) in sections {
+            let (details, problems) = RecipeDetails::new_by_text(&text);
+            let mut problems: Vec<(usize, String)> = problems
+                .into_iter()
+                .map(|(i, p)| (start + 1 + i, p))
+                .collect();
+            if name.is_empty() {
+                problems.insert(0, (start, "details without recipe name".to_string()));
+            }
+            all_details.push((start, name, details, problems));
+        }
+        return all_details;
+    }
+
+    /// Export the details of several recipes to the content of the details file.
+    /// Recipes without details are left out.
+    ///
+    /// #Arguments
+    ///
+    /// * `all_details` The recipe names with their details.
+    pub fn all_to_text(all_details: &Vec<(&String, &RecipeDetails)>) -> String {
+        let mut content = String::new();
+        for (name, details) in all_details {
+            if details.is_empty() {
+                continue;
+            }
+            content.push_str(&format!("== {}\n", name));
+            content.push_str(&details.to_text());
+        }
+        return content;
+    }
+
+    /// Export the details to the text of the details file, see `new_by_text`.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        let mut push = |key: &str, value: &str| {
+            text.push_str(key);
+            text.push_str(": ");
+            text.push_str(&value.replace('\n', "\n  "));
+            text.push('\n');
+        };
+
+        if let Some(servings) = self.servings {
+            push("servings", &servings.to_string());
+        }
+        if let Some(prep_time) = self.prep_time {
+            push("prep_time", &prep_time.to_string());
+        }
+        if let Some(cook_time) = self.cook_time {
+            push("cook_time", &cook_time.to_string());
+        }
+        if let Some(difficulty) = self.difficulty {
+            push("difficulty", &(difficulty as u8).to_string());
+        }
+        if !self.source.is_empty() {
+            push("source", &self.source);
+        }
+        if !self.notes.is_empty() {
+            push("notes", &self.notes);
+        }
+        for equipment in &self.equipment {
+            push("equipment", equipment);
+        }
+        for step in &self.steps {
+            push("step", &step.text);
+        }
+        return text;
+    }
+
+    /// Returns whether nothing is known besides the ingredients.
+    pub fn is_empty(&self) -> bool {
+        return *self == RecipeDetails::new();
+    }
+
+    /// Replace the references to an ingredient in all steps.
+    ///
+    /// #Arguments
+    ///
+    /// * `name` The name of the referenced ingredient.
+    /// * `new_name` The name to reference instead.
+    pub fn rename_ingredient(&mut self, name: &str, new_name: &str) {
+        let reference = format!("{{{}}}", name);
+        let replacement = format!("{{{}}}", new_name);
+        for step in &mut self.steps {
+            step.text = step.text.replace(&reference, &replacement);
+        }
+    }
+
+    /// Export the details as JSON fields to append to the recipe object.
+    /// Unknown values are left out. Returns an empty string if nothing is known.
+    ///
+    /// #Arguments
+    ///
+    /// * `locale` The language of the name of the difficulty.
+    pub fn to_json_fields(&self, locale: Locale) -> String {
+        let mut json: String = String::new();
+        if let Some(servings) = self.servings {
+            json.push_str(&format!(", \"servings\": {}", servings));
+        }
+        if let Some(prep_time) = self.prep_time {
+            json.push_str(&format!(", \"prep_time\": {}", prep_time));
+        }
+        if let Some(cook_time) = self.cook_time {
+            json.push_str(&format!(", \"cook_time\": {}", cook_time));
+        }
+        if let Some(total_time) = self.total_time() {
+            json.push_str(&format!(", \"total_time\": {}", total_time));
+        }
+        if let Some(difficulty) = self.difficulty {
+            json.push_str(&format!(
+                ", \"difficulty\": {}, \"difficulty_id\": \"{}\", \"difficulty_name\": \"{}\"",
+                difficulty as u8,
+                difficulty.id(),
+                difficulty.label(locale)
+            ));
+        }
+        if !self.source.is_empty() {
+            json.push_str(&format!(", \"source\": \"{}\"", Json::escape(&self.source)));
+        }
+        if !self.notes.is_empty() {
+            json.push_str(&format!(", \"notes\": \"{}\"", Json::escape(&self.notes)));
+        }
+        if !self.equipment.is_empty() {
+            let equipment: Vec<String> = self
+                .equipment
+                .iter()
+                .map(|e| format!("\"{}\"", Json::escape(e)))
+                .collect();
+            json.push_str(&format!(", \"equipment\": [{}]", equipment.join(", ")));
+        }
+        if !self.steps.is_empty() {
+            let steps: Vec<String> = self.steps.iter().map(|s| s.to_json()).collect();
+            json.push_str(&format!(", \"steps\": [{}]", steps.join(", ")));
+        }
+        return json;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Difficulty, RecipeDetails, Step};
+    use crate::cooking_book::locale::Locale;
+
+    #[test]
+    fn test_step() {
+        let step = Step {
+            text: "{Mehl} und {Milch} verrühren, {10 min} ruhen lassen".to_string(),
+        };
+        assert_eq!(step.get_ingredients(), vec!["Mehl", "Milch"]);
+        assert_eq!(step.get_timer(), Some(10));
+        assert_eq!(
+            step.plain_text(),
+            "Mehl und Milch verrühren, 10 min ruhen lassen"
+        );
+        assert_eq!(
+            step.to_json(),
+            "{\"text\": \"Mehl und Milch verrühren, 10 min ruhen lassen\", \"ingredients\": [\"Mehl\", \"Milch\"], \"timer\": 10}"
+        );
+
+        let step = Step {
+            text: "{Käse \"alt\"} mit \\ reiben".to_string(),
+        };
+        assert_eq!(
+            step.to_json(),
+            "{\"text\": \"Käse \\\"alt\\\" mit \\\\ reiben\", \"ingredients\": [\"Käse \\\"alt\\\"\"]}"
+        );
+    }
+
+    #[test]
+    fn test_text() {
+        let text = "servings: 2\nprep_time: 10\ncook_time: 20\ndifficulty: 1\nsource: Oma\nnotes: Mit Apfelmus.\n  Oder Zucker.\nequipment: Pfanne\nstep: {Mehl} und {Milch} verrühren\nstep: In der Pfanne backen\n";
+        let (details, problems) = RecipeDetails::new_by_text(text);
+        assert!(problems.is_empty());
+        assert_eq!(details.servings, Some(2));
+        assert_eq!(details.total_time(), Some(30));
+        assert_eq!(details.difficulty, Some(Difficulty::Medium));
+        assert_eq!(details.notes, "Mit Apfelmus.\nOder Zucker.");
+        assert_eq!(details.steps.len(), 2);
+        assert_eq!(details.equipment, vec!["Pfanne"]);
+        assert_eq!(details.to_text(), text);
+    }
+
+    #[test]
+    fn test_text_problems() {
+        let (details, problems) =
+            RecipeDetails::new_by_text("  lost\nservings: zwei\ncolor: red\nno key\nstep: Backen");
+        assert_eq!(
+            problems,
+            vec![
+                (0, "continuation without value".to_string()),
+                (1, "invalid number 'zwei' for 'servings'".to_string()),
+                (2, "unknown key 'color'".to_string()),
+                (3, "missing key in 'no key'".to_string())
+            ]
+        );
+        assert_eq!(details.steps.len(), 1);
+    }
+
+    #[test]
+    fn test_all_by_text() {
+        let content =
+            "# details\n== Pfannkuchen\nservings: 2\nstep: Backen\n== Waffel\nprep_time: x\n";
+        let all_details = RecipeDetails::all_by_text(content);
+        assert_eq!(all_details.len(), 2);
+        assert_eq!(all_details[0].0, 1);
+        assert_eq!(all_details[0].1, "Pfannkuchen");
+        assert_eq!(all_details[0].2.servings, Some(2));
+        assert_eq!(
+            all_details[1].3,
+            vec![(5, "invalid number 'x' for 'prep_time'".to_string())]
+        );
+
+        let name = "Pfannkuchen".to_string();
+        assert_eq!(
+            RecipeDetails::all_to_text(&vec![
+                (&name, &all_details[0].2),
+                (&all_details[1].1, &RecipeDetails::new())
+            ]),
+            "== Pfannkuchen\nservings: 2\nstep: Backen\n"
+        );
+    }
+
+    #[test]
+    fn test_json_fields() {
+        let mut details = RecipeDetails::new();
+        assert_eq!(details.to_json_fields(Locale::De), "");
+        assert!(details.is_empty());
+
+        details.cook_time = Some(15);
+        details.notes = "Sehr \"lecker\"".to_string();
+        details.equipment.push("Topf".to_string());
+        assert_eq!(
+            details.to_json_fields(Locale::De),
+            ", \"cook_time\": 15, \"total_time\": 15, \"notes\": \"Sehr \\\"lecker\\\"\", \"equipment\": [\"Topf\"]"
+        );
+
+        details.difficulty = Some(Difficulty::Medium);
+        assert!(details.to_json_fields(Locale::En).contains(
+            ", \"difficulty\": 1, \"difficulty_id\": \"medium\", \"difficulty_name\": \"Medium\""
+        ));
+        assert_eq!(Difficulty::Medium.to_string(), "Mittel");
+    }
+
+    #[test]
+    fn test_rename_ingredient() {
+        let (mut details, _problems) = RecipeDetails::new_by_text("step: {Mehl} sieben");
+        details.rename_ingredient("Mehl", "Weizenmehl");
+        assert_eq!(details.steps[0].text, "{Weizenmehl} sieben");
+    }
+}
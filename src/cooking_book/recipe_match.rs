@@ -0,0 +1,332 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use crate::cooking_book::ingredient::Ingredient;
+use crate::cooking_book::recipe::Recipe;
+use crate::cooking_book::search;
+use crate::cooking_book::shopping_list::ShoppingList;
+
+/// How to rank the recipes which can be cooked with the available ingredients.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Ranking {
+    /// The fewest missing ingredients first.
+    MissingCount,
+    /// The highest share of available ingredients first.
+    Coverage,
+    /// The fewest missing ingredients which are not on the shopping list yet first.
+    ShoppingList,
+}
+
+impl Ranking {
+    /// Returns the ranking with this name, the default is `MissingCount`.
+    ///
+    /// #Arguments
+    ///
+    /// * `name` The name of the ranking.
+    pub fn lookup_ranking(name: &str) -> Ranking {
+        match name {
+            "coverage" => Ranking::Coverage,
+            "shopping_list" => Ranking::ShoppingList,
+            _ => Ranking::MissingCount,
+        }
+    }
+}
+
+/// A recipe with the ingredients missing to cook it.
+/// The ingredients include those of the sub recipes.
+pub struct RecipeMatch<'a> {
+    pub recipe: &'a Recipe,
+    pub ingredients: Vec<Ingredient>,
+    pub missing: Vec<Ingredient>,
+    pub missing_on_shopping_list: usize,
+}
+
+impl<'a> RecipeMatch<'a> {
+    /// Returns the recipes with their missing ingredients, ranked as requested.
+    /// Sub recipes are expanded to their ingredients, recipes which can't be expanded are left out.
+    /// Recipes with equal rank are sorted by name.
+    ///
+    /// #Arguments
+    ///
+    /// * `recipes` The recipes to check.
+    /// * `available` The names or aliases of the available ingredients.
+    /// * `shopping_list` The shopping list.
+    /// * `ranking` How to rank the recipes.
+    pub fn find_all(
+        recipes: &'a HashMap<String, Recipe>,
+        available: &Vec<String>,
+        shopping_list: &ShoppingList,
+        ranking: Ranking,
+    ) -> Vec<RecipeMatch<'a>> {
+        let available: Vec<String> = available.iter().map(|a| search::normalize(a)).collect();
+        let is_available = |i: &Ingredient| {
+            available.contains(&search::normalize(&i.name))
+                || i.aliases
+                    .iter()
+                    .any(|a| available.contains(&search::normalize(a)))
+        };
+
+        let mut matches: Vec<RecipeMatch<'a>> = Vec::with_capacity(recipes.len());
+        for recipe in recipes.values() {
+            let expanded = match recipe.expand(recipes) {
+                Ok(expanded) => expanded,
+                Err(_) => continue,
+            };
+            let ingredients: Vec<Ingredient> =
+                expanded.get_ingredients().into_iter().cloned().collect();
+            let mut missing: Vec<Ingredient> = ingredients
+                .iter()
+                .filter(|i| !is_available(i))
+                .cloned()
+                .collect();
+            missing.sort_by(|a, b| a.name.cmp(&b.name));
+
+            let missing_on_shopping_list = missing
+                .iter()
+                .filter(|i| shopping_list.to_buy.keys().any(|s| s.name == i.name))
+                .count();
+
+            matches.push(RecipeMatch {
+                recipe,
+                ingredients,
+                missing,
+                missing_on_shopping_list,
+            });
+        }
+
+        matches.sort_by(|a, b| {
+            a.compare(b, ranking)
+                .then_with(|| a.recipe.name.cmp(&b.recipe.name))
+        });
+        return matches;
+    }
+
+    fn compare(&self, other: &RecipeMatch, ranking: Ranking) -> Ordering {
+        return match ranking {
+            Ranking::MissingCount => self.missing.len().cmp(&other.missing.len()),
+            Ranking::Coverage => {
+                // a / b > c / d <=> a * d > c * b, compared without floating point
+                let own = self.available_count() * other.ingredients.len();
+                let others = other.available_count() * self.ingredients.len();
+                others.cmp(&own)
+            }
+            Ranking::ShoppingList => self.still_to_buy().cmp(&other.still_to_buy()),
+        };
+    }
+
+    fn available_count(&self) -> usize {
+        return self.ingredients.len() - self.missing.len();
+    }
+
+    fn still_to_buy(&self) -> usize {
+        return self.missing.len() - self.missing_on_shopping_list;
+    }
+
+    /// Returns the share of available ingredients between 0 and 1.
+    pub fn coverage(&self) -> f32 {
+        if self.ingredients.is_empty() {
+            return 1.0;
+        }
+        return self.available_count() as f32 / self.ingredients.len() as f32;
+    }
+
+    /// Returns the price in cent of the missing ingredients with known price
+    /// and the number of missing ingredients without price.
+    pub fn missing_cost(&self) -> (u32, usize) {
+        let mut cost: u32 = 0;
+        let mut unpriced: usize = 0;
+        for i in &self.missing {
+            match i.price {
+                Some(price) => cost += price,
+                None => unpriced += 1,
+            }
+        }
+        return (cost, unpriced);
+    }
+
+    /// Export this match to JSON.
+    pub fn to_json(&self) -> String {
+        let mut json: String = String::new();
+        json.push('{');
+
+        json.push_str("\"name\": \"");
+        json.push_str(&self.recipe.name);
+        json.push_str("\", ");
+
+        json.push_str("\"missing\": [");
+        let mut is_first: bool = true;
+        for i in &self.missing {
+            if !is_first {
+                json.push_str(", ");
+            }
+            json.push_str(&format!("\"{}\"", i.name));
+            is_first = false;
+        }
+        json.push_str("], ");
+
+        let (cost, unpriced) = self.missing_cost();
+        json.push_str(&format!("\"missing_count\": {}, ", self.missing.len()));
+        json.push_str(&format!(
+            "\"missing_on_shopping_list\": {}, ",
+            self.missing_on_shopping_list
+        ));
+        json.push_str(&format!("\"coverage\": {:.2}, ", self.coverage()));
+        json.push_str(&format!("\"missing_cost\": {}, ", cost));
+        json.push_str(&format!("\"missing_unpriced\": {}", unpriced));
+
+        json.push('}');
+        return json;
+    }
+
+    /// Export a list of matches to JSON.
+    ///
+    /// #Arguments
+    ///
+    /// * `matches` The matches to export.
+    pub fn all_to_json(matches: &Vec<RecipeMatch>) -> String {
+        let mut json: String = String::new();
+        json.push('[');
+
+        let mut is_first: bool = true;
+        for m in matches {
+            if !is_first {
+                json.push_str(", ");
+            }
+            json.push_str(&m.to_json());
+            is_first = false;
+        }
+
+        json.push(']');
+        return json;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Ranking, RecipeMatch};
+    use crate::cooking_book::ingredient::Ingredient;
+    use crate::cooking_book::recipe::Recipe;
+    use crate::cooking_book::shopping_list::ShoppingList;
+    use std::collections::HashMap;
+
+    fn get_mocks() -> HashMap<String, Recipe> {
+        let mut all_ingredients: HashMap<String, Ingredient> = HashMap::new();
+        for line in &[
+            "Mehl;5;-1",
+            "Milch;2;-1;;119",
+            "Ei;2;-1;Eier;229",
+            "Zucker;5;-1",
+        ] {
+            let ingredient = Ingredient::new_by_line(line);
+            all_ingredients.insert(ingredient.name.to_string(), ingredient);
+        }
+
+        let mut recipes: HashMap<String, Recipe> = HashMap::new();
+        for line in &[
+            "Pfannkuchen;Mehl,300,G;Milch,150,ML;Ei,2,Stück",
+            "Rührei;Ei,3,Stück",
+            "Kuchen;Mehl,300,G;Zucker,100,G;Ei,3,Stück;Milch,100,ML",
+        ] {
+            let recipe = Recipe::new_by_line(line, &all_ingredients);
+            recipes.insert(recipe.name.to_string(), recipe);
+        }
+        return recipes;
+    }
+
+    fn names(matches: &Vec<RecipeMatch>) -> Vec<String> {
+        return matches.iter().map(|m| m.recipe.name.to_string()).collect();
+    }
+
+    #[test]
+    fn test_missing_count() {
+        let recipes = get_mocks();
+        let available = vec!["mehl".to_string(), "Zucker".to_string()];
+        let matches = RecipeMatch::find_all(
+            &recipes,
+            &available,
+            &ShoppingList::new(),
+            Ranking::MissingCount,
+        );
+
+        assert_eq!(names(&matches), vec!["Rührei", "Kuchen", "Pfannkuchen"]);
+        let missing: Vec<&str> = matches[2].missing.iter().map(|i| i.name.as_str()).collect();
+        assert_eq!(missing, vec!["Ei", "Milch"]);
+        assert_eq!(matches[2].missing_cost(), (348, 0));
+    }
+
+    #[test]
+    fn test_coverage() {
+        let recipes = get_mocks();
+        let available = vec!["Mehl".to_string(), "Zucker".to_string(), "Eier".to_string()];
+        let matches = RecipeMatch::find_all(
+            &recipes,
+            &available,
+            &ShoppingList::new(),
+            Ranking::Coverage,
+        );
+
+        assert_eq!(names(&matches), vec!["Rührei", "Kuchen", "Pfannkuchen"]);
+        assert_eq!(matches[1].coverage(), 0.75);
+    }
+
+    #[test]
+    fn test_shopping_list() {
+        let recipes = get_mocks();
+        let mut shopping_list = ShoppingList::new();
+        shopping_list.add_item(Ingredient::new_by_name("Milch".to_string()), 1);
+        shopping_list.add_item(Ingredient::new_by_name("Ei".to_string()), 1);
+
+        let available = vec!["Mehl".to_string()];
+        let matches =
+            RecipeMatch::find_all(&recipes, &available, &shopping_list, Ranking::ShoppingList);
+
+        assert_eq!(names(&matches), vec!["Pfannkuchen", "Rührei", "Kuchen"]);
+        assert_eq!(matches[0].missing_on_shopping_list, 2);
+    }
+
+    #[test]
+    fn test_sub_recipes() {
+        let mut recipes = get_mocks();
+        let all_ingredients: HashMap<String, Ingredient> = HashMap::new();
+        for line in &[
+            "Pfannkuchen mit Rührei;@Pfannkuchen,1/1;@Rührei,1/2",
+            "Kaputt;@Unbekannt,1/1",
+        ] {
+            let recipe = Recipe::new_by_line(line, &all_ingredients);
+            recipes.insert(recipe.name.to_string(), recipe);
+        }
+
+        let available = vec!["Ei".to_string()];
+        let matches = RecipeMatch::find_all(
+            &recipes,
+            &available,
+            &ShoppingList::new(),
+            Ranking::MissingCount,
+        );
+
+        assert_eq!(
+            names(&matches),
+            vec!["Rührei", "Pfannkuchen", "Pfannkuchen mit Rührei", "Kuchen"]
+        );
+        let missing: Vec<&str> = matches[2].missing.iter().map(|i| i.name.as_str()).collect();
+        assert_eq!(missing, vec!["Mehl", "Milch"]);
+        assert_eq!(matches[2].coverage(), 1.0 / 3.0);
+    }
+
+    #[test]
+    fn test_to_json() {
+        let recipes = get_mocks();
+        let available = vec!["Ei".to_string()];
+        let matches = RecipeMatch::find_all(
+            &recipes,
+            &available,
+            &ShoppingList::new(),
+            Ranking::MissingCount,
+        );
+
+        assert_eq!(
+            matches[0].to_json(),
+            "{\"name\": \"Rührei\", \"missing\": [], \"missing_count\": 0, \"missing_on_shopping_list\": 0, \"coverage\": 1.00, \"missing_cost\": 0, \"missing_unpriced\": 0}"
+        );
+    }
+}
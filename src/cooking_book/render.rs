@@ -0,0 +1,583 @@
+use crate::cooking_book::ingredient::Ingredient;
+use crate::cooking_book::locale::Locale;
+use crate::cooking_book::recipe::{IngredientLine, Recipe};
+use crate::cooking_book::shopping_list::ShoppingList;
+
+/// The formats recipes and the shopping list can be rendered in.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Format {
+    Json,
+    Text,
+    Markdown,
+    Html,
+}
+
+impl Format {
+    /// Returns the format by its name like `?format=md`, `None` for an unknown name.
+    ///
+    /// #Arguments
+    ///
+    /// * `name` The name: `json`, `text`, `txt`, `markdown`, `md` or `html`.
+    pub fn lookup_format(name: &str) -> Option<Format> {
+        return match name.to_lowercase().as_str() {
+            "json" => Some(Format::Json),
+            "text" | "txt" => Some(Format::Text),
+            "markdown" | "md" => Some(Format::Markdown),
+            "html" => Some(Format::Html),
+            _ => None,
+        };
+    }
+
+    /// Returns the format for a media type of an `Accept` header, `None` for an unknown type.
+    ///
+    /// #Arguments
+    ///
+    /// * `top` The top level type like `text`.
+    /// * `sub` The sub type like `markdown`.
+    pub fn lookup_media_type(top: &str, sub: &str) -> Option<Format> {
+        return match (top.to_lowercase().as_str(), sub.to_lowercase().as_str()) {
+            ("application", "json") | ("*", "*") | ("application", "*") => Some(Format::Json),
+            ("text", "plain") => Some(Format::Text),
+            ("text", "markdown") | ("text", "x-markdown") => Some(Format::Markdown),
+            ("text", "html") | ("text", "*") => Some(Format::Html),
+            _ => None,
+        };
+    }
+
+    /// Returns the media type of the format as top level and sub type.
+    pub fn media_type(&self) -> (&'static str, &'static str) {
+        return match self {
+            Format::Json => ("application", "json"),
+            Format::Text => ("text", "plain"),
+            Format::Markdown => ("text", "markdown"),
+            Format::Html => ("text", "html"),
+        };
+    }
+}
+
+/// Renders recipes, each with its ingredients, steps and other details.
+///
+/// #Arguments
+///
+/// * `recipes` The recipes to render.
+/// * `format` The format to render in.
+/// * `locale` The language of the labels.
+pub fn recipes(recipes: &Vec<&Recipe>, format: Format, locale: Locale) -> String {
+    return match format {
+        Format::Json => Recipe::all_to_json(recipes, locale),
+        Format::Text => {
+            let texts: Vec<String> = recipes.iter().map(|r| recipe_to_text(r, locale)).collect();
+            texts.join("\n\n")
+        }
+        Format::Markdown => {
+            let texts: Vec<String> = recipes
+                .iter()
+                .map(|r| recipe_to_markdown(r, locale))
+                .collect();
+            texts.join("\n---\n\n")
+        }
+        Format::Html => {
+            let title = match recipes.len() {
+                1 => recipes[0].name.to_string(),
+                _ => locale.text("label.recipes").to_string(),
+            };
+            let articles: Vec<String> = recipes.iter().map(|r| recipe_to_html(r, locale)).collect();
+            html_page(&title, &articles.join(""), locale)
+        }
+    };
+}
+
+/// Renders the shopping list with checkboxes, grouped by store and group.
+///
+/// #Arguments
+///
+/// * `shopping_list` The shopping list to render.
+/// * `format` The format to render in.
+/// * `locale` The language of the labels.
+pub fn shopping_list(shopping_list: &ShoppingList, format: Format, locale: Locale) -> String {
+    let mut ingredients: Vec<&Ingredient> = shopping_list.to_buy.keys().collect();
+    ingredients.sort();
+    let item = |ingredient: &Ingredient| -> String {
+        return match shopping_list.to_buy.get(ingredient) {
+            Some(amount) if *amount > 1 => format!("{} × {}", amount, ingredient.name),
+            _ => ingredient.name.to_string(),
+        };
+    };
+
+    let title = locale.text("label.shopping_list");
+    let nothing_to_buy = locale.text("label.nothing_to_buy");
+    let mut text = String::new();
+    match format {
+        Format::Json => return shopping_list.to_json(locale),
+        Format::Text => text.push_str(&format!("{}\n", title)),
+        Format::Markdown => text.push_str(&format!("# {}\n", title)),
+        Format::Html => text.push_str(&format!("<h1>{}</h1>", escape_html(title))),
+    }
+    if ingredients.is_empty() {
+        text.push_str(&match format {
+            Format::Html => format!("<p>{}</p>", escape_html(nothing_to_buy)),
+            _ => format!("\n{}\n", nothing_to_buy),
+        });
+    }
+
+    let mut last: Option<&Ingredient> = None;
+    for ingredient in ingredients {
+        let is_new_store = last.map_or(true, |l| l.preferred_store != ingredient.preferred_store);
+        let is_new_group = is_new_store || last.map_or(true, |l| l.group != ingredient.group);
+        if last.is_some() && is_new_group && format == Format::Html {
+            text.push_str("</ul>");
+        }
+        if last.is_some() && is_new_store && format == Format::Html {
+            text.push_str("</section>");
+        }
+        let store = ingredient.preferred_store.label(locale);
+        let group = ingredient.group.label(locale);
+        match format {
+            Format::Text => {
+                if is_new_store {
+                    text.push_str(&format!("\n{}\n", store.to_uppercase()));
+                }
+                if is_new_group {
+                    text.push_str(&format!("{}:\n", group));
+                }
+                text.push_str(&format!("[ ] {}\n", item(ingredient)));
+            }
+            Format::Markdown => {
+                if is_new_store {
+                    text.push_str(&format!("\n## {}\n", store));
+                }
+                if is_new_group {
+                    text.push_str(&format!("\n### {}\n\n", group));
+                }
+                text.push_str(&format!("- [ ] {}\n", item(ingredient)));
+            }
+            _ => {
+                if is_new_store {
+                    text.push_str(&format!("<section><h2>{}</h2>", escape_html(store)));
+                }
+                if is_new_group {
+                    text.push_str(&format!("<h3>{}</h3><ul>", escape_html(group)));
+                }
+                text.push_str(&format!(
+                    "<li><label><input type=\"checkbox\"> {}</label></li>",
+                    escape_html(&item(ingredient))
+                ));
+            }
+        }
+        last = Some(ingredient);
+    }
+
+    if format == Format::Html {
+        if last.is_some() {
+            text.push_str("</ul></section>");
+        }
+        return html_page(title, &text, locale);
+    }
+    return text;
+}
+
+/// Returns the facts of a recipe like the servings and times.
+fn facts(recipe: &Recipe, locale: Locale) -> Vec<String> {
+    let details = &recipe.details;
+    let mut facts: Vec<String> = Vec::new();
+    if let Some(servings) = details.servings {
+        facts.push(format!("{} {}", servings, locale.text("label.servings")));
+    }
+    if let Some(prep_time) = details.prep_time {
+        facts.push(format!(
+            "{} {} min",
+            locale.text("label.prep_time"),
+            prep_time
+        ));
+    }
+    if let Some(cook_time) = details.cook_time {
+        facts.push(format!(
+            "{} {} min",
+            locale.text("label.cook_time"),
+            cook_time
+        ));
+    }
+    if let (Some(_prep), Some(_cook), Some(total_time)) =
+        (details.prep_time, details.cook_time, details.total_time())
+    {
+        facts.push(format!(
+            "{} {} min",
+            locale.text("label.total_time"),
+            total_time
+        ));
+    }
+    if let Some(difficulty) = details.difficulty {
+        facts.push(format!(
+            "{} {}",
+            locale.text("label.difficulty"),
+            difficulty.label(locale)
+        ));
+    }
+    return facts;
+}
+
+/// Returns the tags of a recipe sorted by name.
+fn tags(recipe: &Recipe) -> Vec<&str> {
+    let mut tags: Vec<&str> = recipe.tags.iter().map(|t| t.as_str()).collect();
+    tags.sort();
+    return tags;
+}
+
+/// Returns the ingredients grouped by their sections, keeping their order.
+/// The sub recipes are added to the first group.
+fn ingredient_sections(recipe: &Recipe, locale: Locale) -> Vec<(&str, Vec<String>)> {
+    let mut sections: Vec<(&str, Vec<String>)> = Vec::new();
+    for line in &recipe.ingredients {
+        let is_new = sections.last().map_or(true, |(s, _l)| *s != line.section);
+        if is_new {
+            sections.push((&line.section, Vec::new()));
+        }
+        sections.last_mut().unwrap().1.push(ingredient_text(line));
+    }
+
+    let mut sub_recipes: Vec<(&String, &(u16, u16))> = recipe.sub_recipes.iter().collect();
+    sub_recipes.sort();
+    if !sub_recipes.is_empty() && sections.first().map_or(true, |(s, _l)| !s.is_empty()) {
+        sections.insert(0, ("", Vec::new()));
+    }
+    for (name, share) in sub_recipes {
+        sections[0].1.push(format!(
+            "{} {} {}",
+            Recipe::share_to_string(share),
+            locale.text("label.recipe"),
+            name
+        ));
+    }
+    return sections;
+}
+
+/// Returns an ingredient like `300 G Mehl, gesiebt`. The amount is left out if unknown.
+pub fn ingredient_text(line: &IngredientLine) -> String {
+    let mut text = match (line.amount, line.unit.as_str()) {
+        (0, _) => String::new(),
+        (amount, "") => format!("{} ", amount),
+        (amount, unit) => format!("{} {} ", amount, unit),
+    };
+    text.push_str(&line.ingredient.name);
+    if !line.note.is_empty() {
+        text.push_str(", ");
+        text.push_str(&line.note);
+    }
+    return text;
+}
+
+fn recipe_to_text(recipe: &Recipe, locale: Locale) -> String {
+    let details = &recipe.details;
+    let mut text = format!("{}\n", recipe.name.to_uppercase());
+    let tags = tags(recipe);
+    if !tags.is_empty() {
+        text.push_str(&format!("{}\n", tags.join(" ")));
+    }
+    let facts = facts(recipe, locale);
+    if !facts.is_empty() {
+        text.push_str(&format!("{}\n", facts.join(", ")));
+    }
+
+    text.push_str(&format!("\n{}\n", locale.text("label.ingredients")));
+    for (section, lines) in ingredient_sections(recipe, locale) {
+        if !section.is_empty() {
+            text.push_str(&format!("{}:\n", section));
+        }
+        for line in lines {
+            text.push_str(&format!("- {}\n", line));
+        }
+    }
+    if !details.equipment.is_empty() {
+        text.push_str(&format!(
+            "\n{}: {}\n",
+            locale.text("label.equipment"),
+            details.equipment.join(", ")
+        ));
+    }
+    if !details.steps.is_empty() {
+        text.push_str(&format!("\n{}\n", locale.text("label.preparation")));
+        for (i, step) in details.steps.iter().enumerate() {
+            text.push_str(&format!("{}. {}\n", i + 1, step.plain_text()));
+        }
+    }
+    if !details.notes.is_empty() {
+        text.push_str(&format!(
+            "\n{}\n{}\n",
+            locale.text("label.notes"),
+            details.notes
+        ));
+    }
+    if !details.source.is_empty() {
+        text.push_str(&format!(
+            "\n{}: {}\n",
+            locale.text("label.source"),
+            details.source
+        ));
+    }
+    return text;
+}
+
+fn recipe_to_markdown(recipe: &Recipe, locale: Locale) -> String {
+    let details = &recipe.details;
+    let mut text = format!("# {}\n\n", recipe.name);
+    let tags: Vec<String> = tags(recipe).iter().map(|t| format!("`{}`", t)).collect();
+    if !tags.is_empty() {
+        text.push_str(&format!("{}\n\n", tags.join(" ")));
+    }
+    let facts = facts(recipe, locale);
+    if !facts.is_empty() {
+        text.push_str(&format!("{}\n\n", facts.join(" · ")));
+    }
+
+    text.push_str(&format!("## {}\n\n", locale.text("label.ingredients")));
+    for (section, lines) in ingredient_sections(recipe, locale) {
+        if !section.is_empty() {
+            text.push_str(&format!("### {}\n\n", section));
+        }
+        for line in lines {
+            text.push_str(&format!("- {}\n", line));
+        }
+        text.push('\n');
+    }
+    if !details.equipment.is_empty() {
+        text.push_str(&format!(
+            "**{}:** {}\n\n",
+            locale.text("label.equipment"),
+            details.equipment.join(", ")
+        ));
+    }
+    if !details.steps.is_empty() {
+        text.push_str(&format!("## {}\n\n", locale.text("label.preparation")));
+        for (i, step) in details.steps.iter().enumerate() {
+            text.push_str(&format!("{}. {}\n", i + 1, step.plain_text()));
+        }
+        text.push('\n');
+    }
+    if !details.notes.is_empty() {
+        text.push_str(&format!(
+            "## {}\n\n{}\n\n",
+            locale.text("label.notes"),
+            details.notes
+        ));
+    }
+    if !details.source.is_empty() {
+        text.push_str(&format!(
+            "{}: {}\n\n",
+            locale.text("label.source"),
+            details.source
+        ));
+    }
+    text.pop();
+    return text;
+}
+
+fn recipe_to_html(recipe: &Recipe, locale: Locale) -> String {
+    let details = &recipe.details;
+    let mut html = format!("<article><h1>{}</h1>", escape_html(&recipe.name));
+    let tags = tags(recipe);
+    if !tags.is_empty() {
+        html.push_str(&format!(
+            "<p class=\"tags\">{}</p>",
+            escape_html(&tags.join(" "))
+        ));
+    }
+    let facts = facts(recipe, locale);
+    if !facts.is_empty() {
+        html.push_str(&format!("<p>{}</p>", escape_html(&facts.join(" · "))));
+    }
+
+    html.push_str(&format!("<h2>{}</h2>", locale.text("label.ingredients")));
+    for (section, lines) in ingredient_sections(recipe, locale) {
+        if !section.is_empty() {
+            html.push_str(&format!("<h3>{}</h3>", escape_html(section)));
+        }
+        html.push_str("<ul>");
+        for line in lines {
+            html.push_str(&format!(
+                "<li><label><input type=\"checkbox\"> {}</label></li>",
+                escape_html(&line)
+            ));
+        }
+        html.push_str("</ul>");
+    }
+    if !details.equipment.is_empty() {
+        html.push_str(&format!(
+            "<p><strong>{}:</strong> {}</p>",
+            locale.text("label.equipment"),
+            escape_html(&details.equipment.join(", "))
+        ));
+    }
+    if !details.steps.is_empty() {
+        html.push_str(&format!(
+            "<h2>{}</h2><ol>",
+            locale.text("label.preparation")
+        ));
+        for step in &details.steps {
+            html.push_str(&format!("<li>{}</li>", escape_html(&step.plain_text())));
+        }
+        html.push_str("</ol>");
+    }
+    if !details.notes.is_empty() {
+        html.push_str(&format!(
+            "<h2>{}</h2><p>{}</p>",
+            locale.text("label.notes"),
+            escape_html(&details.notes).replace('\n', "<br>")
+        ));
+    }
+    if !details.source.is_empty() {
+        html.push_str(&format!(
+            "<p>{}: {}</p>",
+            locale.text("label.source"),
+            escape_html(&details.source)
+        ));
+    }
+    html.push_str("</article>");
+    return html;
+}
+
+/// Returns a complete HTML page for printing.
+fn html_page(title: &str, body: &str, locale: Locale) -> String {
+    return format!(
+        "<!DOCTYPE html>\n<html lang=\"{}\"><head><meta charset=\"utf-8\"><title>{}</title><style>{}</style></head><body>{}</body></html>\n",
+        locale.code(),
+        escape_html(title),
+        PRINT_STYLE,
+        body
+    );
+}
+
+/// Keeps the lists compact and sections together when printing.
+const PRINT_STYLE: &'static str = "body{font-family:sans-serif;max-width:40em;margin:1em auto}\
+ul{list-style:none;padding-left:0}li{margin:.2em 0}h2,h3{break-after:avoid}\
+section,li{break-inside:avoid}article+article{break-before:page}.tags{color:#555}\
+@media print{body{margin:0;font-size:11pt}input{width:1em;height:1em}}";
+
+/// Escapes the characters with a special meaning in HTML.
+fn escape_html(text: &str) -> String {
+    return text
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{recipes, shopping_list, Format};
+    use crate::cooking_book::group::Group;
+    use crate::cooking_book::ingredient::Ingredient;
+    use crate::cooking_book::locale::Locale;
+    use crate::cooking_book::recipe::Recipe;
+    use crate::cooking_book::recipe_details::RecipeDetails;
+    use crate::cooking_book::shopping_list::ShoppingList;
+    use crate::cooking_book::store::Store;
+    use std::collections::HashMap;
+
+    fn get_recipe() -> Recipe {
+        let mut recipe = Recipe::new_by_line(
+            "Pfannkuchen;Mehl,300,G,gesiebt;Salz,0,;[Zum Servieren];Apfelmus,1,Glas;@Sirup,1/2;#Breakfast",
+            &HashMap::new(),
+        );
+        let (details, _problems) = RecipeDetails::new_by_text(
+            "servings: 2\nprep_time: 10\ncook_time: 20\nequipment: Pfanne\nstep: {Mehl} verrühren, {10 min} ruhen lassen\nstep: Backen\nsource: Oma <Erna>",
+        );
+        recipe.details = details;
+        return recipe;
+    }
+
+    fn get_shopping_list() -> ShoppingList {
+        let mut shopping_list = ShoppingList::new();
+        let mut ingredient = |name: &str, group: Group, store: Store, amount: u16| {
+            let mut ingredient = Ingredient::new_by_name(name.to_string());
+            ingredient.group = group;
+            ingredient.preferred_store = store;
+            shopping_list.add_item(ingredient, amount);
+        };
+        ingredient("Tomaten", Group::Vegetable, Store::Rewe, 2);
+        ingredient("Gurke", Group::Vegetable, Store::Rewe, 1);
+        ingredient("Mehl", Group::Baking, Store::Rewe, 1);
+        ingredient("Zahnpasta", Group::Other, Store::DM, 1);
+        return shopping_list;
+    }
+
+    #[test]
+    fn test_lookup() {
+        assert_eq!(Format::lookup_format("MD"), Some(Format::Markdown));
+        assert_eq!(Format::lookup_format("pdf"), None);
+        assert_eq!(Format::lookup_media_type("*", "*"), Some(Format::Json));
+        assert_eq!(
+            Format::lookup_media_type("text", "html"),
+            Some(Format::Html)
+        );
+        assert_eq!(Format::lookup_media_type("image", "png"), None);
+        assert_eq!(Format::Markdown.media_type(), ("text", "markdown"));
+    }
+
+    #[test]
+    fn test_recipe_text() {
+        let recipe = get_recipe();
+        assert_eq!(
+            recipes(&vec![&recipe], Format::Text, Locale::De),
+            "PFANNKUCHEN\n#Breakfast\n2 Portionen, Vorbereitung 10 min, Kochen 20 min, Gesamt 30 min\n\nZutaten\n- 300 G Mehl, gesiebt\n- Salz\n- 1/2 Rezept Sirup\nZum Servieren:\n- 1 Glas Apfelmus\n\nAusstattung: Pfanne\n\nZubereitung\n1. Mehl verrühren, 10 min ruhen lassen\n2. Backen\n\nQuelle: Oma <Erna>\n"
+        );
+    }
+
+    #[test]
+    fn test_recipe_text_english() {
+        let recipe = get_recipe();
+        let text = recipes(&vec![&recipe], Format::Text, Locale::En);
+        assert!(text.contains(
+            "2 servings, Preparation 10 min, Cooking 20 min, Total 30 min\n\nIngredients\n"
+        ));
+        assert!(text.contains("- 1/2 recipe Sirup\n"));
+        assert!(text.ends_with("\nSource: Oma <Erna>\n"));
+        assert!(recipes(&vec![&recipe], Format::Html, Locale::En).contains("<html lang=\"en\">"));
+    }
+
+    #[test]
+    fn test_recipe_markdown() {
+        let recipe = get_recipe();
+        assert_eq!(
+            recipes(&vec![&recipe], Format::Markdown, Locale::De),
+            "# Pfannkuchen\n\n`#Breakfast`\n\n2 Portionen · Vorbereitung 10 min · Kochen 20 min · Gesamt 30 min\n\n## Zutaten\n\n- 300 G Mehl, gesiebt\n- Salz\n- 1/2 Rezept Sirup\n\n### Zum Servieren\n\n- 1 Glas Apfelmus\n\n**Ausstattung:** Pfanne\n\n## Zubereitung\n\n1. Mehl verrühren, 10 min ruhen lassen\n2. Backen\n\nQuelle: Oma <Erna>\n"
+        );
+    }
+
+    #[test]
+    fn test_recipe_html() {
+        let recipe = get_recipe();
+        let html = recipes(&vec![&recipe, &recipe], Format::Html, Locale::De);
+        assert!(html.starts_with("<!DOCTYPE html>\n<html lang=\"de\"><head><meta charset=\"utf-8\"><title>Rezepte</title>"));
+        assert_eq!(html.matches("<article>").count(), 2);
+        assert!(html.contains("<h3>Zum Servieren</h3><ul><li><label><input type=\"checkbox\"> 1 Glas Apfelmus</label></li></ul>"));
+        assert!(html.contains("<p>Quelle: Oma &lt;Erna&gt;</p>"));
+        assert_eq!(
+            recipes(&vec![&recipe], Format::Json, Locale::En),
+            Recipe::all_to_json(&vec![&recipe], Locale::En)
+        );
+    }
+
+    #[test]
+    fn test_shopping_list() {
+        let list = get_shopping_list();
+        assert_eq!(
+            shopping_list(&list, Format::Text, Locale::De),
+            "Einkaufsliste\n\nREWE\nGemüse:\n[ ] Gurke\n[ ] 2 × Tomaten\nBackzutat:\n[ ] Mehl\n\nDM\nAnderes:\n[ ] Zahnpasta\n"
+        );
+        assert_eq!(
+            shopping_list(&list, Format::Markdown, Locale::De),
+            "# Einkaufsliste\n\n## Rewe\n\n### Gemüse\n\n- [ ] Gurke\n- [ ] 2 × Tomaten\n\n### Backzutat\n\n- [ ] Mehl\n\n## DM\n\n### Anderes\n\n- [ ] Zahnpasta\n"
+        );
+        let html = shopping_list(&list, Format::Html, Locale::De);
+        assert!(html.contains("<body><h1>Einkaufsliste</h1><section><h2>Rewe</h2><h3>Gemüse</h3><ul><li><label><input type=\"checkbox\"> Gurke</label></li><li><label><input type=\"checkbox\"> 2 × Tomaten</label></li></ul><h3>Backzutat</h3><ul><li><label><input type=\"checkbox\"> Mehl</label></li></ul></section><section><h2>DM</h2>"));
+        assert!(html.ends_with("</ul></section></body></html>\n"));
+        assert_eq!(
+            shopping_list(&ShoppingList::new(), Format::Text, Locale::De),
+            "Einkaufsliste\n\nNichts zu kaufen.\n"
+        );
+        assert_eq!(
+            shopping_list(&list, Format::Text, Locale::En),
+            "Shopping list\n\nREWE\nVegetables:\n[ ] Gurke\n[ ] 2 × Tomaten\nBaking:\n[ ] Mehl\n\nDM\nOther:\n[ ] Zahnpasta\n"
+        );
+    }
+}
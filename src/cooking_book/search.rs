@@ -0,0 +1,163 @@
+use std::cmp;
+
+/// Normalises a text for comparison: lower case, umlauts without dots and ß as ss.
+///
+/// #Arguments
+///
+/// * `text` The text to normalise.
+pub fn normalize(text: &str) -> String {
+    let mut normalized = String::with_capacity(text.len());
+    for c in text.trim().to_lowercase().chars() {
+        match c {
+            'ä' => normalized.push('a'),
+            'ö' => normalized.push('o'),
+            'ü' => normalized.push('u'),
+            'ß' => normalized.push_str("ss"),
+            _ => normalized.push(c),
+        }
+    }
+    return normalized;
+}
+
+/// Returns the Levenshtein distance between two texts.
+///
+/// #Arguments
+///
+/// * `a` The first text.
+/// * `b` The second text.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current: Vec<usize> = vec![0; b.len() + 1];
+
+    for (i, ca) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + if ca == *cb { 0 } else { 1 };
+            current[j + 1] = cmp::min(substitution, cmp::min(previous[j + 1], current[j]) + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    return previous[b.len()];
+}
+
+/// Returns how well a candidate matches the query, lower is better.
+/// An equal text scores 0, a prefix 1, a contained text 2, a misspelling 3 plus its distance
+/// and a misspelled prefix 4 plus its distance.
+/// Returns `None` if they don't match at all.
+///
+/// #Arguments
+///
+/// * `query` The text searched for.
+/// * `candidate` The text to compare with.
+pub fn score(query: &str, candidate: &str) -> Option<usize> {
+    let query = normalize(query);
+    let candidate = normalize(candidate);
+
+    if query.is_empty() {
+        return None;
+    }
+    if query == candidate {
+        return Some(0);
+    }
+    if candidate.starts_with(&query) {
+        return Some(1);
+    }
+    if candidate.contains(&query) {
+        return Some(2);
+    }
+
+    let allowed = query.chars().count() / 4;
+    let distance = edit_distance(&query, &candidate);
+    if distance <= allowed {
+        return Some(3 + distance);
+    }
+
+    let prefix: String = candidate.chars().take(query.chars().count()).collect();
+    let distance = edit_distance(&query, &prefix);
+    if distance <= allowed {
+        return Some(4 + distance);
+    }
+    return None;
+}
+
+/// Returns whether two names probably mean the same, apart from case, umlauts or a single typo.
+///
+/// #Arguments
+///
+/// * `a` The first name.
+/// * `b` The second name.
+pub fn is_near_duplicate(a: &str, b: &str) -> bool {
+    let a = normalize(a);
+    let b = normalize(b);
+    if a == b {
+        return true;
+    }
+    return cmp::min(a.chars().count(), b.chars().count()) >= 5 && edit_distance(&a, &b) <= 1;
+}
+
+/// Returns the candidates matching the query, best match first.
+/// Candidates with equal score are sorted by name.
+///
+/// #Arguments
+///
+/// * `query` The text searched for.
+/// * `candidates` The names to search with the value to return for them.
+pub fn rank<'a, T>(query: &str, candidates: Vec<(&'a str, T)>) -> Vec<T> {
+    let mut scored: Vec<(usize, &'a str, T)> = Vec::new();
+    for (name, value) in candidates {
+        if let Some(s) = score(query, name) {
+            scored.push((s, name, value));
+        }
+    }
+    scored.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(b.1)));
+    return scored.into_iter().map(|(_s, _n, value)| value).collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{edit_distance, is_near_duplicate, normalize, rank, score};
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("Käse"), "kase");
+        assert_eq!(normalize(" Grüße "), "grusse");
+        assert_eq!(normalize("ÖL"), "ol");
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("abc", ""), 3);
+        assert_eq!(edit_distance("zwibel", "zwiebel"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn test_score() {
+        assert_eq!(score("pfannkuchen", "Pfannkuchen"), Some(0));
+        assert_eq!(score("Kase", "Käse"), Some(0));
+        assert_eq!(score("Zwie", "Zwiebel"), Some(1));
+        assert_eq!(score("kuchen", "Pfannkuchen"), Some(2));
+        assert_eq!(score("Zwibel", "Zwiebel"), Some(4));
+        assert_eq!(score("Zwib", "Zwiebel"), Some(5));
+        assert_eq!(score("Reis", "Mais"), None);
+        assert_eq!(score("", "Mais"), None);
+    }
+
+    #[test]
+    fn test_is_near_duplicate() {
+        assert!(is_near_duplicate("kase", "Käse"));
+        assert!(is_near_duplicate("Zwibel", "Zwiebel"));
+        assert!(!is_near_duplicate("Mais", "Reis"));
+        assert!(!is_near_duplicate("Ei", "Eis"));
+    }
+
+    #[test]
+    fn test_rank() {
+        let candidates = vec![("Zwiebel", 1), ("Zucchini", 2), ("Frühlingszwiebel", 3)];
+        assert_eq!(rank("zwiebel", candidates.clone()), vec![1, 3]);
+        assert_eq!(rank("z", candidates.clone()), vec![2, 1, 3]);
+        assert_eq!(rank("Zwibel", candidates), vec![1]);
+    }
+}
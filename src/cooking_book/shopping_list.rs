@@ -1,7 +1,14 @@
 use std::collections::HashMap;
 
+use crate::cooking_book::group::Group;
+use crate::cooking_book::ingredient::Ingredient;
+use crate::cooking_book::ingredient_parser;
+use crate::cooking_book::locale::Locale;
+use crate::cooking_book::pantry::Pantry;
+use crate::cooking_book::recipe::Recipe;
+use crate::cooking_book::store::Store;
+use crate::cooking_book::unit;
 use crate::file_access::persistency;
-use crate::Ingredient;
 
 /// The shopping list
 #[derive(PartialEq, Eq)]
@@ -27,6 +34,174 @@ impl ShoppingList {
         *amount += 1;
     }
 
+    /// Add the number of items needed for an amount like `2 L` to the shopping list.
+    /// See `count_items`.
+    ///
+    /// #Arguments
+    ///
+    /// * `ingredient` The ingredient to buy.
+    /// * `amount` The amount needed, 0 if unknown.
+    /// * `unit` The unit of the amount.
+    pub fn add_amount(&mut self, ingredient: &Ingredient, amount: u16, unit: &str) {
+        let count = self.to_buy.entry(ingredient.clone()).or_insert(0);
+        *count = count.saturating_add(ShoppingList::count_items(amount, unit));
+    }
+
+    /// Adds an ingredient by its name or alias, or free text like `2 Liter Milch` or
+    /// `Salz und Pfeffer` with the number of items needed, see `parse_text` and `add_entries`.
+    /// Nothing is added if a new ingredient would get an invalid name.
+    ///
+    /// #Arguments
+    ///
+    /// * `text` The name or free text to add.
+    /// * `all_ingredients` The collection of all ingredients, new ingredients are added to it.
+    pub fn add_text(
+        &mut self,
+        text: &str,
+        all_ingredients: &mut HashMap<String, Ingredient>,
+    ) -> Result<(), String> {
+        let entries = ShoppingList::parse_text(text, all_ingredients)?;
+        return self.add_entries(entries, all_ingredients);
+    }
+
+    /// Parses an ingredient by its name or alias, or free text like `2 Liter Milch` or
+    /// `Salz und Pfeffer`, into the names, amounts and units to add. Only exact names and
+    /// aliases are resolved to existing ingredients, every other name is a new ingredient.
+    /// Near duplicates like `Kase` for `Käse` are offered by `Ingredient::suggest` instead.
+    /// Returns an error if a new ingredient would get an invalid name.
+    ///
+    /// #Arguments
+    ///
+    /// * `text` The name or free text to parse.
+    /// * `all_ingredients` The collection of all ingredients.
+    pub fn parse_text(
+        text: &str,
+        all_ingredients: &HashMap<String, Ingredient>,
+    ) -> Result<Vec<(String, u16, String)>, String> {
+        let entries: Vec<(String, u16, String)> =
+            match Ingredient::resolve(all_ingredients, text) {
+                Some(ingredient) => vec![(ingredient.name.to_string(), 0, String::new())],
+                None => {
+                    let parsed = ingredient_parser::parse_all(text);
+                    if parsed.is_empty() {
+                        vec![(text.to_string(), 0, String::new())]
+                    } else {
+                        parsed
+                            .into_iter()
+                            .map(|p| (p.name, p.amount, p.unit))
+                            .collect()
+                    }
+                }
+            };
+
+        let mut resolved: Vec<(String, u16, String)> = Vec::with_capacity(entries.len());
+        for (name, amount, unit) in entries {
+            let name = match Ingredient::resolve(all_ingredients, &name) {
+                Some(ingredient) => ingredient.name.to_string(),
+                None if Ingredient::is_valid_name(&name) => name,
+                None => return Err(format!("Invalid ingredient name '{}'", name)),
+            };
+            resolved.push((name, amount, unit));
+        }
+        return Ok(resolved);
+    }
+
+    /// Adds the entries of `parse_text`. Ingredients which don't exist yet are created.
+    /// Returns the first error persisting a new ingredient, the other ingredients are added anyway.
+    ///
+    /// #Arguments
+    ///
+    /// * `entries` The names, amounts and units to add.
+    /// * `all_ingredients` The collection of all ingredients, new ingredients are added to it.
+    pub fn add_entries(
+        &mut self,
+        entries: Vec<(String, u16, String)>,
+        all_ingredients: &mut HashMap<String, Ingredient>,
+    ) -> Result<(), String> {
+        let mut result = Ok(());
+        for (name, amount, unit) in entries {
+            if !all_ingredients.contains_key(&name) {
+                if let Err(e) = Ingredient::persist_new_ingredient(&name, all_ingredients) {
+                    result = result.and(Err(e));
+                }
+            }
+            if let Some(ingredient) = all_ingredients.get(&name) {
+                self.add_amount(ingredient, amount, &unit);
+            }
+        }
+        return result;
+    }
+
+    /// Returns the number of items to buy for an amount. Weights and volumes are counted
+    /// per started kilogram or liter, spoons and pinches as one item and everything else
+    /// like pieces or cans by their amount. At least one item is bought.
+    ///
+    /// #Arguments
+    ///
+    /// * `amount` The amount needed, 0 if unknown.
+    /// * `unit` The unit of the amount.
+    pub fn count_items(amount: u16, unit: &str) -> u16 {
+        let count = unit::convert(amount as u32, unit, "KG")
+            .or_else(|| unit::convert(amount as u32, unit, "L"))
+            .unwrap_or(amount as u32);
+        let is_small = ["EL", "TL", "Prise"]
+            .iter()
+            .any(|u| u.to_lowercase() == unit.to_lowercase());
+        if count == 0 || is_small {
+            return 1;
+        }
+        if count > u16::max_value() as u32 {
+            return u16::max_value();
+        }
+        return count as u16;
+    }
+
+    /// Add every ingredient of the recipes which is not sufficiently at home.
+    /// Ingredients used by several recipes are summed up before comparing with the pantry.
+    /// Returns the number of ingredients added.
+    ///
+    /// #Arguments
+    ///
+    /// * `recipes` The recipes to cook.
+    /// * `pantry` The ingredients at home.
+    pub fn add_shortfall(&mut self, recipes: &Vec<&Recipe>, pantry: &Pantry) -> usize {
+        let mut needed: Vec<(&Ingredient, u32, String)> = Vec::new();
+        for recipe in recipes {
+            for line in &recipe.ingredients {
+                let (i, a, u) = (&line.ingredient, &line.amount, &line.unit);
+                let existing = needed
+                    .iter_mut()
+                    .find(|(n, _a, nu)| n.name == i.name && unit::convert(0, nu, u).is_some());
+                match existing {
+                    Some(existing) => {
+                        let smaller = unit::smaller_unit(&existing.2, u).to_string();
+                        existing.1 = unit::convert(existing.1, &existing.2, &smaller).unwrap()
+                            + unit::convert(*a as u32, u, &smaller).unwrap();
+                        existing.2 = smaller;
+                    }
+                    None => needed.push((i, *a as u32, u.to_string())),
+                }
+            }
+        }
+
+        let mut added: Vec<&String> = Vec::new();
+        for (i, a, u) in needed {
+            if added.contains(&&i.name) {
+                continue;
+            }
+            let amount = if a > u16::max_value() as u32 {
+                u16::max_value()
+            } else {
+                a as u16
+            };
+            if pantry.shortfall(&i.name, amount, &u) > 0 {
+                self.add_or_increment(i);
+                added.push(&i.name);
+            }
+        }
+        return added.len();
+    }
+
     /// Add an item with amount to the shopping list.
     pub fn add_item(&mut self, ingredient: Ingredient, amount: u16) {
         self.to_buy.insert(ingredient, amount);
@@ -46,45 +221,101 @@ impl ShoppingList {
         self.to_buy.remove(&ingredient);
     }
 
-    /// Exports the shopping list to json.
-    pub fn to_json(&self) -> String {
+    /// Remove an item from the shopping list by the name of its ingredient.
+    /// This also removes items whose ingredient is missing in the catalog.
+    ///
+    /// #Arguments
+    ///
+    /// * `name` The name of the ingredient to remove from the list.
+    pub fn remove_by_name(&mut self, name: &str) {
+        let ingredient = self.to_buy.keys().find(|i| i.name == name).cloned();
+        if let Some(ingredient) = ingredient {
+            self.remove(&ingredient);
+        }
+    }
+
+    /// Replace an ingredient by another one. If the replacement is already on the list,
+    /// the amounts are summed up.
+    ///
+    /// #Arguments
+    ///
+    /// * `name` The name of the ingredient to replace.
+    /// * `replacement` The ingredient to use instead.
+    pub fn replace_ingredient(&mut self, name: &str, replacement: &Ingredient) {
+        let old = match self.to_buy.keys().find(|i| i.name == name) {
+            Some(i) => i.clone(),
+            None => return,
+        };
+        let amount = self.to_buy.remove(&old).unwrap();
+
+        let existing = self
+            .to_buy
+            .keys()
+            .find(|i| i.name == replacement.name)
+            .cloned();
+        let existing_amount = match existing {
+            Some(existing) => self.to_buy.remove(&existing).unwrap(),
+            None => 0,
+        };
+
+        self.to_buy
+            .insert(replacement.clone(), amount + existing_amount);
+    }
+
+    /// Exports the shopping list to json, grouped by store and group.
+    /// Stores and groups are exported by their id and label, like
+    /// `[{"store": "any", "label": "Überall", "groups": [{"group": "fruit", "label": "Obst", "items": [...]}]}]`.
+    ///
+    /// #Arguments
+    ///
+    /// * `locale` The language of the labels.
+    pub fn to_json(&self, locale: Locale) -> String {
         let mut keys: Vec<&Ingredient> = self.to_buy.keys().collect();
         if keys.is_empty() {
-            return "{}".to_string();
+            return "[]".to_string();
         }
 
         keys.sort();
 
         let first_entry = keys.first().unwrap();
-        let store = first_entry.preferred_store;
+        let mut store = first_entry.preferred_store;
         let mut category = first_entry.group;
 
-        let mut json: String = String::new();
-        json.push_str("{\"");
-        json.push_str(&format!("{:?}", store));
-        json.push_str("\": {\"");
+        let store_start = |store: Store| -> String {
+            return format!(
+                "{{\"store\": \"{}\", \"label\": \"{}\", \"groups\": [",
+                store.id(),
+                store.label(locale)
+            );
+        };
+        let group_start = |group: Group| -> String {
+            return format!(
+                "{{\"group\": \"{}\", \"label\": \"{}\", \"items\": [",
+                group.id(),
+                group.label(locale)
+            );
+        };
 
-        json.push_str(&format!("{:}", category));
-        json.push_str("\": [");
+        let mut json: String = String::new();
+        json.push('[');
+        json.push_str(&store_start(store));
+        json.push_str(&group_start(category));
 
         let mut is_first: bool = true;
         for i in keys {
             if i.preferred_store != store {
-                let store = i.preferred_store;
+                store = i.preferred_store;
                 category = i.group;
-                json.push_str("]}, \"");
-                json.push_str(&format!("{:?}", store));
-                json.push_str("\": {\"");
-                json.push_str(&format!("{:}", category));
-                json.push_str("\": [");
+                json.push_str("]}]}, ");
+                json.push_str(&store_start(store));
+                json.push_str(&group_start(category));
                 is_first = true;
             }
 
             if i.group != category {
                 category = i.group;
-                json.push_str("], \"");
-                json.push_str(&format!("{:}", category));
-                json.push_str("\": [");
+                json.push_str("]}, ");
+                json.push_str(&group_start(category));
                 is_first = true;
             }
 
@@ -105,7 +336,7 @@ impl ShoppingList {
             is_first = false;
         }
 
-        json.push_str("]}}");
+        json.push_str("]}]}]");
         return json;
     }
 }
@@ -115,7 +346,11 @@ mod tests {
     use super::ShoppingList;
     use crate::cooking_book::group::Group;
     use crate::cooking_book::ingredient::Ingredient;
+    use crate::cooking_book::locale::Locale;
+    use crate::cooking_book::pantry::Pantry;
+    use crate::cooking_book::recipe::Recipe;
     use crate::cooking_book::store::Store;
+    use std::collections::HashMap;
 
     #[test]
     fn test_add_or_increment() {
@@ -140,6 +375,71 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_add_amount() {
+        let milch = Ingredient::new_by_name("Milch".to_string());
+        let mut shopping_list = ShoppingList::new();
+
+        shopping_list.add_amount(&milch, 2, "L");
+        shopping_list.add_amount(&milch, 1500, "ML");
+        assert_eq!(shopping_list.to_buy.get(&milch), Some(&4));
+
+        assert_eq!(ShoppingList::count_items(3, ""), 3);
+        assert_eq!(ShoppingList::count_items(2, "Dose"), 2);
+        assert_eq!(ShoppingList::count_items(200, "G"), 1);
+        assert_eq!(ShoppingList::count_items(3, "el"), 1);
+        assert_eq!(ShoppingList::count_items(0, ""), 1);
+    }
+
+    #[test]
+    fn test_parse_text() {
+        let mut all_ingredients: HashMap<String, Ingredient> = HashMap::new();
+        all_ingredients.insert("Ei".to_string(), Ingredient::new_by_line("Ei;0;-1;Eier"));
+
+        assert_eq!(
+            ShoppingList::parse_text("Eier", &all_ingredients),
+            Ok(vec![("Ei".to_string(), 0, String::new())])
+        );
+        assert_eq!(
+            ShoppingList::parse_text("2 Liter Milch", &all_ingredients),
+            Ok(vec![("Milch".to_string(), 2, "L".to_string())])
+        );
+        assert_eq!(
+            ShoppingList::parse_text("#Milch", &all_ingredients),
+            Err("Invalid ingredient name '#Milch'".to_string())
+        );
+    }
+
+    #[test]
+    fn test_add_shortfall() {
+        let all_ingredients: HashMap<String, Ingredient> = HashMap::new();
+        let pancake = Recipe::new_by_line(
+            "Pfannkuchen;Mehl,300,G;Milch,150,ML;Ei,2,Stück",
+            &all_ingredients,
+        );
+        let waffle = Recipe::new_by_line("Waffel;Mehl,1,KG;Milch,150,ML", &all_ingredients);
+
+        let mut pantry = Pantry::new();
+        pantry.set(Ingredient::new_by_name("Mehl".to_string()), 1, "KG", None);
+        pantry.set(Ingredient::new_by_name("Milch".to_string()), 1, "L", None);
+        pantry.set(Ingredient::new_by_name("Ei".to_string()), 2, "Stück", None);
+
+        let mut shopping_list = ShoppingList::new();
+        assert_eq!(shopping_list.add_shortfall(&vec![&pancake], &pantry), 0);
+        assert!(shopping_list.to_buy.is_empty());
+
+        assert_eq!(
+            shopping_list.add_shortfall(&vec![&pancake, &waffle], &pantry),
+            1
+        );
+        assert_eq!(
+            shopping_list
+                .to_buy
+                .get(&Ingredient::new_by_name("Mehl".to_string())),
+            Some(&1)
+        );
+    }
+
     #[test]
     fn test_remove() {
         let ingredient = Ingredient::new_by_name("Banane".to_string());
@@ -156,6 +456,35 @@ mod tests {
         assert!(shopping_list.to_buy.is_empty());
     }
 
+    #[test]
+    fn test_remove_by_name() {
+        let mut shopping_list = ShoppingList::new();
+        shopping_list.add_item(Ingredient::new_by_name("Banane".to_string()), 2);
+
+        shopping_list.remove_by_name("Gurke");
+        assert_eq!(shopping_list.to_buy.len(), 1);
+
+        shopping_list.remove_by_name("Banane");
+        assert!(shopping_list.to_buy.is_empty());
+    }
+
+    #[test]
+    fn test_replace_ingredient() {
+        let magarine = Ingredient::new_by_name("Magarine".to_string());
+        let margarine = Ingredient::new_by_name("Margarine".to_string());
+        let mut shopping_list = ShoppingList::new();
+        shopping_list.add_item(magarine.clone(), 2);
+
+        shopping_list.replace_ingredient("Magarine", &margarine);
+        assert!(!shopping_list.to_buy.contains_key(&magarine));
+        assert_eq!(shopping_list.to_buy.get(&margarine), Some(&2));
+
+        shopping_list.add_item(magarine.clone(), 3);
+        shopping_list.replace_ingredient("Magarine", &margarine);
+        assert_eq!(shopping_list.to_buy.len(), 1);
+        assert_eq!(shopping_list.to_buy.get(&margarine), Some(&5));
+    }
+
     #[test]
     fn test_to_json_1() {
         let ingredient1 = Ingredient::new_by_name("Banane".to_string());
@@ -165,8 +494,8 @@ mod tests {
         shopping_list.add_or_increment(&ingredient2);
 
         assert_eq!(
-            shopping_list.to_json(),
-            "{\"Any\": {\"Anderes\": [{\"name\": \"Banane\"}, {\"name\": \"Gurke\"}]}}"
+            shopping_list.to_json(Locale::De),
+            "[{\"store\": \"any\", \"label\": \"Überall\", \"groups\": [{\"group\": \"other\", \"label\": \"Anderes\", \"items\": [{\"name\": \"Banane\"}, {\"name\": \"Gurke\"}]}]}]"
         );
     }
 
@@ -180,8 +509,8 @@ mod tests {
         shopping_list.add_or_increment(&ingredient2);
 
         assert_eq!(
-            shopping_list.to_json(),
-            "{\"Any\": {\"Anderes\": [{\"name\": \"Banane\"}, {\"name\": \"Gurke\", \"amount\": 2}]}}"
+            shopping_list.to_json(Locale::De),
+            "[{\"store\": \"any\", \"label\": \"Überall\", \"groups\": [{\"group\": \"other\", \"label\": \"Anderes\", \"items\": [{\"name\": \"Banane\"}, {\"name\": \"Gurke\", \"amount\": 2}]}]}]"
         );
     }
 
@@ -191,19 +520,23 @@ mod tests {
             name: "Banane".to_string(),
             group: Group::Fruit,
             preferred_store: Store::Any,
+            aliases: Vec::new(),
+            price: None,
         };
         let ingredient2 = Ingredient {
             name: "Gurke".to_string(),
             group: Group::Vegetable,
             preferred_store: Store::Any,
+            aliases: Vec::new(),
+            price: None,
         };
         let mut shopping_list = ShoppingList::new();
         shopping_list.add_or_increment(&ingredient1);
         shopping_list.add_or_increment(&ingredient2);
 
         assert_eq!(
-            shopping_list.to_json(),
-            "{\"Any\": {\"Gemüse\": [{\"name\": \"Gurke\"}], \"Obst\": [{\"name\": \"Banane\"}]}}"
+            shopping_list.to_json(Locale::En),
+            "[{\"store\": \"any\", \"label\": \"Anywhere\", \"groups\": [{\"group\": \"vegetable\", \"label\": \"Vegetables\", \"items\": [{\"name\": \"Gurke\"}]}, {\"group\": \"fruit\", \"label\": \"Fruit\", \"items\": [{\"name\": \"Banane\"}]}]}]"
         );
     }
 
@@ -213,19 +546,23 @@ mod tests {
             name: "Banane".to_string(),
             group: Group::Other,
             preferred_store: Store::Any,
+            aliases: Vec::new(),
+            price: None,
         };
         let ingredient2 = Ingredient {
             name: "Gurke".to_string(),
             group: Group::Other,
             preferred_store: Store::DM,
+            aliases: Vec::new(),
+            price: None,
         };
         let mut shopping_list = ShoppingList::new();
         shopping_list.add_or_increment(&ingredient1);
         shopping_list.add_or_increment(&ingredient2);
 
         assert_eq!(
-            shopping_list.to_json(),
-            "{\"Any\": {\"Anderes\": [{\"name\": \"Banane\"}]}, \"DM\": {\"Anderes\": [{\"name\": \"Gurke\"}]}}"
+            shopping_list.to_json(Locale::De),
+            "[{\"store\": \"any\", \"label\": \"Überall\", \"groups\": [{\"group\": \"other\", \"label\": \"Anderes\", \"items\": [{\"name\": \"Banane\"}]}]}, {\"store\": \"dm\", \"label\": \"DM\", \"groups\": [{\"group\": \"other\", \"label\": \"Anderes\", \"items\": [{\"name\": \"Gurke\"}]}]}]"
         );
     }
 
@@ -233,6 +570,6 @@ mod tests {
     fn test_to_json_5() {
         let shopping_list = ShoppingList::new();
 
-        assert_eq!(shopping_list.to_json(), "{}");
+        assert_eq!(shopping_list.to_json(Locale::De), "[]");
     }
 }
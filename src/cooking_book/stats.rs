@@ -0,0 +1,385 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::cooking_book::date::Date;
+use crate::cooking_book::history::History;
+use crate::cooking_book::meal_plan::MealPlan;
+use crate::cooking_book::recipe::Recipe;
+
+/// A recipe which was cooked on a day, either recorded in the history or planned in the past.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Meal<'a> {
+    pub date: Date,
+    pub recipe: &'a str,
+}
+
+/// Returns the meals cooked before the given day, ordered by date.
+/// Meals planned in the past count as cooked unless the history already has them.
+///
+/// #Arguments
+///
+/// * `history` The recorded cooking history.
+/// * `meal_plan` The meal plan.
+/// * `today` The first day which is not in the past.
+pub fn get_meals<'a>(history: &'a History, meal_plan: &'a MealPlan, today: Date) -> Vec<Meal<'a>> {
+    let mut meals: Vec<Meal<'a>> = history
+        .entries
+        .iter()
+        .map(|e| Meal {
+            date: e.date,
+            recipe: &e.recipe,
+        })
+        .collect();
+
+    for ((date, _slot), recipe) in &meal_plan.meals {
+        if *date < today && !history.contains(*date, recipe) {
+            meals.push(Meal {
+                date: *date,
+                recipe,
+            });
+        }
+    }
+
+    meals.sort_by(|a, b| a.date.cmp(&b.date));
+    return meals;
+}
+
+/// How to order the recipe stats.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Order {
+    /// The most cooked recipes first.
+    MostCooked,
+    /// The least cooked recipes first.
+    LeastCooked,
+    /// The recipes not cooked for the longest time first, never cooked ones before all others.
+    LongestAgo,
+}
+
+impl Order {
+    /// Returns the order with this name, the default is `MostCooked`.
+    ///
+    /// #Arguments
+    ///
+    /// * `name` The name of the order.
+    pub fn lookup_order(name: &str) -> Order {
+        match name {
+            "least" => Order::LeastCooked,
+            "longest_ago" => Order::LongestAgo,
+            _ => Order::MostCooked,
+        }
+    }
+}
+
+/// How often a recipe was cooked.
+pub struct RecipeStats<'a> {
+    pub name: &'a str,
+    pub count: usize,
+    pub last_cooked: Option<Date>,
+    pub average_rating: Option<f32>,
+}
+
+impl<'a> RecipeStats<'a> {
+    /// Returns the stats of all recipes in the requested order.
+    /// Recipes which are equal in that order are sorted by name.
+    ///
+    /// #Arguments
+    ///
+    /// * `recipes` All recipes, including those never cooked.
+    /// * `meals` The cooked meals.
+    /// * `history` The recorded cooking history with the ratings.
+    /// * `order` How to order the stats.
+    pub fn find_all(
+        recipes: &'a HashMap<String, Recipe>,
+        meals: &Vec<Meal>,
+        history: &History,
+        order: Order,
+    ) -> Vec<RecipeStats<'a>> {
+        let mut stats: Vec<RecipeStats<'a>> = Vec::with_capacity(recipes.len());
+        for name in recipes.keys() {
+            let cooked: Vec<&Meal> = meals.iter().filter(|m| m.recipe == name).collect();
+            let ratings: Vec<u8> = history
+                .get_entries(Some(name))
+                .iter()
+                .filter_map(|e| e.rating)
+                .collect();
+
+            let average_rating = if ratings.is_empty() {
+                None
+            } else {
+                let sum: u32 = ratings.iter().map(|r| *r as u32).sum();
+                Some(sum as f32 / ratings.len() as f32)
+            };
+
+            stats.push(RecipeStats {
+                name,
+                count: cooked.len(),
+                last_cooked: cooked.iter().map(|m| m.date).max(),
+                average_rating,
+            });
+        }
+
+        stats.sort_by(|a, b| {
+            let ordering = match order {
+                Order::MostCooked => b.count.cmp(&a.count),
+                Order::LeastCooked => a.count.cmp(&b.count),
+                // None is less than any date
+                Order::LongestAgo => a.last_cooked.cmp(&b.last_cooked),
+            };
+            ordering.then_with(|| a.name.cmp(b.name))
+        });
+        return stats;
+    }
+
+    /// Export these stats to JSON.
+    ///
+    /// #Arguments
+    ///
+    /// * `today` The day to count the days since the recipe was last cooked from.
+    pub fn to_json(&self, today: Date) -> String {
+        let mut json: String = String::new();
+        json.push_str(&format!(
+            "{{\"name\": \"{}\", \"count\": {}",
+            self.name, self.count
+        ));
+        if let Some(last_cooked) = self.last_cooked {
+            json.push_str(&format!(
+                ", \"last_cooked\": \"{}\", \"days_since_last_cooked\": {}",
+                last_cooked,
+                last_cooked.days_until(&today)
+            ));
+        }
+        if let Some(average_rating) = self.average_rating {
+            json.push_str(&format!(", \"average_rating\": {:.1}", average_rating));
+        }
+        json.push('}');
+        return json;
+    }
+
+    /// Export a list of stats to JSON.
+    ///
+    /// #Arguments
+    ///
+    /// * `stats` The stats to export.
+    /// * `today` The day to count the days since a recipe was last cooked from.
+    pub fn all_to_json(stats: &Vec<&RecipeStats>, today: Date) -> String {
+        let mut json: String = String::new();
+        json.push('[');
+
+        let mut is_first: bool = true;
+        for s in stats {
+            if !is_first {
+                json.push_str(", ");
+            }
+            json.push_str(&s.to_json(today));
+            is_first = false;
+        }
+
+        json.push(']');
+        return json;
+    }
+}
+
+/// Returns how often each ingredient was used in the cooked meals, the most used first.
+/// Ingredients used equally often are sorted by name.
+///
+/// #Arguments
+///
+/// * `recipes` All recipes.
+/// * `meals` The cooked meals.
+pub fn get_ingredient_usage(
+    recipes: &HashMap<String, Recipe>,
+    meals: &Vec<Meal>,
+) -> Vec<(String, usize)> {
+    let mut usage: HashMap<&str, usize> = HashMap::new();
+    for meal in meals {
+        if let Some(recipe) = recipes.get(meal.recipe) {
+            for ingredient in recipe.get_ingredients() {
+                *usage.entry(&ingredient.name).or_insert(0) += 1;
+            }
+        }
+    }
+
+    let mut usage: Vec<(String, usize)> = usage
+        .into_iter()
+        .map(|(name, count)| (name.to_string(), count))
+        .collect();
+    usage.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    return usage;
+}
+
+/// Returns how often an ingredient was used per month as `YYYY-MM`, from the first to the last
+/// month with a cooked meal. Months without usage are included with 0.
+///
+/// #Arguments
+///
+/// * `recipes` All recipes.
+/// * `meals` The cooked meals.
+/// * `ingredient` The name of the ingredient.
+pub fn get_ingredient_usage_by_month(
+    recipes: &HashMap<String, Recipe>,
+    meals: &Vec<Meal>,
+    ingredient: &str,
+) -> Vec<(String, usize)> {
+    let (first, last) = match (meals.first(), meals.last()) {
+        (Some(first), Some(last)) => (first.date, last.date),
+        _ => return Vec::new(),
+    };
+
+    let mut usage: BTreeMap<(i32, u32), usize> = BTreeMap::new();
+    let (mut year, mut month) = (first.year, first.month);
+    while (year, month) <= (last.year, last.month) {
+        usage.insert((year, month), 0);
+        month += 1;
+        if month > 12 {
+            month = 1;
+            year += 1;
+        }
+    }
+
+    for meal in meals {
+        let uses = match recipes.get(meal.recipe) {
+            Some(recipe) => recipe.contains_ingredient(ingredient),
+            None => false,
+        };
+        if uses {
+            *usage.entry((meal.date.year, meal.date.month)).or_insert(0) += 1;
+        }
+    }
+
+    return usage
+        .into_iter()
+        .map(|((year, month), count)| (format!("{:04}-{:02}", year, month), count))
+        .collect();
+}
+
+/// Export names with counts to JSON.
+///
+/// #Arguments
+///
+/// * `key` The JSON key of the names.
+/// * `counts` The names with their counts.
+pub fn counts_to_json(key: &str, counts: &[(String, usize)]) -> String {
+    let mut json: String = String::new();
+    json.push('[');
+
+    let mut is_first: bool = true;
+    for (name, count) in counts {
+        if !is_first {
+            json.push_str(", ");
+        }
+        json.push_str(&format!(
+            "{{\"{}\": \"{}\", \"count\": {}}}",
+            key, name, count
+        ));
+        is_first = false;
+    }
+
+    json.push(']');
+    return json;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{counts_to_json, get_ingredient_usage, get_ingredient_usage_by_month, get_meals};
+    use super::{Order, RecipeStats};
+    use crate::cooking_book::date::Date;
+    use crate::cooking_book::history::History;
+    use crate::cooking_book::ingredient::Ingredient;
+    use crate::cooking_book::meal_plan::{MealPlan, Slot};
+    use crate::cooking_book::recipe::Recipe;
+    use std::collections::HashMap;
+
+    fn get_recipes() -> HashMap<String, Recipe> {
+        let all_ingredients: HashMap<String, Ingredient> = HashMap::new();
+        let mut recipes: HashMap<String, Recipe> = HashMap::new();
+        for line in &[
+            "Pfannkuchen;Mehl,300,G;Milch,150,ML",
+            "Waffel;Mehl,200,G;Ei,2,Stück",
+            "Suppe;Karotte,3,Stück",
+        ] {
+            let recipe = Recipe::new_by_line(line, &all_ingredients);
+            recipes.insert(recipe.name.clone(), recipe);
+        }
+        return recipes;
+    }
+
+    fn get_history() -> History {
+        let mut history = History::new();
+        let _ = history.record(Date::new(2019, 3, 30).unwrap(), "Waffel", Some(4), "");
+        let _ = history.record(Date::new(2019, 4, 1).unwrap(), "Waffel", Some(5), "");
+        return history;
+    }
+
+    fn get_meal_plan() -> MealPlan {
+        let mut meal_plan = MealPlan::new();
+        meal_plan.plan(Date::new(2019, 4, 1).unwrap(), Slot::Lunch, "Waffel");
+        meal_plan.plan(Date::new(2019, 5, 20).unwrap(), Slot::Lunch, "Pfannkuchen");
+        meal_plan.plan(Date::new(2019, 6, 1).unwrap(), Slot::Lunch, "Suppe");
+        return meal_plan;
+    }
+
+    #[test]
+    fn test_get_meals() {
+        let history = get_history();
+        let meal_plan = get_meal_plan();
+        let meals = get_meals(&history, &meal_plan, Date::new(2019, 6, 1).unwrap());
+        let names: Vec<&str> = meals.iter().map(|m| m.recipe).collect();
+        assert_eq!(names, vec!["Waffel", "Waffel", "Pfannkuchen"]);
+    }
+
+    #[test]
+    fn test_recipe_stats() {
+        let recipes = get_recipes();
+        let history = get_history();
+        let meal_plan = get_meal_plan();
+        let today = Date::new(2019, 6, 1).unwrap();
+        let meals = get_meals(&history, &meal_plan, today);
+
+        let stats = RecipeStats::find_all(&recipes, &meals, &history, Order::MostCooked);
+        let stats: Vec<&RecipeStats> = stats.iter().collect();
+        assert_eq!(
+            RecipeStats::all_to_json(&stats, today),
+            "[{\"name\": \"Waffel\", \"count\": 2, \"last_cooked\": \"2019-04-01\", \"days_since_last_cooked\": 61, \"average_rating\": 4.5}, {\"name\": \"Pfannkuchen\", \"count\": 1, \"last_cooked\": \"2019-05-20\", \"days_since_last_cooked\": 12}, {\"name\": \"Suppe\", \"count\": 0}]"
+        );
+    }
+
+    #[test]
+    fn test_ingredient_usage() {
+        let recipes = get_recipes();
+        let history = get_history();
+        let meal_plan = get_meal_plan();
+        let meals = get_meals(&history, &meal_plan, Date::new(2019, 6, 1).unwrap());
+
+        assert_eq!(
+            counts_to_json("name", &get_ingredient_usage(&recipes, &meals)),
+            "[{\"name\": \"Mehl\", \"count\": 3}, {\"name\": \"Ei\", \"count\": 2}, {\"name\": \"Milch\", \"count\": 1}]"
+        );
+        assert_eq!(
+            get_ingredient_usage_by_month(&recipes, &meals, "Ei"),
+            vec![
+                ("2019-03".to_string(), 1),
+                ("2019-04".to_string(), 1),
+                ("2019-05".to_string(), 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_recipe_stats_order() {
+        let recipes = get_recipes();
+        let history = get_history();
+        let meal_plan = get_meal_plan();
+        let meals = get_meals(&history, &meal_plan, Date::new(2019, 6, 1).unwrap());
+
+        assert_eq!(Order::lookup_order("least"), Order::LeastCooked);
+        assert_eq!(Order::lookup_order("longest_ago"), Order::LongestAgo);
+        assert_eq!(Order::lookup_order(""), Order::MostCooked);
+
+        let least = RecipeStats::find_all(&recipes, &meals, &history, Order::LeastCooked);
+        let names: Vec<&str> = least.iter().map(|s| s.name).collect();
+        assert_eq!(names, vec!["Suppe", "Pfannkuchen", "Waffel"]);
+
+        let longest_ago = RecipeStats::find_all(&recipes, &meals, &history, Order::LongestAgo);
+        let names: Vec<&str> = longest_ago.iter().map(|s| s.name).collect();
+        assert_eq!(names, vec!["Suppe", "Waffel", "Pfannkuchen"]);
+    }
+}
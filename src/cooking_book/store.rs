@@ -2,6 +2,8 @@ use self::Store::*;
 use std::fmt;
 use std::slice::Iter;
 
+use crate::cooking_book::locale::Locale;
+
 /// The available stores for shopping.
 #[derive(Eq, Ord, PartialEq, PartialOrd, Clone, Debug, Copy, Hash)]
 pub enum Store {
@@ -17,24 +19,49 @@ impl Store {
         STORES.into_iter()
     }
 
-    pub fn all_as_json() -> String {
+    /// Export all stores with their ids and labels to JSON.
+    ///
+    /// #Arguments
+    /// * `locale` The language of the labels.
+    pub fn all_as_json(locale: Locale) -> String {
         let mut json = String::new();
         json.push_str("{\"stores\": [");
 
         let mut is_first: bool = true;
         for store in Store::get_store_iterator() {
-            if is_first {
-                json.push_str(&format!("\"{}\"", store));
-                is_first = false;
-            } else {
-                json.push_str(&format!(", \"{}\"", store));
+            if !is_first {
+                json.push_str(", ");
             }
+            json.push_str(&format!(
+                "{{\"id\": \"{}\", \"label\": \"{}\"}}",
+                store.id(),
+                store.label(locale)
+            ));
+            is_first = false;
         }
         json.push_str("]}");
 
         return json;
     }
 
+    /// Returns the stable id of the store like `any`, which doesn't depend on the language.
+    pub fn id(&self) -> &'static str {
+        return match *self {
+            Store::Rewe => "rewe",
+            Store::DM => "dm",
+            Store::Denz => "denz",
+            Store::Any => "any",
+        };
+    }
+
+    /// Returns the name of the store shown to the user.
+    ///
+    /// #Arguments
+    /// * `locale` The language of the name.
+    pub fn label(&self, locale: Locale) -> &'static str {
+        return locale.text(&format!("store.{}", self.id()));
+    }
+
     /// Returns the decoded Store.
     ///
     /// #Arguments
@@ -47,22 +74,38 @@ impl Store {
             _ => Store::Any,
         }
     }
+
+    /// Returns the store by its number, its id or its name in any language like `Überall`,
+    /// ignoring the case.
+    ///
+    /// #Arguments
+    /// * `name` The number, id or name of the store.
+    pub fn lookup_store_name(name: &str) -> Option<Store> {
+        if let Ok(number) = name.parse::<usize>() {
+            return Some(Store::lookup_store_number(number));
+        }
+        let name = name.trim().to_lowercase();
+        return Store::get_store_iterator()
+            .find(|s| {
+                return s.id() == name
+                    || [Locale::De, Locale::En]
+                        .iter()
+                        .any(|l| s.label(*l).to_lowercase() == name);
+            })
+            .cloned();
+    }
 }
 
 impl fmt::Display for Store {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match *self {
-            Store::Rewe => write!(f, "Rewe"),
-            Store::DM => write!(f, "DM"),
-            Store::Denz => write!(f, "Denz"),
-            Store::Any => write!(f, "Überall"),
-        }
+        write!(f, "{}", self.label(Locale::De))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::Store;
+    use crate::cooking_book::locale::Locale;
     #[test]
     fn test_store_size() {
         let it = Store::get_store_iterator();
@@ -70,6 +113,15 @@ mod tests {
         assert_eq!(size, 4);
     }
 
+    #[test]
+    fn test_all_as_json() {
+        assert_eq!(
+            Store::all_as_json(Locale::En),
+            "{\"stores\": [{\"id\": \"rewe\", \"label\": \"Rewe\"}, {\"id\": \"dm\", \"label\": \"DM\"}, {\"id\": \"denz\", \"label\": \"Denz\"}, {\"id\": \"any\", \"label\": \"Anywhere\"}]}"
+        );
+        assert_eq!(Store::Any.to_string(), "Überall");
+    }
+
     #[test]
     fn test_lookup_store() {
         assert_eq!(Store::lookup_store_number(0), Store::Rewe);
@@ -78,4 +130,14 @@ mod tests {
         assert_eq!(Store::lookup_store_number(3), Store::Any);
         assert_eq!(Store::lookup_store_number(4), Store::Any);
     }
+
+    #[test]
+    fn test_lookup_store_name() {
+        assert_eq!(Store::lookup_store_name("rewe"), Some(Store::Rewe));
+        assert_eq!(Store::lookup_store_name("Überall"), Some(Store::Any));
+        assert_eq!(Store::lookup_store_name("1"), Some(Store::DM));
+        assert_eq!(Store::lookup_store_name("anywhere"), Some(Store::Any));
+        assert_eq!(Store::lookup_store_name("denz"), Some(Store::Denz));
+        assert_eq!(Store::lookup_store_name("Aldi"), None);
+    }
 }
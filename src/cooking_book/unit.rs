@@ -0,0 +1,101 @@
+/// The units which can be used for amounts in recipes.
+static UNITS: [&str; 12] = [
+    "G", "KG", "ML", "L", "EL", "TL", "Prise", "Stück", "Dose", "Packung", "Bund", "Becher",
+];
+
+/// Returns whether the unit is known. The comparison ignores case, an empty unit is valid.
+///
+/// #Arguments
+///
+/// * `unit` The unit to look up.
+pub fn is_known_unit(unit: &str) -> bool {
+    if unit.is_empty() {
+        return true;
+    }
+    let unit = unit.to_lowercase();
+    return UNITS.iter().any(|u| u.to_lowercase() == unit);
+}
+
+/// Converts an amount between units of the same kind, like KG to G.
+/// Units are compared ignoring case, converting to a larger unit rounds up.
+/// Returns `None` if the units can't be converted.
+///
+/// #Arguments
+///
+/// * `amount` The amount in the unit `from`.
+/// * `from` The unit of the amount.
+/// * `to` The unit to convert to.
+pub fn convert(amount: u32, from: &str, to: &str) -> Option<u32> {
+    if from.to_lowercase() == to.to_lowercase() {
+        return Some(amount);
+    }
+    let (kind_from, factor_from) = base_unit(from)?;
+    let (kind_to, factor_to) = base_unit(to)?;
+    if kind_from != kind_to {
+        return None;
+    }
+    return Some((amount * factor_from + factor_to - 1) / factor_to);
+}
+
+/// Returns the smaller of two units of the same kind, like G for G and KG.
+/// If the units can't be converted, the first one is returned.
+///
+/// #Arguments
+///
+/// * `a` The first unit.
+/// * `b` The second unit.
+pub fn smaller_unit<'a>(a: &'a str, b: &'a str) -> &'a str {
+    return match (base_unit(a), base_unit(b)) {
+        (Some((kind_a, factor_a)), Some((kind_b, factor_b))) => {
+            if kind_a == kind_b && factor_b < factor_a {
+                b
+            } else {
+                a
+            }
+        }
+        _ => a,
+    };
+}
+
+/// Returns the kind of a unit and its factor to the base unit of that kind.
+fn base_unit(unit: &str) -> Option<(&'static str, u32)> {
+    return match unit.to_lowercase().as_str() {
+        "g" => Some(("mass", 1)),
+        "kg" => Some(("mass", 1000)),
+        "ml" => Some(("volume", 1)),
+        "l" => Some(("volume", 1000)),
+        _ => None,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{convert, is_known_unit, smaller_unit};
+
+    #[test]
+    fn test_is_known_unit() {
+        assert!(is_known_unit("G"));
+        assert!(is_known_unit("ml"));
+        assert!(is_known_unit("stück"));
+        assert!(is_known_unit(""));
+        assert!(!is_known_unit("Eimer"));
+    }
+
+    #[test]
+    fn test_convert() {
+        assert_eq!(convert(2, "kg", "G"), Some(2000));
+        assert_eq!(convert(1500, "ML", "L"), Some(2));
+        assert_eq!(convert(3, "EL", "el"), Some(3));
+        assert_eq!(convert(3, "EL", "TL"), None);
+        assert_eq!(convert(3, "", "G"), None);
+        assert_eq!(convert(3, "KG", "L"), None);
+    }
+
+    #[test]
+    fn test_smaller_unit() {
+        assert_eq!(smaller_unit("KG", "g"), "g");
+        assert_eq!(smaller_unit("ML", "L"), "ML");
+        assert_eq!(smaller_unit("EL", "TL"), "EL");
+        assert_eq!(smaller_unit("KG", "ML"), "KG");
+    }
+}
@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+
+use crate::cooking_book::ingredient::Ingredient;
+use crate::cooking_book::recipe::Recipe;
+use crate::cooking_book::shopping_list::ShoppingList;
+
+/// An ingredient which is referenced by a recipe or the shopping list but missing in the catalog.
+#[derive(PartialEq, Eq, Debug)]
+pub struct UnresolvedIngredient {
+    pub name: String,
+    pub recipes: Vec<String>,
+    pub on_shopping_list: bool,
+}
+
+impl UnresolvedIngredient {
+    /// Returns all unresolved ingredients sorted by name.
+    ///
+    /// #Arguments
+    ///
+    /// * `all_ingredients` The collection of all ingredients.
+    /// * `recipes` The recipes to search for references.
+    /// * `shopping_list` The shopping list to search for references.
+    pub fn find_all(
+        all_ingredients: &HashMap<String, Ingredient>,
+        recipes: &HashMap<String, Recipe>,
+        shopping_list: &ShoppingList,
+    ) -> Vec<UnresolvedIngredient> {
+        let mut unresolved: HashMap<String, UnresolvedIngredient> = HashMap::new();
+
+        for (recipe_name, recipe) in recipes {
+            for i in recipe.get_ingredients() {
+                if all_ingredients.contains_key(&i.name) {
+                    continue;
+                }
+                unresolved
+                    .entry(i.name.to_string())
+                    .or_insert_with(|| UnresolvedIngredient::new(&i.name))
+                    .recipes
+                    .push(recipe_name.to_string());
+            }
+        }
+
+        for (i, _amount) in &shopping_list.to_buy {
+            if all_ingredients.contains_key(&i.name) {
+                continue;
+            }
+            unresolved
+                .entry(i.name.to_string())
+                .or_insert_with(|| UnresolvedIngredient::new(&i.name))
+                .on_shopping_list = true;
+        }
+
+        let mut unresolved: Vec<UnresolvedIngredient> =
+            unresolved.into_iter().map(|(_k, v)| v).collect();
+        for u in unresolved.iter_mut() {
+            u.recipes.sort();
+        }
+        unresolved.sort_by(|a, b| a.name.cmp(&b.name));
+        return unresolved;
+    }
+
+    fn new(name: &str) -> UnresolvedIngredient {
+        return UnresolvedIngredient {
+            name: name.to_string(),
+            recipes: Vec::new(),
+            on_shopping_list: false,
+        };
+    }
+
+    /// Creates and persists an ingredient with default group and store for every unresolved one.
+    ///
+    /// #Arguments
+    ///
+    /// * `unresolved` The unresolved ingredients to create.
+    /// * `all_ingredients` The collection of all ingredients.
+    pub fn create_all(
+        unresolved: &Vec<UnresolvedIngredient>,
+        all_ingredients: &mut HashMap<String, Ingredient>,
+    ) -> Result<(), String> {
+        for u in unresolved {
+            if !all_ingredients.contains_key(&u.name) {
+                Ingredient::persist_new_ingredient(&u.name, all_ingredients)?;
+            }
+        }
+        return Ok(());
+    }
+
+    /// Export this unresolved ingredient to JSON.
+    pub fn to_json(&self) -> String {
+        let mut json: String = String::new();
+        json.push('{');
+
+        json.push_str("\"name\": \"");
+        json.push_str(&self.name);
+        json.push_str("\", ");
+
+        json.push_str("\"recipes\": [");
+        let mut is_first: bool = true;
+        for recipe in &self.recipes {
+            if !is_first {
+                json.push_str(", ");
+            }
+            json.push_str(&format!("\"{}\"", recipe));
+            is_first = false;
+        }
+        json.push_str("], ");
+
+        json.push_str("\"on_shopping_list\": ");
+        json.push_str(&format!("{}", self.on_shopping_list));
+
+        json.push('}');
+        return json;
+    }
+
+    /// Export a list of unresolved ingredients to JSON.
+    ///
+    /// #Arguments
+    ///
+    /// * `unresolved` The unresolved ingredients to export.
+    pub fn all_to_json(unresolved: &Vec<UnresolvedIngredient>) -> String {
+        let mut json: String = String::new();
+        json.push('[');
+
+        let mut is_first: bool = true;
+        for u in unresolved {
+            if !is_first {
+                json.push_str(", ");
+            }
+            json.push_str(&u.to_json());
+            is_first = false;
+        }
+
+        json.push(']');
+        return json;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::UnresolvedIngredient;
+    use crate::cooking_book::ingredient::Ingredient;
+    use crate::cooking_book::recipe::Recipe;
+    use crate::cooking_book::shopping_list::ShoppingList;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_find_all() {
+        let mut all_ingredients: HashMap<String, Ingredient> = HashMap::new();
+        all_ingredients.insert(
+            "Mehl".to_string(),
+            Ingredient::new_by_name("Mehl".to_string()),
+        );
+
+        let mut recipes: HashMap<String, Recipe> = HashMap::new();
+        recipes.insert(
+            "Waffel".to_string(),
+            Recipe::new_by_line("Waffel;Mehl,300,G;Magarine,2,EL", &all_ingredients),
+        );
+        recipes.insert(
+            "Kuchen".to_string(),
+            Recipe::new_by_line("Kuchen;Magarine,1,EL", &all_ingredients),
+        );
+
+        let mut shopping_list = ShoppingList::new();
+        shopping_list.add_item(Ingredient::new_by_name("Mais".to_string()), 2);
+        shopping_list.add_item(Ingredient::new_by_name("Mehl".to_string()), 1);
+
+        let unresolved = UnresolvedIngredient::find_all(&all_ingredients, &recipes, &shopping_list);
+        assert_eq!(
+            unresolved,
+            vec![
+                UnresolvedIngredient {
+                    name: "Magarine".to_string(),
+                    recipes: vec!["Kuchen".to_string(), "Waffel".to_string()],
+                    on_shopping_list: false,
+                },
+                UnresolvedIngredient {
+                    name: "Mais".to_string(),
+                    recipes: Vec::new(),
+                    on_shopping_list: true,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_to_json() {
+        let unresolved = vec![UnresolvedIngredient {
+            name: "Magarine".to_string(),
+            recipes: vec!["Waffel".to_string()],
+            on_shopping_list: true,
+        }];
+        assert_eq!(
+            UnresolvedIngredient::all_to_json(&unresolved),
+            "[{\"name\": \"Magarine\", \"recipes\": [\"Waffel\"], \"on_shopping_list\": true}]"
+        );
+    }
+}
@@ -0,0 +1,439 @@
+use crate::cooking_book::date::Date;
+use crate::cooking_book::group::Group;
+use crate::cooking_book::store::Store;
+use crate::file_access::check;
+use crate::file_access::check::Diagnostic;
+use crate::file_access::json::Json;
+use crate::file_access::persistency::paths;
+
+/// Identifies an export of the cooking book.
+pub const FORMAT: &'static str = "cooking-book";
+
+/// The version of the export format, increased on incompatible changes.
+pub const VERSION: u32 = 1;
+
+/// The parts of an export with the persistency file holding them.
+static PARTS: [(&str, &str); 7] = [
+    ("ingredients", paths::INGREDIENTS),
+    ("recipes", paths::RECIPES),
+    ("recipe_details", paths::RECIPE_DETAILS),
+    ("shopping_list", paths::SHOPPING_LIST),
+    ("pantry", paths::PANTRY),
+    ("meal_plan", paths::MEAL_PLAN),
+    ("history", paths::HISTORY),
+];
+
+/// How imported entries are combined with the existing ones.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Mode {
+    /// Adds the entries which don't exist yet, existing entries are kept.
+    Merge,
+    /// Replaces all entries of the imported parts.
+    Replace,
+}
+
+impl Mode {
+    /// Returns the mode by its name, `None` for an unknown name.
+    ///
+    /// #Arguments
+    ///
+    /// * `name` The name of the mode: `merge` or `replace`.
+    pub fn lookup_mode(name: &str) -> Option<Mode> {
+        return match name {
+            "merge" => Some(Mode::Merge),
+            "replace" => Some(Mode::Replace),
+            _ => None,
+        };
+    }
+}
+
+/// An imported entry which differs from an existing entry with the same key.
+/// The existing entry is kept.
+#[derive(PartialEq, Eq, Debug)]
+pub struct Conflict {
+    pub part: String,
+    pub key: String,
+    pub existing: String,
+    pub imported: String,
+}
+
+impl Conflict {
+    /// Export this conflict to JSON.
+    pub fn to_json(&self) -> String {
+        return format!(
+            "{{\"part\": \"{}\", \"key\": \"{}\", \"existing\": \"{}\", \"imported\": \"{}\"}}",
+            self.part,
+            Json::escape(&self.key),
+            Json::escape(&self.existing),
+            Json::escape(&self.imported)
+        );
+    }
+}
+
+/// The result of an import: the new content of the files and what happened.
+pub struct Import {
+    pub mode: Mode,
+    /// The file names with their new content, only for the imported parts.
+    pub files: Vec<(&'static str, String)>,
+    /// The imported parts with the number of written entries.
+    pub imported: Vec<(&'static str, usize)>,
+    pub conflicts: Vec<Conflict>,
+    /// The problems of the files after the import. Nothing may be written if there are any.
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl Import {
+    /// Export the report of the import to JSON.
+    pub fn to_json(&self) -> String {
+        let mode = match self.mode {
+            Mode::Merge => "merge",
+            Mode::Replace => "replace",
+        };
+        let imported: Vec<String> = self
+            .imported
+            .iter()
+            .map(|(part, count)| format!("\"{}\": {}", part, count))
+            .collect();
+        let conflicts: Vec<String> = self.conflicts.iter().map(|c| c.to_json()).collect();
+        return format!(
+            "{{\"mode\": \"{}\", \"imported\": {{{}}}, \"conflicts\": [{}], \"problems\": {}}}",
+            mode,
+            imported.join(", "),
+            conflicts.join(", "),
+            Diagnostic::all_to_json(&self.diagnostics)
+        );
+    }
+}
+
+/// Exports the whole cooking book as one JSON document.
+/// Besides the format, version and date of the export it contains the groups and stores
+/// with their numbers and every part of the cooking book as a list of its entries.
+/// The entries are written like the lines of the persistency files, an entry of the
+/// recipe details contains all lines of one recipe starting with `== name`.
+///
+/// #Arguments
+///
+/// * `files` The persistency file names with their content.
+/// * `today` The date of the export.
+pub fn export(files: &Vec<(&str, String)>, today: Date) -> String {
+    let mut json = format!(
+        "{{\"format\": \"{}\", \"version\": {}, \"exported\": \"{}\"",
+        FORMAT, VERSION, today
+    );
+
+    let mut groups: Vec<String> = Vec::new();
+    for number in 0.. {
+        let group = Group::lookup_group_number(number);
+        if group == Group::Other {
+            break;
+        }
+        groups.push(format!("{{\"id\": {}, \"name\": \"{}\"}}", number, group));
+    }
+    groups.push(format!("{{\"id\": -1, \"name\": \"{}\"}}", Group::Other));
+    json.push_str(&format!(", \"groups\": [{}]", groups.join(", ")));
+
+    let mut stores: Vec<String> = Vec::new();
+    for number in 0.. {
+        let store = Store::lookup_store_number(number);
+        if store == Store::Any {
+            break;
+        }
+        stores.push(format!("{{\"id\": {}, \"name\": \"{}\"}}", number, store));
+    }
+    stores.push(format!("{{\"id\": -1, \"name\": \"{}\"}}", Store::Any));
+    json.push_str(&format!(", \"stores\": [{}]", stores.join(", ")));
+
+    for (part, file_name) in PARTS.iter() {
+        let content = files
+            .iter()
+            .find(|(f, _c)| f == file_name)
+            .map_or("", |(_f, c)| c.as_str());
+        let (_comments, entries) = split_entries(file_name, content);
+        let entries: Vec<String> = entries
+            .iter()
+            .map(|(_key, entry)| format!("\"{}\"", Json::escape(entry)))
+            .collect();
+        json.push_str(&format!(", \"{}\": [{}]", part, entries.join(", ")));
+    }
+    json.push('}');
+    return json;
+}
+
+/// Imports an export of the cooking book, see `export`. Parts missing in the export are
+/// left unchanged. When merging, entries are matched by their key like the name of an
+/// ingredient or the date and slot of a meal. Entries which don't exist yet are added,
+/// different entries with an existing key are reported as conflicts and not imported.
+/// The files after the import are checked, but not written.
+/// Returns an error if the document isn't a valid export.
+///
+/// #Arguments
+///
+/// * `text` The JSON document.
+/// * `files` The persistency file names with their current content.
+/// * `mode` How to combine the imported and the existing entries.
+pub fn import(text: &str, files: &Vec<(&str, String)>, mode: Mode) -> Result<Import, String> {
+    let json = Json::parse(text)?;
+    if json.get("format").and_then(|f| f.as_str()) != Some(FORMAT) {
+        return Err(format!("The document is no export of the {}", FORMAT));
+    }
+    let version = match json.get("version").and_then(|v| v.as_number()) {
+        Some(version) => version,
+        None => return Err("The document has no version".to_string()),
+    };
+    if version < 1.0 || version > VERSION as f64 || version.fract() != 0.0 {
+        return Err(format!(
+            "Version {} isn't supported, the latest version is {}",
+            version, VERSION
+        ));
+    }
+
+    let mut result = Import {
+        mode,
+        files: Vec::new(),
+        imported: Vec::new(),
+        conflicts: Vec::new(),
+        diagnostics: Vec::new(),
+    };
+    let mut all_files: Vec<(&str, String)> = Vec::new();
+    for (part, file_name) in PARTS.iter() {
+        let content = files
+            .iter()
+            .find(|(f, _c)| f == file_name)
+            .map_or("", |(_f, c)| c.as_str());
+        let values = match json.get(part) {
+            Some(values) => values.as_list(),
+            None => {
+                all_files.push((*file_name, content.to_string()));
+                continue;
+            }
+        };
+
+        let mut imported: Vec<(String, String)> = Vec::with_capacity(values.len());
+        for value in values {
+            let entry = match value.as_str() {
+                Some(entry) => entry.trim_end_matches('\n'),
+                None => return Err(format!("The entries of '{}' must be texts", part)),
+            };
+            if *file_name == paths::RECIPE_DETAILS {
+                if !entry.starts_with("== ") {
+                    return Err(format!("An entry of '{}' must start with '== '", part));
+                }
+            } else if entry.contains('\n') {
+                return Err(format!("An entry of '{}' contains a line break", part));
+            }
+            imported.push((entry_key(file_name, entry), entry.to_string()));
+        }
+
+        let (comments, mut entries) = split_entries(file_name, content);
+        let mut count = 0;
+        if mode == Mode::Replace {
+            count = imported.len();
+            entries = imported;
+        } else {
+            for (key, entry) in imported {
+                match entries.iter().find(|(k, _e)| *k == key) {
+                    None => {
+                        entries.push((key, entry));
+                        count += 1;
+                    }
+                    Some((_k, existing)) if *existing == entry => {}
+                    Some((_k, existing)) => result.conflicts.push(Conflict {
+                        part: part.to_string(),
+                        key,
+                        existing: existing.to_string(),
+                        imported: entry,
+                    }),
+                }
+            }
+        }
+
+        let mut content = String::new();
+        for line in comments.iter().chain(entries.iter().map(|(_k, e)| e)) {
+            content.push_str(line);
+            content.push('\n');
+        }
+        result.imported.push((*part, count));
+        result.files.push((*file_name, content.to_string()));
+        all_files.push((*file_name, content));
+    }
+
+    result.diagnostics = check::check_contents(&all_files);
+    return Ok(result);
+}
+
+/// Splits the content of a persistency file into its comments and its entries with their key.
+/// Empty lines are left out.
+fn split_entries(file_name: &str, content: &str) -> (Vec<String>, Vec<(String, String)>) {
+    let mut comments: Vec<String> = Vec::new();
+    let mut entries: Vec<(String, String)> = Vec::new();
+    for line in content.lines() {
+        if line.starts_with('#') {
+            comments.push(line.to_string());
+            continue;
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        if file_name == paths::RECIPE_DETAILS && !line.starts_with("== ") {
+            if let Some((_key, entry)) = entries.last_mut() {
+                entry.push('\n');
+                entry.push_str(line);
+                continue;
+            }
+        }
+        entries.push((entry_key(file_name, line), line.to_string()));
+    }
+    return (comments, entries);
+}
+
+/// Returns the key of an entry: the date and slot of a meal, the date and recipe of
+/// the history, otherwise the name.
+fn entry_key(file_name: &str, entry: &str) -> String {
+    if file_name == paths::RECIPE_DETAILS {
+        let name = entry.lines().next().unwrap_or("");
+        return name.trim_start_matches("==").trim().to_string();
+    }
+    let fields = if file_name == paths::MEAL_PLAN || file_name == paths::HISTORY {
+        2
+    } else {
+        1
+    };
+    let key: Vec<&str> = entry.split(';').take(fields).collect();
+    return key.join(";");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{export, import, Conflict, Mode};
+    use crate::cooking_book::date::Date;
+    use crate::file_access::json::Json;
+    use crate::file_access::persistency::paths;
+
+    fn get_files() -> Vec<(&'static str, String)> {
+        return vec![
+            (paths::INGREDIENTS, "Mehl;5;0\nMilch;2;-1\n".to_string()),
+            (
+                paths::RECIPES,
+                "#name;(ingredient,amount,unit)...\nPfannkuchen;Mehl,300,G;Milch,500,ML\n"
+                    .to_string(),
+            ),
+            (
+                paths::RECIPE_DETAILS,
+                "== Pfannkuchen\nservings: 2\nstep: {Mehl} und {Milch} verrühren\n".to_string(),
+            ),
+            (paths::MEAL_PLAN, "2026-10-19;2;Pfannkuchen\n".to_string()),
+        ];
+    }
+
+    fn get_content<'a>(files: &'a Vec<(&str, String)>, file_name: &str) -> &'a str {
+        return files
+            .iter()
+            .find(|(f, _c)| *f == file_name)
+            .map_or("", |(_f, c)| c.as_str());
+    }
+
+    #[test]
+    fn test_export() {
+        let json = export(&get_files(), Date::new(2026, 10, 19).unwrap());
+        let json = Json::parse(&json).unwrap();
+        assert_eq!(json.get("format").unwrap().as_str(), Some("cooking-book"));
+        assert_eq!(json.get("version").unwrap().as_number(), Some(1.0));
+        assert_eq!(json.get("exported").unwrap().as_str(), Some("2026-10-19"));
+        assert_eq!(json.get("groups").unwrap().as_list().len(), 12);
+        assert_eq!(json.get("stores").unwrap().as_list().len(), 4);
+        assert_eq!(
+            json.get("recipes").unwrap().as_list(),
+            vec![&Json::String(
+                "Pfannkuchen;Mehl,300,G;Milch,500,ML".to_string()
+            )]
+        );
+        assert_eq!(
+            json.get("recipe_details").unwrap().as_list()[0].as_str(),
+            Some("== Pfannkuchen\nservings: 2\nstep: {Mehl} und {Milch} verrühren")
+        );
+        assert!(json.get("history").unwrap().as_list().is_empty());
+    }
+
+    #[test]
+    fn test_import_replace() {
+        let files = get_files();
+        let exported = export(&files, Date::new(2026, 10, 19).unwrap());
+        let result = import(&exported, &files, Mode::Replace).unwrap();
+        assert!(result.diagnostics.is_empty());
+        assert!(result.conflicts.is_empty());
+        assert_eq!(result.files.len(), 7);
+        for (file_name, content) in &files {
+            assert_eq!(get_content(&result.files, file_name), content.as_str());
+        }
+        assert_eq!(
+            result.to_json(),
+            "{\"mode\": \"replace\", \"imported\": {\"ingredients\": 2, \"recipes\": 1, \"recipe_details\": 1, \"shopping_list\": 0, \"pantry\": 0, \"meal_plan\": 1, \"history\": 0}, \"conflicts\": [], \"problems\": []}"
+        );
+    }
+
+    #[test]
+    fn test_import_merge() {
+        let text = "{\"format\": \"cooking-book\", \"version\": 1, \"ingredients\": [\"Mehl;5;1\", \"Eier;-1;-1\"], \"recipes\": [\"Pfannkuchen;Mehl,300,G;Milch,500,ML\", \"Rührei;Eier,4,\"], \"meal_plan\": [\"2026-10-19;2;Rührei\", \"2026-10-20;0;Rührei\"]}";
+        let result = import(text, &get_files(), Mode::Merge).unwrap();
+        assert!(result.diagnostics.is_empty());
+        assert_eq!(
+            result.imported,
+            vec![("ingredients", 1), ("recipes", 1), ("meal_plan", 1)]
+        );
+        assert_eq!(
+            result.conflicts,
+            vec![
+                Conflict {
+                    part: "ingredients".to_string(),
+                    key: "Mehl".to_string(),
+                    existing: "Mehl;5;0".to_string(),
+                    imported: "Mehl;5;1".to_string()
+                },
+                Conflict {
+                    part: "meal_plan".to_string(),
+                    key: "2026-10-19;2".to_string(),
+                    existing: "2026-10-19;2;Pfannkuchen".to_string(),
+                    imported: "2026-10-19;2;Rührei".to_string()
+                }
+            ]
+        );
+        assert_eq!(
+            get_content(&result.files, paths::INGREDIENTS),
+            "Mehl;5;0\nMilch;2;-1\nEier;-1;-1\n"
+        );
+        assert_eq!(
+            get_content(&result.files, paths::RECIPES),
+            "#name;(ingredient,amount,unit)...\nPfannkuchen;Mehl,300,G;Milch,500,ML\nRührei;Eier,4,\n"
+        );
+        assert_eq!(get_content(&result.files, paths::PANTRY), "");
+    }
+
+    #[test]
+    fn test_import_invalid() {
+        let files = get_files();
+        assert!(import("[]", &files, Mode::Merge).is_err());
+        assert_eq!(
+            import(
+                "{\"format\": \"cooking-book\", \"version\": 2}",
+                &files,
+                Mode::Merge
+            )
+            .err(),
+            Some("Version 2 isn't supported, the latest version is 1".to_string())
+        );
+        assert!(import(
+            "{\"format\": \"cooking-book\", \"version\": 1, \"recipes\": [\"a\\nb\"]}",
+            &files,
+            Mode::Merge
+        )
+        .is_err());
+
+        let result = import(
+            "{\"format\": \"cooking-book\", \"version\": 1, \"ingredients\": []}",
+            &files,
+            Mode::Replace,
+        )
+        .unwrap();
+        assert!(!result.diagnostics.is_empty());
+    }
+}
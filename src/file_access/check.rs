@@ -0,0 +1,898 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::cooking_book::date::Date;
+use crate::cooking_book::group::Group;
+use crate::cooking_book::meal_plan::Slot;
+use crate::cooking_book::recipe::Recipe;
+use crate::cooking_book::recipe_details::RecipeDetails;
+use crate::cooking_book::store::Store;
+use crate::cooking_book::unit;
+use crate::file_access::json::Json;
+use crate::file_access::persistency;
+use crate::file_access::persistency::paths;
+
+/// A problem found while strictly parsing a persistency file.
+#[derive(PartialEq, Eq, Debug)]
+pub struct Diagnostic {
+    pub file: String,
+    pub line: usize,
+    pub problem: String,
+}
+
+impl Diagnostic {
+    fn new(file: &str, line: usize, problem: String) -> Diagnostic {
+        return Diagnostic {
+            file: file.to_string(),
+            line,
+            problem,
+        };
+    }
+
+    /// Export this diagnostic to JSON.
+    pub fn to_json(&self) -> String {
+        let mut json: String = String::new();
+        json.push('{');
+
+        json.push_str("\"file\": \"");
+        json.push_str(&Json::escape(&self.file));
+        json.push_str("\", ");
+
+        json.push_str("\"line\": ");
+        json.push_str(&format!("{}", self.line));
+        json.push_str(", ");
+
+        json.push_str("\"problem\": \"");
+        json.push_str(&Json::escape(&self.problem));
+        json.push_str("\"");
+
+        json.push('}');
+        return json;
+    }
+
+    /// Export a list of diagnostics to JSON.
+    ///
+    /// #Arguments
+    ///
+    /// * `diagnostics` The diagnostics to export.
+    pub fn all_to_json(diagnostics: &Vec<Diagnostic>) -> String {
+        let mut json: String = String::new();
+        json.push('[');
+
+        let mut is_first: bool = true;
+        for diagnostic in diagnostics {
+            if !is_first {
+                json.push_str(", ");
+            }
+            json.push_str(&diagnostic.to_json());
+            is_first = false;
+        }
+
+        json.push(']');
+        return json;
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", &self.file, self.line, &self.problem)
+    }
+}
+
+/// Strictly parses all persistency files and returns every problem found.
+/// Nothing will be modified.
+pub fn check_all() -> Vec<Diagnostic> {
+    return check_contents(&persistency::read_all_files());
+}
+
+/// Strictly parses the contents of the persistency files and returns every problem found.
+/// Missing files count as empty.
+///
+/// #Arguments
+///
+/// * `files` The file names with their content.
+pub fn check_contents(files: &Vec<(&str, String)>) -> Vec<Diagnostic> {
+    let read_file = |file_name: &str| -> &str {
+        return files
+            .iter()
+            .find(|(f, _c)| *f == file_name)
+            .map_or("", |(_f, c)| c.as_str());
+    };
+    let (mut diagnostics, ingredient_names) = check_ingredients(read_file(paths::INGREDIENTS));
+    let (mut recipe_diagnostics, recipe_names) =
+        check_recipes(read_file(paths::RECIPES), &ingredient_names);
+    diagnostics.append(&mut recipe_diagnostics);
+    diagnostics.append(&mut check_shopping_list(
+        read_file(paths::SHOPPING_LIST),
+        &ingredient_names,
+    ));
+    diagnostics.append(&mut check_pantry(
+        read_file(paths::PANTRY),
+        &ingredient_names,
+    ));
+    diagnostics.append(&mut check_recipe_details(
+        read_file(paths::RECIPE_DETAILS),
+        &recipe_names,
+    ));
+    diagnostics.append(&mut check_meal_plan(
+        read_file(paths::MEAL_PLAN),
+        &recipe_names,
+    ));
+    diagnostics.append(&mut check_history(read_file(paths::HISTORY), &recipe_names));
+    return diagnostics;
+}
+
+/// Checks the content of the ingredients file.
+/// Returns the problems found and the names and aliases of all ingredients.
+///
+/// #Arguments
+///
+/// * `content` The content of the ingredients file.
+fn check_ingredients(content: &str) -> (Vec<Diagnostic>, HashSet<String>) {
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+    let mut names: HashSet<String> = HashSet::new();
+    let file = paths::INGREDIENTS;
+
+    for (i, line) in content.lines().enumerate() {
+        let line_number = i + 1;
+        if line.starts_with('#') {
+            continue;
+        }
+
+        let mut values = line.split(';');
+        let name = values.next().unwrap();
+        if name.trim().is_empty() {
+            diagnostics.push(Diagnostic::new(
+                file,
+                line_number,
+                "missing ingredient name".to_string(),
+            ));
+        } else if !names.insert(name.to_string()) {
+            diagnostics.push(Diagnostic::new(
+                file,
+                line_number,
+                format!("duplicate ingredient '{}'", name),
+            ));
+        }
+
+        match values.next() {
+            Some(group) => {
+                if !is_in_range(group, Group::Other as i8, Group::Sweets as i8) {
+                    diagnostics.push(Diagnostic::new(
+                        file,
+                        line_number,
+                        format!("invalid group number '{}'", group),
+                    ));
+                }
+            }
+            None => diagnostics.push(Diagnostic::new(
+                file,
+                line_number,
+                "missing group".to_string(),
+            )),
+        }
+
+        match values.next() {
+            Some(store) => {
+                if !is_in_range(store, Store::Any as i8, Store::Denz as i8) {
+                    diagnostics.push(Diagnostic::new(
+                        file,
+                        line_number,
+                        format!("invalid store number '{}'", store),
+                    ));
+                }
+            }
+            None => diagnostics.push(Diagnostic::new(
+                file,
+                line_number,
+                "missing store".to_string(),
+            )),
+        }
+
+        if let Some(aliases) = values.next() {
+            for alias in aliases.split(',').filter(|a| !a.trim().is_empty()) {
+                if !names.insert(alias.to_string()) {
+                    diagnostics.push(Diagnostic::new(
+                        file,
+                        line_number,
+                        format!("duplicate alias '{}'", alias),
+                    ));
+                }
+            }
+        }
+
+        if let Some(price) = values.next() {
+            if price.trim().parse::<u32>().is_err() {
+                diagnostics.push(Diagnostic::new(
+                    file,
+                    line_number,
+                    format!("invalid price '{}'", price),
+                ));
+            }
+        }
+
+        if values.next().is_some() {
+            diagnostics.push(Diagnostic::new(
+                file,
+                line_number,
+                "unexpected additional field".to_string(),
+            ));
+        }
+    }
+
+    return (diagnostics, names);
+}
+
+/// Checks the content of the recipes file.
+/// Returns the problems found and the names of all recipes.
+///
+/// #Arguments
+///
+/// * `content` The content of the recipes file.
+/// * `ingredient_names` The names and aliases of all known ingredients.
+fn check_recipes(
+    content: &str,
+    ingredient_names: &HashSet<String>,
+) -> (Vec<Diagnostic>, HashSet<String>) {
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+    let mut recipe_names: HashSet<String> = HashSet::new();
+    let mut references: Vec<(usize, &str, &str)> = Vec::new();
+    let file = paths::RECIPES;
+
+    for (i, line) in content.lines().enumerate() {
+        let line_number = i + 1;
+        if line.starts_with('#') {
+            continue;
+        }
+
+        let mut values = line.split(';');
+        let name = values.next().unwrap();
+        if name.trim().is_empty() {
+            diagnostics.push(Diagnostic::new(
+                file,
+                line_number,
+                "missing recipe name".to_string(),
+            ));
+        } else if !recipe_names.insert(name.to_string()) {
+            diagnostics.push(Diagnostic::new(
+                file,
+                line_number,
+                format!("duplicate recipe '{}'", name),
+            ));
+        }
+
+        let mut used_ingredients: HashSet<(&str, &str)> = HashSet::new();
+        let mut used_sections: HashSet<&str> = HashSet::new();
+        let mut section = "";
+        for s in values {
+            if s.starts_with('#') {
+                if s.len() == 1 {
+                    diagnostics.push(Diagnostic::new(file, line_number, "empty tag".to_string()));
+                }
+                continue;
+            }
+
+            if s.starts_with('[') && s.ends_with(']') {
+                section = s[1..s.len() - 1].trim();
+                if !section.is_empty() && !used_sections.insert(section) {
+                    diagnostics.push(Diagnostic::new(
+                        file,
+                        line_number,
+                        format!("duplicate section '{}'", section),
+                    ));
+                }
+                continue;
+            }
+
+            if s.starts_with('@') {
+                let mut recipe_share = s[1..].split(',');
+                let sub_recipe = recipe_share.next().unwrap();
+                if references
+                    .iter()
+                    .any(|(l, _from, to)| *l == line_number && *to == sub_recipe)
+                {
+                    diagnostics.push(Diagnostic::new(
+                        file,
+                        line_number,
+                        format!("duplicate sub recipe '{}'", sub_recipe),
+                    ));
+                } else {
+                    references.push((line_number, name, sub_recipe));
+                }
+
+                let share = recipe_share.next().unwrap_or("");
+                if Recipe::parse_share(share).is_none() {
+                    diagnostics.push(Diagnostic::new(
+                        file,
+                        line_number,
+                        format!("invalid share '{}' for '{}'", share, sub_recipe),
+                    ));
+                }
+                if recipe_share.next().is_some() {
+                    diagnostics.push(Diagnostic::new(
+                        file,
+                        line_number,
+                        format!("unexpected additional value for '{}'", sub_recipe),
+                    ));
+                }
+                continue;
+            }
+
+            let mut ingre_amount = s.split(',');
+            let ingredient = ingre_amount.next().unwrap();
+            if ingredient.trim().is_empty() {
+                diagnostics.push(Diagnostic::new(
+                    file,
+                    line_number,
+                    "missing ingredient name".to_string(),
+                ));
+            } else if !ingredient_names.contains(ingredient) {
+                diagnostics.push(Diagnostic::new(
+                    file,
+                    line_number,
+                    format!("unknown ingredient '{}'", ingredient),
+                ));
+            }
+            if !ingredient.is_empty() && !used_ingredients.insert((section, ingredient)) {
+                diagnostics.push(Diagnostic::new(
+                    file,
+                    line_number,
+                    format!("duplicate ingredient '{}'", ingredient),
+                ));
+            }
+
+            let amount = ingre_amount.next().unwrap_or("");
+            if amount.parse::<u16>().is_err() {
+                diagnostics.push(Diagnostic::new(
+                    file,
+                    line_number,
+                    format!("invalid amount '{}' for '{}'", amount, ingredient),
+                ));
+            }
+
+            let unit = ingre_amount.next().unwrap_or("");
+            if !unit::is_known_unit(unit) {
+                diagnostics.push(Diagnostic::new(
+                    file,
+                    line_number,
+                    format!("unknown unit '{}' for '{}'", unit, ingredient),
+                ));
+            }
+
+            // the optional note
+            ingre_amount.next();
+            if ingre_amount.next().is_some() {
+                diagnostics.push(Diagnostic::new(
+                    file,
+                    line_number,
+                    format!("unexpected additional value for '{}'", ingredient),
+                ));
+            }
+        }
+    }
+
+    let mut graph: HashMap<&str, Vec<&str>> = HashMap::new();
+    for (_line, from, to) in &references {
+        graph.entry(from).or_insert_with(Vec::new).push(to);
+    }
+    let mut reported: HashSet<&str> = HashSet::new();
+    for (line_number, from, to) in &references {
+        if !recipe_names.contains(*to) {
+            diagnostics.push(Diagnostic::new(
+                file,
+                *line_number,
+                format!("unknown recipe '{}'", to),
+            ));
+        }
+
+        let mut path: Vec<&str> = vec![from];
+        if !reported.contains(from) && find_cycle(&graph, &mut path) {
+            diagnostics.push(Diagnostic::new(
+                file,
+                *line_number,
+                format!("recipe references itself: {}", path.join(" -> ")),
+            ));
+            reported.insert(from);
+        }
+    }
+    diagnostics.sort_by_key(|d| d.line);
+
+    return (diagnostics, recipe_names);
+}
+
+/// Returns whether the last recipe of the path leads back to the first one.
+/// The path is extended to the cycle if one is found.
+fn find_cycle<'a>(graph: &HashMap<&'a str, Vec<&'a str>>, path: &mut Vec<&'a str>) -> bool {
+    let current = path[path.len() - 1];
+    let next_recipes = match graph.get(current) {
+        Some(next_recipes) => next_recipes,
+        None => return false,
+    };
+    for next in next_recipes {
+        if *next == path[0] {
+            path.push(next);
+            return true;
+        }
+        if path.contains(next) {
+            continue;
+        }
+        path.push(next);
+        if find_cycle(graph, path) {
+            return true;
+        }
+        path.pop();
+    }
+    return false;
+}
+
+/// Checks the content of the recipe details file.
+///
+/// #Arguments
+///
+/// * `content` The content of the recipe details file.
+/// * `recipe_names` The names of all known recipes.
+fn check_recipe_details(content: &str, recipe_names: &HashSet<String>) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+    let mut names: HashSet<String> = HashSet::new();
+    let file = paths::RECIPE_DETAILS;
+
+    for (header, name, _details, problems) in RecipeDetails::all_by_text(content) {
+        if !name.is_empty() {
+            if !recipe_names.contains(&name) {
+                diagnostics.push(Diagnostic::new(
+                    file,
+                    header + 1,
+                    format!("unknown recipe '{}'", name),
+                ));
+            } else if !names.insert(name.to_string()) {
+                diagnostics.push(Diagnostic::new(
+                    file,
+                    header + 1,
+                    format!("duplicate details for '{}'", name),
+                ));
+            }
+        }
+        for (i, problem) in problems {
+            diagnostics.push(Diagnostic::new(file, i + 1, problem));
+        }
+    }
+
+    return diagnostics;
+}
+
+/// Checks the content of the shopping list file.
+///
+/// #Arguments
+///
+/// * `content` The content of the shopping list file.
+/// * `ingredient_names` The names and aliases of all known ingredients.
+fn check_shopping_list(content: &str, ingredient_names: &HashSet<String>) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+    let mut entries: HashSet<&str> = HashSet::new();
+    let file = paths::SHOPPING_LIST;
+
+    for (i, line) in content.lines().enumerate() {
+        let line_number = i + 1;
+
+        let mut values = line.split(';');
+        let name = values.next().unwrap();
+        if !ingredient_names.contains(name) {
+            diagnostics.push(Diagnostic::new(
+                file,
+                line_number,
+                format!("orphaned entry '{}' without ingredient", name),
+            ));
+        }
+        if !entries.insert(name) {
+            diagnostics.push(Diagnostic::new(
+                file,
+                line_number,
+                format!("duplicate entry '{}'", name),
+            ));
+        }
+
+        let amount = values.next().unwrap_or("");
+        match amount.parse::<u16>() {
+            Ok(x) if x > 0 => {}
+            _ => diagnostics.push(Diagnostic::new(
+                file,
+                line_number,
+                format!("invalid amount '{}'", amount),
+            )),
+        }
+
+        if values.next().is_some() {
+            diagnostics.push(Diagnostic::new(
+                file,
+                line_number,
+                "unexpected additional field".to_string(),
+            ));
+        }
+    }
+
+    return diagnostics;
+}
+
+/// Checks the content of the pantry file.
+///
+/// #Arguments
+///
+/// * `content` The content of the pantry file.
+/// * `ingredient_names` The names and aliases of all known ingredients.
+fn check_pantry(content: &str, ingredient_names: &HashSet<String>) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+    let file = paths::PANTRY;
+
+    for (i, line) in content.lines().enumerate() {
+        let line_number = i + 1;
+
+        let mut values = line.split(';');
+        let name = values.next().unwrap();
+        if !ingredient_names.contains(name) {
+            diagnostics.push(Diagnostic::new(
+                file,
+                line_number,
+                format!("orphaned entry '{}' without ingredient", name),
+            ));
+        }
+
+        let amount = values.next().unwrap_or("");
+        if amount.parse::<u16>().is_err() {
+            diagnostics.push(Diagnostic::new(
+                file,
+                line_number,
+                format!("invalid amount '{}'", amount),
+            ));
+        }
+
+        let unit = values.next().unwrap_or("");
+        if !unit::is_known_unit(unit) {
+            diagnostics.push(Diagnostic::new(
+                file,
+                line_number,
+                format!("unknown unit '{}'", unit),
+            ));
+        }
+
+        if let Some(best_before) = values.next() {
+            if Date::parse(best_before).is_none() {
+                diagnostics.push(Diagnostic::new(
+                    file,
+                    line_number,
+                    format!("invalid best before date '{}'", best_before),
+                ));
+            }
+        }
+    }
+
+    return diagnostics;
+}
+
+/// Checks the content of the meal plan file.
+///
+/// #Arguments
+///
+/// * `content` The content of the meal plan file.
+/// * `recipe_names` The names of all known recipes.
+fn check_meal_plan(content: &str, recipe_names: &HashSet<String>) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+    let file = paths::MEAL_PLAN;
+
+    for (i, line) in content.lines().enumerate() {
+        let line_number = i + 1;
+
+        let mut values = line.split(';');
+        let date = values.next().unwrap();
+        if Date::parse(date).is_none() {
+            diagnostics.push(Diagnostic::new(
+                file,
+                line_number,
+                format!("invalid date '{}'", date),
+            ));
+        }
+
+        let slot = values.next().unwrap_or("");
+        if !is_in_range(slot, Slot::Breakfast as i8, Slot::Dinner as i8) {
+            diagnostics.push(Diagnostic::new(
+                file,
+                line_number,
+                format!("invalid slot number '{}'", slot),
+            ));
+        }
+
+        let recipe = values.next().unwrap_or("");
+        if !recipe_names.contains(recipe) {
+            diagnostics.push(Diagnostic::new(
+                file,
+                line_number,
+                format!("unknown recipe '{}'", recipe),
+            ));
+        }
+    }
+
+    return diagnostics;
+}
+
+/// Checks the content of the cooking history file.
+///
+/// #Arguments
+///
+/// * `content` The content of the cooking history file.
+/// * `recipe_names` The names of all known recipes.
+fn check_history(content: &str, recipe_names: &HashSet<String>) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+    let file = paths::HISTORY;
+
+    for (i, line) in content.lines().enumerate() {
+        let line_number = i + 1;
+
+        let mut values = line.split(';');
+        let date = values.next().unwrap();
+        if Date::parse(date).is_none() {
+            diagnostics.push(Diagnostic::new(
+                file,
+                line_number,
+                format!("invalid date '{}'", date),
+            ));
+        }
+
+        let recipe = values.next().unwrap_or("");
+        if !recipe_names.contains(recipe) {
+            diagnostics.push(Diagnostic::new(
+                file,
+                line_number,
+                format!("unknown recipe '{}'", recipe),
+            ));
+        }
+
+        let rating = values.next().unwrap_or("");
+        if !rating.is_empty() && !is_in_range(rating, 1, 5) {
+            diagnostics.push(Diagnostic::new(
+                file,
+                line_number,
+                format!("invalid rating '{}'", rating),
+            ));
+        }
+
+        // the notes are free text
+        values.next();
+        if values.next().is_some() {
+            diagnostics.push(Diagnostic::new(
+                file,
+                line_number,
+                "unexpected additional field".to_string(),
+            ));
+        }
+    }
+
+    return diagnostics;
+}
+
+fn is_in_range(value: &str, min: i8, max: i8) -> bool {
+    return match value.trim().parse::<i8>() {
+        Ok(num) => num >= min && num <= max,
+        Err(_) => false,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        check_history, check_ingredients, check_meal_plan, check_pantry, check_recipe_details,
+        check_recipes, check_shopping_list, Diagnostic,
+    };
+    use std::collections::HashSet;
+
+    fn get_names() -> HashSet<String> {
+        let mut names: HashSet<String> = HashSet::new();
+        names.insert("Mehl".to_string());
+        names.insert("Milch".to_string());
+        return names;
+    }
+
+    #[test]
+    fn test_check_ingredients_valid() {
+        let (diagnostics, names) = check_ingredients("#name;group;store\nMehl;5;-1\nMilch;-1;0");
+        assert!(diagnostics.is_empty());
+        assert_eq!(names, get_names());
+    }
+
+    #[test]
+    fn test_check_ingredients_invalid() {
+        let (diagnostics, _names) = check_ingredients("Mehl;5;-1\nMehl;11;x\n;0");
+        let lines: Vec<usize> = diagnostics.iter().map(|d| d.line).collect();
+        assert_eq!(lines, vec![2, 2, 2, 3, 3]);
+        assert_eq!(diagnostics[0].problem, "duplicate ingredient 'Mehl'");
+        assert_eq!(diagnostics[1].problem, "invalid group number '11'");
+        assert_eq!(diagnostics[2].problem, "invalid store number 'x'");
+        assert_eq!(diagnostics[3].problem, "missing ingredient name");
+        assert_eq!(diagnostics[4].problem, "missing store");
+    }
+
+    #[test]
+    fn test_check_ingredients_aliases_and_price() {
+        let (diagnostics, names) = check_ingredients(
+            "Zwiebel;0;-1;Zwiebeln\nMargarine;5;-1;Magarine,Zwiebeln\nButter;2;0;;1.99",
+        );
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].line, 2);
+        assert_eq!(diagnostics[0].problem, "duplicate alias 'Zwiebeln'");
+        assert_eq!(diagnostics[1].line, 3);
+        assert_eq!(diagnostics[1].problem, "invalid price '1.99'");
+        assert!(names.contains("Magarine"));
+    }
+
+    #[test]
+    fn test_check_recipes_valid() {
+        let (diagnostics, _names) = check_recipes(
+            "#comment\nPfannkuchen;Mehl,300,G,gesiebt;Milch,1,l;#Breakfast",
+            &get_names(),
+        );
+        assert!(diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_check_recipes_invalid() {
+        let (diagnostics, _names) = check_recipes(
+            "Pfannkuchen;Mehl,300,G\nPfannkuchen;Mehl,x,G;Mehl,1,Eimer;Magarine,2,EL",
+            &get_names(),
+        );
+        assert_eq!(diagnostics.len(), 5);
+        assert!(diagnostics.iter().all(|d| d.line == 2));
+        assert_eq!(diagnostics[0].problem, "duplicate recipe 'Pfannkuchen'");
+        assert_eq!(diagnostics[1].problem, "invalid amount 'x' for 'Mehl'");
+        assert_eq!(diagnostics[2].problem, "duplicate ingredient 'Mehl'");
+        assert_eq!(diagnostics[3].problem, "unknown unit 'Eimer' for 'Mehl'");
+        assert_eq!(diagnostics[4].problem, "unknown ingredient 'Magarine'");
+    }
+
+    #[test]
+    fn test_check_recipe_sections() {
+        let (diagnostics, _names) = check_recipes(
+            "Kuchen;[Teig];Mehl,300,G;Milch,1,l;[Guss];Milch,2,EL;[Teig];Mehl,1,EL",
+            &get_names(),
+        );
+        let problems: Vec<&str> = diagnostics.iter().map(|d| d.problem.as_str()).collect();
+        assert_eq!(
+            problems,
+            vec!["duplicate section 'Teig'", "duplicate ingredient 'Mehl'"]
+        );
+    }
+
+    #[test]
+    fn test_check_sub_recipes() {
+        let (diagnostics, _names) = check_recipes(
+            "Lasagne;Mehl,1,KG;@Bolognese;@Bechamel,1/2\nBolognese;@Lasagne,1/0\nSuppe;@Pesto,2;@Pesto",
+            &get_names(),
+        );
+        let problems: Vec<(usize, &str)> = diagnostics
+            .iter()
+            .map(|d| (d.line, d.problem.as_str()))
+            .collect();
+        assert_eq!(
+            problems,
+            vec![
+                (
+                    1,
+                    "recipe references itself: Lasagne -> Bolognese -> Lasagne"
+                ),
+                (1, "unknown recipe 'Bechamel'"),
+                (2, "invalid share '1/0' for 'Lasagne'"),
+                (
+                    2,
+                    "recipe references itself: Bolognese -> Lasagne -> Bolognese"
+                ),
+                (3, "duplicate sub recipe 'Pesto'"),
+                (3, "unknown recipe 'Pesto'")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_recipe_details() {
+        let mut recipes = HashSet::new();
+        recipes.insert("Waffel".to_string());
+
+        let diagnostics = check_recipe_details(
+            "servings: 2\n== Waffel\nservings: x\n== Suppe\nstep: Kochen\n== Waffel\n",
+            &recipes,
+        );
+        let problems: Vec<(usize, &str)> = diagnostics
+            .iter()
+            .map(|d| (d.line, d.problem.as_str()))
+            .collect();
+        assert_eq!(
+            problems,
+            vec![
+                (1, "details without recipe name"),
+                (3, "invalid number 'x' for 'servings'"),
+                (4, "unknown recipe 'Suppe'"),
+                (6, "duplicate details for 'Waffel'")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_shopping_list() {
+        let diagnostics = check_shopping_list("Mehl;1\nMais;2\nMilch;0", &get_names());
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].line, 2);
+        assert_eq!(
+            diagnostics[0].problem,
+            "orphaned entry 'Mais' without ingredient"
+        );
+        assert_eq!(diagnostics[1].line, 3);
+        assert_eq!(diagnostics[1].problem, "invalid amount '0'");
+    }
+
+    #[test]
+    fn test_to_json() {
+        let diagnostics = vec![Diagnostic::new(
+            "a.csv",
+            3,
+            "unknown recipe 'Soße \"fein\"'".to_string(),
+        )];
+        assert_eq!(
+            Diagnostic::all_to_json(&diagnostics),
+            "[{\"file\": \"a.csv\", \"line\": 3, \"problem\": \"unknown recipe 'Soße \\\"fein\\\"'\"}]"
+        );
+        assert_eq!(
+            format!("{}", diagnostics[0]),
+            "a.csv:3: unknown recipe 'Soße \"fein\"'"
+        );
+    }
+
+    #[test]
+    fn test_check_pantry() {
+        let diagnostics = check_pantry(
+            "Mehl;500;G;2019-05-01\nMais;2;Dose\nMilch;x;Eimer;morgen",
+            &get_names(),
+        );
+        let problems: Vec<&str> = diagnostics.iter().map(|d| d.problem.as_str()).collect();
+        assert_eq!(
+            problems,
+            vec![
+                "orphaned entry 'Mais' without ingredient",
+                "invalid amount 'x'",
+                "unknown unit 'Eimer'",
+                "invalid best before date 'morgen'"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_meal_plan() {
+        let mut recipes = HashSet::new();
+        recipes.insert("Waffel".to_string());
+
+        let diagnostics = check_meal_plan(
+            "2019-04-01;0;Waffel\n2019-02-30;3;Waffel\n2019-04-02;1;Suppe",
+            &recipes,
+        );
+        let lines: Vec<usize> = diagnostics.iter().map(|d| d.line).collect();
+        assert_eq!(lines, vec![2, 2, 3]);
+        assert_eq!(diagnostics[0].problem, "invalid date '2019-02-30'");
+        assert_eq!(diagnostics[1].problem, "invalid slot number '3'");
+        assert_eq!(diagnostics[2].problem, "unknown recipe 'Suppe'");
+    }
+
+    #[test]
+    fn test_check_history() {
+        let mut recipes = HashSet::new();
+        recipes.insert("Waffel".to_string());
+
+        let diagnostics = check_history(
+            "2019-04-01;Waffel;4;lecker\n2019-04-02;Waffel;;\n2019-04-03;Suppe;0;a;b",
+            &recipes,
+        );
+        let problems: Vec<&str> = diagnostics.iter().map(|d| d.problem.as_str()).collect();
+        assert_eq!(
+            problems,
+            vec![
+                "unknown recipe 'Suppe'",
+                "invalid rating '0'",
+                "unexpected additional field"
+            ]
+        );
+    }
+}
@@ -0,0 +1,930 @@
+use crate::cooking_book::ingredient::Ingredient;
+use crate::cooking_book::ingredient_parser;
+use crate::cooking_book::recipe::{IngredientLine, Recipe};
+use crate::cooking_book::recipe_details::{Difficulty, RecipeDetails, Step};
+use crate::cooking_book::unit;
+use crate::file_access::recipe_import;
+use crate::file_access::recipe_import::ImportedRecipe;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+/// Replaces the block comments while reading a recipe.
+const COMMENT: char = '\u{1}';
+
+/// Imports a recipe written in Cooklang, see https://cooklang.org/docs/spec/.
+/// Ingredients like `@Mehl{300%g}(gesiebt)` become ingredient lines referenced in the steps
+/// as `{Mehl}`, cookware like `#Topf{}` becomes equipment and timers like `~{10%minutes}`
+/// become `{10 min}`. Other recipes like `@./Pizzateig{1/2}` are used as sub recipes.
+/// A line like `= Teig` starts a section for the following ingredients.
+/// The metadata is read from lines like `>> servings: 2` or a front matter,
+/// notes from lines starting with `>`. Comments are left out.
+/// Uses of an ingredient with units which can't be converted to each other are skipped,
+/// just like ingredients with names which aren't valid, see `Ingredient::is_valid_name`.
+///
+/// #Arguments
+///
+/// * `name` The name of the recipe, usually the file name. A `title` in the metadata is preferred.
+/// * `text` The Cooklang text.
+/// * `all_ingredients` The collection of all ingredients.
+pub fn from_cooklang(
+    name: &str,
+    text: &str,
+    all_ingredients: &HashMap<String, Ingredient>,
+) -> Result<ImportedRecipe, String> {
+    let text = remove_block_comments(text);
+    let mut recipe = Recipe {
+        name: recipe_import::clean(name),
+        ingredients: Vec::new(),
+        sub_recipes: HashMap::new(),
+        tags: HashSet::new(),
+        details: RecipeDetails::new(),
+    };
+    let mut total_time: Option<u16> = None;
+    let mut notes: Vec<String> = Vec::new();
+
+    let mut lines = text.lines().peekable();
+    if lines.peek().map(|l| l.trim()) == Some("---") {
+        lines.next();
+        let mut metadata: Vec<(String, String)> = Vec::new();
+        for line in lines.by_ref() {
+            if line.trim() == "---" {
+                break;
+            }
+            if line.trim().starts_with("- ") {
+                // a list like the tags in YAML
+                if let Some((_key, value)) = metadata.last_mut() {
+                    if !value.is_empty() {
+                        value.push_str(", ");
+                    }
+                    value.push_str(line.trim()[2..].trim());
+                }
+                continue;
+            }
+            if let Some(colon) = line.find(':') {
+                metadata.push((line[..colon].to_string(), line[colon + 1..].to_string()));
+            }
+        }
+        for (key, value) in metadata {
+            add_metadata(&key, &value, &mut recipe, &mut total_time, &mut notes);
+        }
+    }
+
+    let mut paragraphs: Vec<(String, String)> = Vec::new();
+    let mut section = String::new();
+    let mut paragraph = String::new();
+    for line in lines {
+        let is_comment = line.contains(COMMENT) || line.trim_start().starts_with("--");
+        let line = match line.find("--") {
+            Some(comment) => &line[..comment],
+            None => line,
+        };
+        let line = line.replace(COMMENT, "");
+        let line = line.trim();
+        if line.is_empty() && is_comment {
+            // a line of comments doesn't end the step
+            continue;
+        }
+        if line.starts_with(">>") {
+            if let Some(colon) = line.find(':') {
+                let key = &line[2..colon];
+                add_metadata(
+                    key,
+                    &line[colon + 1..],
+                    &mut recipe,
+                    &mut total_time,
+                    &mut notes,
+                );
+            }
+            continue;
+        }
+        if line.starts_with('>') {
+            notes.push(recipe_import::clean(&line[1..]));
+            continue;
+        }
+        if line.is_empty() || line.starts_with('=') {
+            if !paragraph.is_empty() {
+                paragraphs.push((section.to_string(), paragraph));
+                paragraph = String::new();
+            }
+            if line.starts_with('=') {
+                section = recipe_import::clean(&line.trim_matches('=').replace(',', " "));
+            }
+            continue;
+        }
+        if !paragraph.is_empty() {
+            paragraph.push(' ');
+        }
+        paragraph.push_str(line);
+    }
+    if !paragraph.is_empty() {
+        paragraphs.push((section, paragraph));
+    }
+
+    if recipe.name.is_empty() {
+        return Err("The recipe has no name".to_string());
+    }
+
+    let mut skipped: Vec<String> = Vec::new();
+    for (section, paragraph) in paragraphs {
+        let text = parse_step(
+            &paragraph,
+            &section,
+            all_ingredients,
+            &mut recipe,
+            &mut skipped,
+        );
+        if !text.is_empty() {
+            recipe.details.steps.push(Step { text });
+        }
+    }
+
+    if recipe.details.cook_time.is_none() {
+        if let Some(total_time) = total_time {
+            let prep_time = recipe.details.prep_time.unwrap_or(0);
+            recipe.details.cook_time = Some(total_time.saturating_sub(prep_time));
+        }
+    }
+    recipe.details.notes = notes.join("\n");
+    return Ok(ImportedRecipe::new(recipe, skipped, all_ingredients));
+}
+
+/// Imports all `.cook` files of a directory sorted by file name, see `from_cooklang`.
+/// The file name without the extension is used as the name of the recipe.
+/// Returns the file names with the imported recipe or the problem of each file.
+///
+/// #Arguments
+///
+/// * `directory` The path of the directory.
+/// * `all_ingredients` The collection of all ingredients.
+pub fn from_directory(
+    directory: &str,
+    all_ingredients: &HashMap<String, Ingredient>,
+) -> Result<Vec<(String, Result<ImportedRecipe, String>)>, String> {
+    let entries = match fs::read_dir(directory) {
+        Ok(entries) => entries,
+        Err(e) => return Err(format!("Can't read directory '{}': {}", directory, e)),
+    };
+    let mut paths: Vec<_> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_file() && p.extension().map_or(false, |e| e == "cook"))
+        .collect();
+    paths.sort();
+
+    let mut imported = Vec::with_capacity(paths.len());
+    for path in paths {
+        let file_name = path
+            .file_name()
+            .map_or(String::new(), |n| n.to_string_lossy().to_string());
+        let name = path
+            .file_stem()
+            .map_or(String::new(), |n| n.to_string_lossy().to_string());
+        let recipe = match fs::read_to_string(&path) {
+            Ok(text) => from_cooklang(&name, &text, all_ingredients),
+            Err(e) => Err(format!("Can't read file: {}", e)),
+        };
+        imported.push((file_name, recipe));
+    }
+    return Ok(imported);
+}
+
+/// Exports a recipe to Cooklang, see `from_cooklang`.
+/// The first reference to an ingredient in the steps carries its amount, unit and note.
+/// Ingredients, sub recipes and equipment not mentioned in any step are listed
+/// after the steps.
+///
+/// #Arguments
+///
+/// * `recipe` The recipe to export.
+pub fn to_cooklang(recipe: &Recipe) -> String {
+    let details = &recipe.details;
+    let mut text = String::new();
+    if let Some(servings) = details.servings {
+        text.push_str(&format!(">> servings: {}\n", servings));
+    }
+    if let Some(prep_time) = details.prep_time {
+        text.push_str(&format!(">> prep time: {} minutes\n", prep_time));
+    }
+    if let Some(cook_time) = details.cook_time {
+        text.push_str(&format!(">> cook time: {} minutes\n", cook_time));
+    }
+    if let Some(difficulty) = details.difficulty {
+        text.push_str(&format!(">> difficulty: {}\n", difficulty.id()));
+    }
+    if !details.source.is_empty() {
+        text.push_str(&format!(">> source: {}\n", details.source));
+    }
+    let mut tags: Vec<&str> = recipe
+        .tags
+        .iter()
+        .map(|t| t.trim_start_matches('#'))
+        .collect();
+    tags.sort();
+    if !tags.is_empty() {
+        text.push_str(&format!(">> tags: {}\n", tags.join(", ")));
+    }
+    for note in details.notes.lines() {
+        text.push_str(&format!("> {}\n", note));
+    }
+
+    let mut marks: Vec<(&str, String)> = details
+        .equipment
+        .iter()
+        .map(|e| (e.as_str(), format!("#{}{{}}", e)))
+        .collect();
+    let mut sub_recipes: Vec<(&String, &(u16, u16))> = recipe.sub_recipes.iter().collect();
+    sub_recipes.sort();
+    for (name, share) in sub_recipes {
+        let share = match share {
+            (1, 1) => String::new(),
+            share => Recipe::share_to_string(share),
+        };
+        marks.push((name, format!("@./{}{{{}}}", name, share)));
+    }
+
+    let mut paragraphs: Vec<String> = Vec::new();
+    let mut used = vec![false; recipe.ingredients.len()];
+    let mut marked = vec![false; marks.len()];
+    let mut section = "";
+    for step in &details.steps {
+        let mut paragraph = String::new();
+        let mut step_section: Option<&str> = None;
+        let mut rest = step.text.as_str();
+        while let Some(start) = rest.find('{') {
+            let end = match rest[start..].find('}') {
+                Some(end) => start + end,
+                None => break,
+            };
+            paragraph.push_str(&mark_mentions(&rest[..start], &marks, &mut marked));
+            let reference = rest[start + 1..end].trim();
+            rest = &rest[end + 1..];
+
+            if let Some(minutes) = parse_timer_reference(reference) {
+                paragraph.push_str(&format!("~{{{}%minutes}}", minutes));
+                continue;
+            }
+            let line = (0..recipe.ingredients.len())
+                .find(|i| !used[*i] && recipe.ingredients[*i].ingredient.name == reference);
+            match line {
+                Some(i) => {
+                    used[i] = true;
+                    let line = &recipe.ingredients[i];
+                    if step_section.is_none() {
+                        step_section = Some(&line.section);
+                    }
+                    paragraph.push_str(&ingredient_to_cooklang(line));
+                }
+                None => paragraph.push_str(&format!("@{}{{}}", reference)),
+            }
+        }
+        paragraph.push_str(&mark_mentions(rest, &marks, &mut marked));
+
+        if let Some(step_section) = step_section {
+            if step_section != section {
+                section = step_section;
+                paragraphs.push(format!("= {}", section).trim().to_string());
+            }
+        }
+        paragraphs.push(paragraph);
+    }
+
+    let mut listed: Vec<String> = Vec::new();
+    for (i, line) in recipe.ingredients.iter().enumerate() {
+        if used[i] {
+            continue;
+        }
+        if line.section != section {
+            if !listed.is_empty() {
+                paragraphs.push(listed.join(", "));
+                listed.clear();
+            }
+            section = &line.section;
+            paragraphs.push(format!("= {}", section).trim().to_string());
+        }
+        listed.push(ingredient_to_cooklang(line));
+    }
+    for (i, (_name, mark)) in marks.iter().enumerate() {
+        if !marked[i] {
+            listed.push(mark.to_string());
+        }
+    }
+    if !listed.is_empty() {
+        paragraphs.push(listed.join(", "));
+    }
+
+    if !text.is_empty() && !paragraphs.is_empty() {
+        text.push('\n');
+    }
+    text.push_str(&paragraphs.join("\n\n"));
+    if !paragraphs.is_empty() {
+        text.push('\n');
+    }
+    return text;
+}
+
+/// Exports recipes to a directory, one `.cook` file per recipe named like the recipe.
+/// The directory is created if it doesn't exist. Returns the number of written files.
+///
+/// #Arguments
+///
+/// * `directory` The path of the directory.
+/// * `recipes` The recipes to export.
+pub fn to_directory(directory: &str, recipes: &Vec<&Recipe>) -> Result<usize, String> {
+    if let Err(e) = fs::create_dir_all(directory) {
+        return Err(format!("Can't create directory '{}': {}", directory, e));
+    }
+    for recipe in recipes {
+        let file_name = format!(
+            "{}.cook",
+            recipe.name.replace(|c| c == '/' || c == '\\', "-")
+        );
+        let path = Path::new(directory).join(file_name);
+        if let Err(e) = fs::write(&path, to_cooklang(recipe)) {
+            return Err(format!("Can't write file '{}': {}", path.display(), e));
+        }
+    }
+    return Ok(recipes.len());
+}
+
+/// Reads one metadata entry like `servings: 2`. Unknown keys are ignored.
+fn add_metadata(
+    key: &str,
+    value: &str,
+    recipe: &mut Recipe,
+    total_time: &mut Option<u16>,
+    notes: &mut Vec<String>,
+) {
+    let key = key
+        .trim()
+        .to_lowercase()
+        .replace(|c| c == '_' || c == '-' || c == '.', " ");
+    let value = recipe_import::clean(value);
+    let number = value
+        .split(|c: char| !c.is_ascii_digit())
+        .find(|n| !n.is_empty())
+        .and_then(|n| n.parse::<u16>().ok());
+    let details = &mut recipe.details;
+    match key.as_str() {
+        "title" if !value.is_empty() => recipe.name = value,
+        "servings" | "serves" | "yield" => details.servings = number,
+        "source" | "source url" | "url" => details.source = value,
+        "prep time" | "time prep" => details.prep_time = parse_time(&value),
+        "cook time" | "time cook" => details.cook_time = parse_time(&value),
+        "time" | "total time" | "duration" | "time required" => *total_time = parse_time(&value),
+        "difficulty" => {
+            details.difficulty = match value.to_lowercase().as_str() {
+                "easy" | "einfach" => Some(Difficulty::Easy),
+                "medium" | "mittel" => Some(Difficulty::Medium),
+                "hard" | "schwer" => Some(Difficulty::Hard),
+                _ => number.map(|n| Difficulty::lookup_difficulty_number(n as usize)),
+            }
+        }
+        "tags" => {
+            for tag in value.trim_matches(|c| c == '[' || c == ']').split(',') {
+                let tag = tag.trim().trim_start_matches('#');
+                if !tag.is_empty() {
+                    recipe.tags.insert(format!("#{}", tag));
+                }
+            }
+        }
+        "description" | "introduction" | "notes" if !value.is_empty() => notes.push(value),
+        _ => {}
+    }
+}
+
+/// Converts the text of a step to a step of the details, adding the ingredients,
+/// sub recipes and equipment to the recipe.
+fn parse_step(
+    text: &str,
+    section: &str,
+    all_ingredients: &HashMap<String, Ingredient>,
+    recipe: &mut Recipe,
+    skipped: &mut Vec<String>,
+) -> String {
+    let mut step = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find(|c: char| c == '@' || c == '#' || c == '~') {
+        // braces would be read as references to ingredients or timers
+        step.push_str(&rest[..start].replace('{', "(").replace('}', ")"));
+        let marker = &rest[start..start + 1];
+        rest = &rest[start + 1..];
+        let (name, quantity, end) = match split_token(rest, marker == "~") {
+            Some(token) => token,
+            None => {
+                step.push_str(marker);
+                continue;
+            }
+        };
+        rest = &rest[end..];
+
+        match marker {
+            "@" => {
+                let mut note = "";
+                if rest.starts_with('(') {
+                    if let Some(close) = rest.find(')') {
+                        note = &rest[1..close];
+                        rest = &rest[close + 1..];
+                    }
+                }
+                if name.starts_with("./") {
+                    let name = name.rsplit('/').next().unwrap_or("").trim();
+                    let share = Recipe::parse_share(quantity).unwrap_or((1, 1));
+                    recipe.sub_recipes.insert(name.to_string(), share);
+                    step.push_str(name);
+                    continue;
+                }
+                let reference = add_ingredient(
+                    name,
+                    quantity,
+                    note,
+                    section,
+                    all_ingredients,
+                    &mut recipe.ingredients,
+                    skipped,
+                );
+                match reference {
+                    Some(reference) => step.push_str(&format!("{{{}}}", reference)),
+                    None => step.push_str(name),
+                }
+            }
+            "#" => {
+                if !recipe.details.equipment.iter().any(|e| e == name) {
+                    recipe.details.equipment.push(name.to_string());
+                }
+                step.push_str(name);
+            }
+            _ => match parse_timer(quantity) {
+                Some(minutes) => step.push_str(&format!("{{{} min}}", minutes)),
+                None => {
+                    let timer = format!("{} {}", name, quantity.replace('%', " "));
+                    step.push_str(timer.trim());
+                }
+            },
+        }
+    }
+    step.push_str(&rest.replace('{', "(").replace('}', ")"));
+    return recipe_import::clean(&step);
+}
+
+/// Splits the token after a marker into its name and quantity and returns where it ends.
+/// A name of several words is ended by braces, a single word may go without them.
+/// Only timers may have no name.
+fn split_token(text: &str, is_timer: bool) -> Option<(&str, &str, usize)> {
+    if let Some(brace) = text.find('{') {
+        let name = &text[..brace];
+        let is_name = name.chars().all(|c| {
+            c.is_alphanumeric()
+                || c == ' '
+                || c == '-'
+                || c == '_'
+                || c == '\''
+                || c == '.'
+                || c == '/'
+        });
+        if is_name && !(name.starts_with(' ') || name.contains("  ")) {
+            let close = brace + text[brace..].find('}')?;
+            let name = name.trim();
+            if name.is_empty() && !is_timer {
+                return None;
+            }
+            return Some((name, text[brace + 1..close].trim(), close + 1));
+        }
+    }
+    let end = text
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(text.len());
+    if end == 0 {
+        return None;
+    }
+    return Some((&text[..end], "", end));
+}
+
+/// Adds one use of an ingredient like `300%g` to the section. Uses of the same ingredient
+/// in a section are summed up. Returns the name to reference the ingredient in the step,
+/// or `None` if the name of a new ingredient isn't valid.
+fn add_ingredient(
+    name: &str,
+    quantity: &str,
+    note: &str,
+    section: &str,
+    all_ingredients: &HashMap<String, Ingredient>,
+    ingredients: &mut Vec<IngredientLine>,
+    skipped: &mut Vec<String>,
+) -> Option<String> {
+    let ingredient = match Ingredient::find_similar(all_ingredients, name) {
+        Some(ingredient) => ingredient.clone(),
+        None => {
+            let cleaned = recipe_import::clean(name);
+            if !Ingredient::is_valid_name(&cleaned) {
+                skipped.push(format!("@{}{{{}}}", name, quantity));
+                return None;
+            }
+            Ingredient::new_by_name(cleaned)
+        }
+    };
+    let reference = ingredient.name.to_string();
+
+    let (number, unit_text) = match quantity.find('%') {
+        Some(percent) => (quantity[..percent].trim(), quantity[percent + 1..].trim()),
+        None => (quantity, ""),
+    };
+    let mut notes: Vec<&str> = Vec::new();
+    let mut amount = 0.0;
+    let mut unit = "";
+    match parse_number(number) {
+        Some(number) => match ingredient_parser::lookup_unit(unit_text) {
+            Some((u, factor)) => {
+                amount = number * factor;
+                unit = u;
+            }
+            None => {
+                amount = number;
+                notes.push(unit_text);
+            }
+        },
+        None => notes.push(quantity),
+    }
+    if amount.fract() != 0.0 && (unit == "KG" || unit == "L") {
+        amount *= 1000.0;
+        unit = if unit == "KG" { "G" } else { "ML" };
+    }
+    let amount = if amount.ceil() > u16::max_value() as f64 {
+        u16::max_value()
+    } else {
+        amount.ceil() as u16
+    };
+    notes.push(note.trim());
+    notes.retain(|n| !n.is_empty());
+    // commas separate the values in the recipes file
+    let note = recipe_import::clean(&notes.join(", ").replace(',', " /"));
+
+    let existing = ingredients
+        .iter_mut()
+        .find(|i| i.section == section && i.ingredient.name == reference);
+    let existing = match existing {
+        Some(existing) => existing,
+        None => {
+            let mut line = IngredientLine::new(ingredient, amount, unit, section);
+            line.note = note;
+            ingredients.push(line);
+            return Some(reference);
+        }
+    };
+
+    if existing.amount == 0 && existing.unit.is_empty() {
+        existing.amount = amount;
+        existing.unit = unit.to_string();
+    } else if amount != 0 {
+        let target = unit::smaller_unit(&existing.unit, unit).to_string();
+        let sum = unit::convert(existing.amount as u32, &existing.unit, &target)
+            .and_then(|a| unit::convert(amount as u32, unit, &target).map(|b| a + b));
+        match sum {
+            Some(sum) => {
+                existing.amount = sum.min(u16::max_value() as u32) as u16;
+                existing.unit = target;
+            }
+            None => {
+                skipped.push(format!("@{}{{{}}}", name, quantity));
+                return Some(reference);
+            }
+        }
+    }
+    if existing.note.is_empty() {
+        existing.note = note;
+    } else if !note.is_empty() && existing.note != note {
+        existing.note = format!("{} / {}", existing.note, note);
+    }
+    return Some(reference);
+}
+
+/// Renders an ingredient line like `@Mehl{300%G}(gesiebt)`.
+fn ingredient_to_cooklang(line: &IngredientLine) -> String {
+    let quantity = match (line.amount, line.unit.as_str()) {
+        (0, _) => String::new(),
+        (amount, "") => amount.to_string(),
+        (amount, unit) => format!("{}%{}", amount, unit),
+    };
+    let mut text = format!("@{}{{{}}}", line.ingredient.name, quantity);
+    if !line.note.is_empty() {
+        text.push_str(&format!("({})", line.note));
+    }
+    return text;
+}
+
+/// Marks the first mention of each name not marked yet, like equipment as `#Topf{}`.
+fn mark_mentions(text: &str, marks: &Vec<(&str, String)>, marked: &mut Vec<bool>) -> String {
+    let mut text = text.to_string();
+    for (i, (name, mark)) in marks.iter().enumerate() {
+        if marked[i] {
+            continue;
+        }
+        if let Some(start) = find_word(&text, name) {
+            text.replace_range(start..start + name.len(), mark);
+            marked[i] = true;
+        }
+    }
+    return text;
+}
+
+/// Returns the position of a word which isn't part of a longer word or a marked name.
+fn find_word(text: &str, word: &str) -> Option<usize> {
+    let is_boundary = |c: Option<char>| {
+        c.map_or(true, |c| {
+            !(c.is_alphanumeric() || c == '#' || c == '@' || c == '/')
+        })
+    };
+    let mut start = 0;
+    while let Some(position) = text[start..].find(word) {
+        let position = start + position;
+        let end = position + word.len();
+        if is_boundary(text[..position].chars().last()) && is_boundary(text[end..].chars().next()) {
+            return Some(position);
+        }
+        start = position + text[position..].chars().next().map_or(1, |c| c.len_utf8());
+    }
+    return None;
+}
+
+/// Returns the minutes of a timer reference like `10 min`.
+fn parse_timer_reference(reference: &str) -> Option<u16> {
+    if !reference.ends_with(" min") {
+        return None;
+    }
+    return reference[..reference.len() - 4].trim().parse::<u16>().ok();
+}
+
+/// Parses the quantity of a timer like `10%minutes` or `1.5%hours` into minutes.
+/// Seconds are rounded up.
+fn parse_timer(quantity: &str) -> Option<u16> {
+    let percent = quantity.find('%')?;
+    let number = parse_number(&quantity[..percent])?;
+    let minutes = to_minutes(number, &quantity[percent + 1..])?;
+    return Some(minutes.ceil().min(u16::max_value() as f64) as u16);
+}
+
+/// Parses a time like `15 minutes`, `1 hour 30 minutes`, `1h30m` or `PT45M` into minutes.
+/// A number without unit is taken as minutes.
+fn parse_time(text: &str) -> Option<u16> {
+    if text.starts_with('P') {
+        return recipe_import::parse_duration(text);
+    }
+    let mut spaced = String::with_capacity(text.len());
+    let mut last_is_digit = false;
+    for c in text.chars() {
+        let is_digit = c.is_ascii_digit() || c == '.' || c == '/';
+        if is_digit != last_is_digit {
+            spaced.push(' ');
+        }
+        spaced.push(c);
+        last_is_digit = is_digit;
+    }
+
+    let mut minutes = 0.0;
+    let mut words = spaced.split_whitespace().peekable();
+    while let Some(word) = words.next() {
+        let number = parse_number(word)?;
+        let unit = match words.peek() {
+            Some(unit) if parse_number(unit).is_none() => words.next().unwrap(),
+            _ => "",
+        };
+        minutes += to_minutes(number, unit)?;
+    }
+    if minutes == 0.0 || minutes > u16::max_value() as f64 {
+        return None;
+    }
+    return Some(minutes.ceil() as u16);
+}
+
+fn to_minutes(number: f64, unit: &str) -> Option<f64> {
+    return match unit.trim().to_lowercase().trim_end_matches('.') {
+        "" | "m" | "min" | "mins" | "minute" | "minutes" | "minute(s)" | "minuten" => Some(number),
+        "h" | "hr" | "hrs" | "hour" | "hours" | "std" | "stunde" | "stunden" => Some(number * 60.0),
+        "s" | "sec" | "secs" | "second" | "seconds" | "sek" | "sekunden" => Some(number / 60.0),
+        _ => None,
+    };
+}
+
+/// Parses a number like `2`, `1.5`, `1,5`, `1/2` or `1 1/2`.
+fn parse_number(text: &str) -> Option<f64> {
+    let mut number = 0.0;
+    let mut is_empty = true;
+    for part in text.split_whitespace() {
+        number += match part.find('/') {
+            Some(slash) => {
+                let numerator = part[..slash].parse::<f64>().ok()?;
+                let denominator = part[slash + 1..].parse::<f64>().ok()?;
+                if denominator == 0.0 {
+                    return None;
+                }
+                numerator / denominator
+            }
+            None => part.replace(',', ".").parse::<f64>().ok()?,
+        };
+        is_empty = false;
+    }
+    if is_empty || !number.is_finite() || number < 0.0 {
+        return None;
+    }
+    return Some(number);
+}
+
+/// Replaces block comments like `[- TODO -]` by `COMMENT`.
+fn remove_block_comments(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("[-") {
+        result.push_str(&rest[..start]);
+        result.push(COMMENT);
+        rest = match rest[start..].find("-]") {
+            Some(end) => &rest[start + end + 2..],
+            None => "",
+        };
+    }
+    result.push_str(rest);
+    return result;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{add_ingredient, from_cooklang, parse_time, parse_timer, to_cooklang};
+    use crate::cooking_book::ingredient::Ingredient;
+    use crate::cooking_book::recipe::{IngredientLine, Recipe};
+    use crate::cooking_book::recipe_details::{Difficulty, Step};
+    use std::collections::HashMap;
+
+    fn import(name: &str, text: &str) -> Recipe {
+        let mut all_ingredients: HashMap<String, Ingredient> = HashMap::new();
+        let butter = Ingredient::new_by_name("Butter".to_string());
+        all_ingredients.insert(butter.name.to_string(), butter);
+        return from_cooklang(name, text, &all_ingredients).unwrap().recipe;
+    }
+
+    fn assert_round_trip(text: &str) -> Recipe {
+        let recipe = import("Test", text);
+        let exported = to_cooklang(&recipe);
+        // the file name is the name of the recipe
+        let imported = import(&recipe.name, &exported);
+        assert!(recipe == imported, "changed by round trip:\n{}", exported);
+        assert_eq!(to_cooklang(&imported), exported);
+        return recipe;
+    }
+
+    #[test]
+    fn test_spec_ingredients() {
+        let recipe = assert_round_trip(
+            "Then add @salt and @ground black pepper{} to taste.\n\nPoke holes in @potato{2}.\n\nPlace @bacon strips{1%kg} on a baking sheet and glaze with @syrup{1/2%tbsp}.\n\nFry in @butter{30%g}(softened).",
+        );
+        let lines: Vec<String> = recipe
+            .ingredients
+            .iter()
+            .map(|i| format!("{},{},{},{}", i.ingredient.name, i.amount, i.unit, i.note))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                "salt,0,,",
+                "ground black pepper,0,,",
+                "potato,2,,",
+                "bacon strips,1,KG,",
+                "syrup,1,EL,",
+                "Butter,30,G,softened"
+            ]
+        );
+        assert_eq!(
+            recipe.details.steps[0].text,
+            "Then add {salt} and {ground black pepper} to taste."
+        );
+        assert_eq!(recipe.details.steps[3].text, "Fry in {Butter}.");
+    }
+
+    #[test]
+    fn test_spec_cookware_and_timers() {
+        let recipe = assert_round_trip(
+            "Place the potatoes into a #pot.\nMash the potatoes with a #potato masher{}.\n\nLay the potatoes on a #baking sheet{} and place into the oven. Bake for ~{25%minutes}.\n\nBoil @eggs{2} for ~eggs{3%minutes}, then rest ~{1.5%hours}.",
+        );
+        assert_eq!(
+            recipe.details.equipment,
+            vec!["pot", "potato masher", "baking sheet"]
+        );
+        assert_eq!(recipe.details.steps.len(), 3);
+        assert_eq!(
+            recipe.details.steps[0].text,
+            "Place the potatoes into a pot. Mash the potatoes with a potato masher."
+        );
+        assert_eq!(recipe.details.steps[1].get_timer(), Some(25));
+        assert_eq!(
+            recipe.details.steps[2].text,
+            "Boil {eggs} for {3 min}, then rest {90 min}."
+        );
+    }
+
+    #[test]
+    fn test_spec_metadata_and_comments() {
+        let recipe = assert_round_trip(
+            ">> servings: 2\n>> source: https://cooklang.org\n>> prep time: 15 minutes\n>> total time: 1h 30m\n>> difficulty: medium\n>> tags: Breakfast, #vegan\n> Keeps for a week.\n\n-- Don't burn the roux!\n\nMash @potato{2%kg} until smooth -- alternatively, boil 'em first\n[- TODO change units to metric -]\nand serve.",
+        );
+        assert_eq!(recipe.details.servings, Some(2));
+        assert_eq!(recipe.details.source, "https://cooklang.org");
+        assert_eq!(recipe.details.prep_time, Some(15));
+        assert_eq!(recipe.details.cook_time, Some(75));
+        assert_eq!(recipe.details.difficulty, Some(Difficulty::Medium));
+        assert!(recipe.tags.contains("#Breakfast"));
+        assert!(recipe.tags.contains("#vegan"));
+        assert_eq!(recipe.details.notes, "Keeps for a week.");
+        assert_eq!(recipe.details.steps.len(), 1);
+        assert_eq!(
+            recipe.details.steps[0].text,
+            "Mash {potato} until smooth and serve."
+        );
+    }
+
+    #[test]
+    fn test_sections_and_sub_recipes() {
+        let recipe = assert_round_trip(
+            "---\ntitle: Pizza\ntags:\n  - Italian\n---\n= Dough\n\nMix @flour{200%g} and @water{50%ml} together until smooth.\nAdd @flour{0.1%kg} if sticky.\n\n== Filling ==\n\nCombine @cheese{100%g} and @./Tomatensauce{1/2}, then season with @salt{}(to taste).",
+        );
+        assert_eq!(recipe.name, "Pizza");
+        assert!(recipe.tags.contains("#Italian"));
+        let lines: Vec<String> = recipe
+            .ingredients
+            .iter()
+            .map(|i| {
+                format!(
+                    "{},{},{},{}",
+                    i.section, i.ingredient.name, i.amount, i.unit
+                )
+            })
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                "Dough,flour,300,G",
+                "Dough,water,50,ML",
+                "Filling,cheese,100,G",
+                "Filling,salt,0,"
+            ]
+        );
+        assert_eq!(recipe.sub_recipes.get("Tomatensauce"), Some(&(1, 2)));
+        assert_eq!(recipe.ingredients[3].note, "to taste");
+    }
+
+    #[test]
+    fn test_to_cooklang() {
+        let mut recipe = import("Test", "@Butter{30%g} schmelzen.");
+        recipe.details.steps.clear();
+        recipe.details.equipment.push("Pfanne".to_string());
+        recipe.details.servings = Some(4);
+        recipe.tags.insert("#Frühstück".to_string());
+        assert_eq!(
+            to_cooklang(&recipe),
+            ">> servings: 4\n>> tags: Frühstück\n\n@Butter{30%G}, #Pfanne{}\n"
+        );
+
+        recipe.details.steps = vec![Step {
+            text: "In der Pfanne {Butter} schmelzen.".to_string(),
+        }];
+        assert_eq!(
+            to_cooklang(&recipe),
+            ">> servings: 4\n>> tags: Frühstück\n\nIn der #Pfanne{} @Butter{30%G} schmelzen.\n"
+        );
+    }
+
+    #[test]
+    fn test_skipped() {
+        let all_ingredients: HashMap<String, Ingredient> = HashMap::new();
+        let imported =
+            from_cooklang("Test", "@salt{1%tsp} and @salt{5%g}", &all_ingredients).unwrap();
+        assert_eq!(imported.skipped, vec!["@salt{5%g}"]);
+        assert_eq!(imported.unresolved, vec!["salt"]);
+
+        let mut ingredients: Vec<IngredientLine> = Vec::new();
+        let mut skipped: Vec<String> = Vec::new();
+        let reference = add_ingredient(
+            "Salz, Pfeffer",
+            "1%Prise",
+            "",
+            "",
+            &all_ingredients,
+            &mut ingredients,
+            &mut skipped,
+        );
+        assert_eq!(reference, None);
+        assert!(ingredients.is_empty());
+        assert_eq!(skipped, vec!["@Salz, Pfeffer{1%Prise}"]);
+        assert!(from_cooklang("", "@salt", &all_ingredients).is_err());
+    }
+
+    #[test]
+    fn test_parse_time() {
+        assert_eq!(parse_time("15 minutes"), Some(15));
+        assert_eq!(parse_time("1 hour 30 minutes"), Some(90));
+        assert_eq!(parse_time("1h30m"), Some(90));
+        assert_eq!(parse_time("PT45M"), Some(45));
+        assert_eq!(parse_time("20"), Some(20));
+        assert_eq!(parse_time("a while"), None);
+        assert_eq!(parse_timer("30%seconds"), Some(1));
+        assert_eq!(parse_timer("10"), None);
+    }
+}
@@ -0,0 +1,341 @@
+/// The most arrays and objects nested in each other, deeper documents are rejected.
+const MAX_DEPTH: usize = 128;
+
+/// A parsed JSON value. Object members keep their order.
+#[derive(PartialEq, Clone, Debug)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// Parses a JSON document.
+    /// Returns an error with the byte position if the text isn't valid JSON.
+    ///
+    /// #Arguments
+    ///
+    /// * `text` The text to parse.
+    pub fn parse(text: &str) -> Result<Json, String> {
+        let mut parser = Parser {
+            text: text.as_bytes(),
+            position: 0,
+            depth: 0,
+        };
+        let value = parser.parse_value()?;
+        parser.skip_whitespace();
+        if parser.position < parser.text.len() {
+            return Err(parser.error("unexpected content after the value"));
+        }
+        return Ok(value);
+    }
+
+    /// Returns the value of a member if this is an object containing it.
+    ///
+    /// #Arguments
+    ///
+    /// * `key` The name of the member.
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        return match self {
+            Json::Object(members) => members.iter().find(|(k, _v)| k == key).map(|(_k, v)| v),
+            _ => None,
+        };
+    }
+
+    /// Returns the text if this is a string.
+    pub fn as_str(&self) -> Option<&str> {
+        return match self {
+            Json::String(text) => Some(text),
+            _ => None,
+        };
+    }
+
+    /// Returns the number if this is a number.
+    pub fn as_number(&self) -> Option<f64> {
+        return match self {
+            Json::Number(number) => Some(*number),
+            _ => None,
+        };
+    }
+
+    /// Returns the elements of an array, or the value itself as the only element otherwise.
+    /// Null is an empty array.
+    pub fn as_list(&self) -> Vec<&Json> {
+        return match self {
+            Json::Array(elements) => elements.iter().collect(),
+            Json::Null => Vec::new(),
+            value => vec![value],
+        };
+    }
+
+    /// Escapes a text to be used inside a JSON string.
+    ///
+    /// #Arguments
+    ///
+    /// * `text` The text to escape.
+    pub fn escape(text: &str) -> String {
+        let mut escaped = String::with_capacity(text.len());
+        for c in text.chars() {
+            match c {
+                '"' => escaped.push_str("\\\""),
+                '\\' => escaped.push_str("\\\\"),
+                '\n' => escaped.push_str("\\n"),
+                '\r' => escaped.push_str("\\r"),
+                '\t' => escaped.push_str("\\t"),
+                c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+                c => escaped.push(c),
+            }
+        }
+        return escaped;
+    }
+}
+
+struct Parser<'a> {
+    text: &'a [u8],
+    position: usize,
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, message: &str) -> String {
+        return format!("Invalid JSON at position {}: {}", self.position, message);
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.position < self.text.len()
+            && (self.text[self.position] as char).is_ascii_whitespace()
+        {
+            self.position += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        return self.text.get(self.position).cloned();
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<(), String> {
+        if self.text[self.position..].starts_with(expected.as_bytes()) {
+            self.position += expected.len();
+            return Ok(());
+        }
+        return Err(self.error(&format!("expected '{}'", expected)));
+    }
+
+    fn parse_value(&mut self) -> Result<Json, String> {
+        self.skip_whitespace();
+        return match self.peek() {
+            Some(b'{') => self.parse_nested(Parser::parse_object),
+            Some(b'[') => self.parse_nested(Parser::parse_array),
+            Some(b'"') => Ok(Json::String(self.parse_string()?)),
+            Some(b't') => self.expect("true").map(|_| Json::Bool(true)),
+            Some(b'f') => self.expect("false").map(|_| Json::Bool(false)),
+            Some(b'n') => self.expect("null").map(|_| Json::Null),
+            Some(c) if c == b'-' || c.is_ascii_digit() => self.parse_number(),
+            Some(_) => Err(self.error("unexpected character")),
+            None => Err(self.error("unexpected end")),
+        };
+    }
+
+    fn parse_nested(
+        &mut self,
+        parse: fn(&mut Parser<'a>) -> Result<Json, String>,
+    ) -> Result<Json, String> {
+        if self.depth >= MAX_DEPTH {
+            return Err(self.error("nested too deeply"));
+        }
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        return value;
+    }
+
+    fn parse_object(&mut self) -> Result<Json, String> {
+        self.position += 1;
+        let mut members: Vec<(String, Json)> = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.position += 1;
+            return Ok(Json::Object(members));
+        }
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some(b'"') {
+                return Err(self.error("expected a member name"));
+            }
+            let key = self.parse_string()?;
+            self.skip_whitespace();
+            self.expect(":")?;
+            let value = self.parse_value()?;
+            members.push((key, value));
+
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                Some(b'}') => {
+                    self.position += 1;
+                    return Ok(Json::Object(members));
+                }
+                _ => return Err(self.error("expected ',' or '}'")),
+            }
+        }
+    }
+
+    fn parse_array(&mut self) -> Result<Json, String> {
+        self.position += 1;
+        let mut elements: Vec<Json> = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.position += 1;
+            return Ok(Json::Array(elements));
+        }
+        loop {
+            elements.push(self.parse_value()?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                Some(b']') => {
+                    self.position += 1;
+                    return Ok(Json::Array(elements));
+                }
+                _ => return Err(self.error("expected ',' or ']'")),
+            }
+        }
+    }
+
+    fn parse_number(&mut self) -> Result<Json, String> {
+        let start = self.position;
+        while let Some(c) = self.peek() {
+            if c.is_ascii_digit() || c == b'-' || c == b'+' || c == b'.' || c == b'e' || c == b'E' {
+                self.position += 1;
+            } else {
+                break;
+            }
+        }
+        let text = String::from_utf8_lossy(&self.text[start..self.position]);
+        return match text.parse::<f64>() {
+            Ok(number) => Ok(Json::Number(number)),
+            Err(_) => {
+                self.position = start;
+                Err(self.error("invalid number"))
+            }
+        };
+    }
+
+    fn parse_string(&mut self) -> Result<String, String> {
+        self.position += 1;
+        let mut bytes: Vec<u8> = Vec::new();
+        loop {
+            let c = match self.peek() {
+                Some(c) => c,
+                None => return Err(self.error("unterminated string")),
+            };
+            self.position += 1;
+            match c {
+                b'"' => break,
+                b'\\' => {
+                    let escaped = match self.peek() {
+                        Some(e) => e,
+                        None => return Err(self.error("unterminated string")),
+                    };
+                    self.position += 1;
+                    match escaped {
+                        b'"' | b'\\' | b'/' => bytes.push(escaped),
+                        b'b' => bytes.push(8),
+                        b'f' => bytes.push(12),
+                        b'n' => bytes.push(b'\n'),
+                        b'r' => bytes.push(b'\r'),
+                        b't' => bytes.push(b'\t'),
+                        b'u' => {
+                            let c = self.parse_unicode_escape()?;
+                            let mut buffer = [0; 4];
+                            bytes.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes());
+                        }
+                        _ => return Err(self.error("invalid escape")),
+                    }
+                }
+                c => bytes.push(c),
+            }
+        }
+        return match String::from_utf8(bytes) {
+            Ok(text) => Ok(text),
+            Err(_) => Err(self.error("invalid UTF-8 in string")),
+        };
+    }
+
+    fn parse_hex(&mut self) -> Result<u32, String> {
+        if self.position + 4 > self.text.len() {
+            return Err(self.error("invalid unicode escape"));
+        }
+        let hex = String::from_utf8_lossy(&self.text[self.position..self.position + 4]);
+        return match u32::from_str_radix(&hex, 16) {
+            Ok(code) => {
+                self.position += 4;
+                Ok(code)
+            }
+            Err(_) => Err(self.error("invalid unicode escape")),
+        };
+    }
+
+    fn parse_unicode_escape(&mut self) -> Result<char, String> {
+        let mut code = self.parse_hex()?;
+        if code >= 0xD800 && code < 0xDC00 && self.text[self.position..].starts_with(b"\\u") {
+            self.position += 2;
+            let low = self.parse_hex()?;
+            code = 0x10000 + ((code - 0xD800) << 10) + (low.wrapping_sub(0xDC00) & 0x3FF);
+        }
+        return Ok(std::char::from_u32(code).unwrap_or('\u{FFFD}'));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Json;
+
+    #[test]
+    fn test_parse() {
+        let json = Json::parse(
+            "{\"name\": \"K\\u00e4se\\\"kuchen\", \"n\": -1.5e1, \"a\": [true, null, {}], \"e\": []}",
+        )
+        .unwrap();
+        assert_eq!(json.get("name").unwrap().as_str(), Some("Käse\"kuchen"));
+        assert_eq!(json.get("n").unwrap().as_number(), Some(-15.0));
+        assert_eq!(
+            json.get("a").unwrap().as_list(),
+            vec![&Json::Bool(true), &Json::Null, &Json::Object(Vec::new())]
+        );
+        assert!(json.get("e").unwrap().as_list().is_empty());
+        assert_eq!(json.get("missing"), None);
+        assert_eq!(
+            Json::parse("\"\\ud83c\\udf70\"").unwrap(),
+            Json::String("🍰".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(Json::parse("{\"a\": }").is_err());
+        assert!(Json::parse("[1, 2").is_err());
+        assert!(Json::parse("\"open").is_err());
+        assert_eq!(
+            Json::parse("{} x").err(),
+            Some("Invalid JSON at position 3: unexpected content after the value".to_string())
+        );
+        assert_eq!(
+            Json::parse(&"[".repeat(100_000)).err(),
+            Some("Invalid JSON at position 128: nested too deeply".to_string())
+        );
+        assert!(Json::parse(&"{\"a\": [".repeat(100))
+            .unwrap_err()
+            .contains("nested too deeply"));
+        let deepest = format!("{}{}", "[".repeat(128), "]".repeat(128));
+        assert!(Json::parse(&deepest).is_ok());
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(Json::escape("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+}
@@ -1,3 +1,4 @@
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::fs;
 use std::fs::File;
@@ -5,17 +6,132 @@ use std::fs::OpenOptions;
 use std::io::prelude::*;
 use std::path::Path;
 
+use crate::account::accounts::{self, Accounts};
+use crate::cooking_book::date::Date;
+use crate::cooking_book::history::History;
 use crate::cooking_book::ingredient::Ingredient;
+use crate::cooking_book::meal_plan::{MealPlan, Slot};
+use crate::cooking_book::pantry::Pantry;
 use crate::cooking_book::recipe::Recipe;
+use crate::cooking_book::recipe_details::RecipeDetails;
 use crate::cooking_book::shopping_list::ShoppingList;
 
-mod paths {
+pub mod paths {
     pub const INGREDIENTS: &'static str = "persistency/ingredients.csv";
     pub const SHOPPING_LIST: &'static str = "persistency/shoppingList.csv";
     pub const RECIPES: &'static str = "persistency/recipes.csv";
+    pub const RECIPE_DETAILS: &'static str = "persistency/recipeDetails.txt";
+    pub const PANTRY: &'static str = "persistency/pantry.csv";
+    pub const MEAL_PLAN: &'static str = "persistency/mealPlan.csv";
+    pub const HISTORY: &'static str = "persistency/history.csv";
+
+    pub const ALL: [&'static str; 7] = [
+        INGREDIENTS,
+        RECIPES,
+        RECIPE_DETAILS,
+        SHOPPING_LIST,
+        PANTRY,
+        MEAL_PLAN,
+        HISTORY,
+    ];
+
+    /// The users and households, shared by all households.
+    pub const ACCOUNTS: &'static str = "persistency/accounts.csv";
+    /// The directory with one subdirectory of the files in `ALL` per household.
+    pub const HOUSEHOLDS: &'static str = "persistency/households";
+}
+
+thread_local! {
+    /// The household whose files are read and written by this thread.
+    static HOUSEHOLD: RefCell<Option<String>> = RefCell::new(None);
+}
+
+/// Selects the household whose files are read and written by the current thread.
+/// Without a household the files directly in `persistency` are used.
+///
+/// #Arguments
+///
+/// * `household` The id of the household.
+pub fn select_household(household: Option<&str>) {
+    HOUSEHOLD.with(|h| *h.borrow_mut() = household.map(|id| id.to_string()));
+}
+
+/// Returns the file of the selected household for one of the `paths::ALL`.
+/// Other file names are returned unchanged.
+///
+/// #Arguments
+///
+/// * `file_name` The file name like in `paths`.
+fn resolve(file_name: &str) -> String {
+    if !paths::ALL.contains(&file_name) {
+        return file_name.to_string();
+    }
+    return HOUSEHOLD.with(|h| match h.borrow().as_ref() {
+        Some(id) => {
+            file_name.replacen("persistency/", &format!("{}/{}/", paths::HOUSEHOLDS, id), 1)
+        }
+        None => file_name.to_string(),
+    });
+}
+
+/// Creates the directory of a new household.
+///
+/// #Arguments
+///
+/// * `id` The id of the household.
+pub fn create_household_directory(id: &str) -> Result<(), String> {
+    if !accounts::is_household_id(id) {
+        return Err(format!("Invalid household '{}'", id));
+    }
+    let directory = format!("{}/{}", paths::HOUSEHOLDS, id);
+    if let Err(e) = fs::create_dir_all(&directory) {
+        return Err(format!("Couldn't create directory {}: {}", directory, e));
+    }
+    return Ok(());
+}
+
+/// Moves the files directly in `persistency` into a household, so the first household keeps
+/// the data from before there were accounts. If a file can't be moved, the files moved so far
+/// are moved back.
+///
+/// #Arguments
+///
+/// * `id` The id of the household, whose directory exists.
+pub fn adopt_existing_files(id: &str) -> Result<(), String> {
+    let directory = format!("{}/{}", paths::HOUSEHOLDS, id);
+    let mut moved: Vec<(&str, String)> = Vec::new();
+    for file_name in paths::ALL.iter().filter(|f| Path::new(f).is_file()) {
+        let target = file_name.replacen("persistency", &directory, 1);
+        if let Err(e) = fs::rename(file_name, &target) {
+            for (file_name, target) in moved.iter().rev() {
+                let _ = fs::rename(target, file_name);
+            }
+            return Err(format!("Couldn't move file {}: {}", file_name, e));
+        }
+        moved.push((file_name, target));
+    }
+    return Ok(());
+}
+
+/// Returns the users and households.
+pub fn load_accounts() -> Accounts {
+    return Accounts::new_by_text(&fs::read_to_string(paths::ACCOUNTS).unwrap_or_default());
 }
 
+/// Writes the users and households.
+///
+/// #Arguments
+///
+/// * `accounts` The accounts to write.
+pub fn write_accounts(accounts: &Accounts) -> Result<(), String> {
+    return write_files_atomically(&[(paths::ACCOUNTS, accounts.to_text())]);
+}
+
+const RECIPES_HEADER: &'static str = "#name;(ingredient,amount,unit)...";
+
 fn load_file(file_name: &str) -> Option<String> {
+    let file_name = resolve(file_name);
+    let file_name = file_name.as_str();
     if Path::new(file_name).is_file() {
         return match fs::read_to_string(file_name) {
             Ok(c) => Some(c),
@@ -47,16 +163,16 @@ pub fn load_shopping_list() -> ShoppingList {
         return shopping_list;
     }
 
-    let mut all_ingredients = load_ingredients();
+    let all_ingredients = load_ingredients();
 
     for line in content.unwrap().lines() {
         let mut values = line.split(';');
         let name = values.next().unwrap().to_string();
 
-        if !all_ingredients.contains_key(&name) {
-            Ingredient::persist_new_ingredient(&name, &mut all_ingredients)
-                .unwrap_or_else(|e| eprintln!("{}", e));
-        }
+        let ingredient = match Ingredient::resolve(&all_ingredients, &name) {
+            Some(ingredient) => ingredient.clone(),
+            None => Ingredient::new_by_name(name),
+        };
 
         let amount = match values.next() {
             Some(x) => x,
@@ -68,7 +184,7 @@ pub fn load_shopping_list() -> ShoppingList {
             Err(_) => 1,
         };
 
-        shopping_list.add_item(all_ingredients.get(&name).unwrap().clone(), amount);
+        shopping_list.add_item(ingredient, amount);
     }
 
     return shopping_list;
@@ -80,20 +196,181 @@ pub fn load_shopping_list() -> ShoppingList {
 ///
 /// * `shopping_list` The shopping list to write.
 pub fn write_shopping_list(shopping_list: &ShoppingList) -> Result<(), String> {
-    let file = OpenOptions::new()
-        .write(true)
-        .truncate(true)
-        .open(paths::SHOPPING_LIST);
+    return write_files_atomically(&[(
+        paths::SHOPPING_LIST,
+        shopping_list_to_string(shopping_list),
+    )]);
+}
 
-    if file.is_ok() {
-        let mut file = file.unwrap();
-        for (ingredient, amount) in &shopping_list.to_buy {
-            if let Err(e) = writeln!(file, "{};{}", ingredient.name, amount) {
-                return Err(format!("Couldn't write to file: {}", e));
-            }
+/// Returns the ingredients at home.
+pub fn load_pantry() -> Pantry {
+    let mut pantry = Pantry::new();
+
+    let content = load_file(paths::PANTRY);
+    if content.is_none() {
+        return pantry;
+    }
+
+    let all_ingredients = load_ingredients();
+
+    for line in content.unwrap().lines() {
+        let mut values = line.split(';');
+        let name = values.next().unwrap().to_string();
+
+        let ingredient = match Ingredient::resolve(&all_ingredients, &name) {
+            Some(ingredient) => ingredient.clone(),
+            None => Ingredient::new_by_name(name),
+        };
+
+        let amount = match values.next().unwrap_or("").parse::<u16>() {
+            Ok(x) => x,
+            Err(_) => 0,
+        };
+        let unit = values.next().unwrap_or("");
+        let best_before = Date::parse(values.next().unwrap_or(""));
+
+        pantry.set(ingredient, amount, unit, best_before);
+    }
+
+    return pantry;
+}
+
+/// Writes the ingredients at home.
+///
+/// #Arguments
+///
+/// * `pantry` The pantry to write.
+pub fn write_pantry(pantry: &Pantry) -> Result<(), String> {
+    let mut content = String::new();
+    for (ingredient, item) in &pantry.stock {
+        content.push_str(&format!(
+            "{};{};{}",
+            ingredient.name, item.amount, item.unit
+        ));
+        if let Some(best_before) = item.best_before {
+            content.push_str(&format!(";{}", best_before));
         }
+        content.push('\n');
     }
-    return Ok(());
+    return write_files_atomically(&[(paths::PANTRY, content)]);
+}
+
+/// Returns the meal plan.
+pub fn load_meal_plan() -> MealPlan {
+    let mut meal_plan = MealPlan::new();
+
+    let content = load_file(paths::MEAL_PLAN);
+    if content.is_none() {
+        return meal_plan;
+    }
+
+    for line in content.unwrap().lines() {
+        let mut values = line.split(';');
+        let date = match Date::parse(values.next().unwrap()) {
+            Some(date) => date,
+            None => continue,
+        };
+        let slot = match values.next().unwrap_or("").parse::<usize>() {
+            Ok(num) => match Slot::lookup_slot_number(num) {
+                Some(slot) => slot,
+                None => continue,
+            },
+            Err(_) => continue,
+        };
+        let recipe = values.next().unwrap_or("");
+
+        meal_plan.plan(date, slot, recipe);
+    }
+
+    return meal_plan;
+}
+
+/// Writes the meal plan.
+///
+/// #Arguments
+///
+/// * `meal_plan` The meal plan to write.
+pub fn write_meal_plan(meal_plan: &MealPlan) -> Result<(), String> {
+    let mut content = String::new();
+    for ((date, slot), recipe) in &meal_plan.meals {
+        content.push_str(&format!("{};{};{}\n", date, *slot as u8, recipe));
+    }
+    return write_files_atomically(&[(paths::MEAL_PLAN, content)]);
+}
+
+/// Returns the cooking history.
+pub fn load_history() -> History {
+    let mut history = History::new();
+
+    let content = load_file(paths::HISTORY);
+    if content.is_none() {
+        return history;
+    }
+
+    for line in content.unwrap().lines() {
+        let mut values = line.split(';');
+        let date = match Date::parse(values.next().unwrap()) {
+            Some(date) => date,
+            None => continue,
+        };
+        let recipe = values.next().unwrap_or("");
+        let rating = values.next().unwrap_or("").parse::<u8>().ok();
+        let notes = decode_field(values.next().unwrap_or(""));
+
+        let _ = history.record(date, recipe, rating, &notes);
+    }
+
+    return history;
+}
+
+/// Writes the cooking history.
+///
+/// #Arguments
+///
+/// * `history` The history to write.
+pub fn write_history(history: &History) -> Result<(), String> {
+    let mut content = String::new();
+    for entry in &history.entries {
+        let rating = match entry.rating {
+            Some(rating) => rating.to_string(),
+            None => String::new(),
+        };
+        content.push_str(&format!(
+            "{};{};{};{}\n",
+            entry.date,
+            entry.recipe,
+            rating,
+            encode_field(&entry.notes)
+        ));
+    }
+    return write_files_atomically(&[(paths::HISTORY, content)]);
+}
+
+/// Percent encodes free text for a field, so semicolons and line breaks don't end the field
+/// or the line.
+///
+/// #Arguments
+///
+/// * `text` The text to encode.
+fn encode_field(text: &str) -> String {
+    return text
+        .replace('%', "%25")
+        .replace(';', "%3B")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A");
+}
+
+/// Decodes a field of `encode_field`.
+///
+/// #Arguments
+///
+/// * `field` The encoded field.
+fn decode_field(field: &str) -> String {
+    return field
+        .replace("%0A", "\n")
+        .replace("%0D", "\r")
+        .replace("%3B", ";")
+        .replace("%25", "%");
 }
 
 /// Returns all recipes.
@@ -105,6 +382,8 @@ pub fn load_recipes() -> HashMap<String, Recipe> {
         return all_recipes;
     }
 
+    let all_ingredients = load_ingredients();
+
     for line in content.unwrap().lines() {
         if line.starts_with("#") {
             continue;
@@ -112,7 +391,18 @@ pub fn load_recipes() -> HashMap<String, Recipe> {
 
         let name = line.split(';').next().unwrap();
 
-        all_recipes.insert(String::from(name), Recipe::new_by_line(line));
+        all_recipes.insert(
+            String::from(name),
+            Recipe::new_by_line(line, &all_ingredients),
+        );
+    }
+
+    if let Some(content) = load_file(paths::RECIPE_DETAILS) {
+        for (_i, name, details, _problems) in RecipeDetails::all_by_text(&content) {
+            if let Some(recipe) = all_recipes.get_mut(&name) {
+                recipe.details = details;
+            }
+        }
     }
     return all_recipes;
 }
@@ -143,19 +433,7 @@ pub fn load_ingredients() -> HashMap<String, Ingredient> {
 ///
 /// * `all_ingredients` The ingredients to write
 pub fn write_all_ingredients(all_ingredients: &HashMap<String, Ingredient>) -> Result<(), String> {
-    let file = OpenOptions::new()
-        .write(true)
-        .truncate(true)
-        .open(paths::INGREDIENTS);
-
-    if file.is_ok() {
-        let mut file = file.unwrap();
-        for (_k, ingredient) in all_ingredients {
-            write_ingredient(&ingredient, &mut file).unwrap_or_else(|e| eprintln!("{}", e));
-        }
-        return Ok(());
-    }
-    return Err(format!("Couldn't write to file: {}", file.unwrap_err()));
+    return write_files_atomically(&[(paths::INGREDIENTS, ingredients_to_string(all_ingredients))]);
 }
 
 /// Write a single ingredient.
@@ -164,7 +442,9 @@ pub fn write_all_ingredients(all_ingredients: &HashMap<String, Ingredient>) -> R
 ///
 /// * `new_ingredient` The ingredient to append.
 pub fn write_single_ingredient(new_ingredient: &Ingredient) -> Result<(), String> {
-    let file = OpenOptions::new().append(true).open(paths::INGREDIENTS);
+    let file = OpenOptions::new()
+        .append(true)
+        .open(resolve(paths::INGREDIENTS));
 
     if file.is_ok() {
         return write_ingredient(&new_ingredient, &mut file.unwrap());
@@ -173,12 +453,239 @@ pub fn write_single_ingredient(new_ingredient: &Ingredient) -> Result<(), String
 }
 
 fn write_ingredient(ingredient: &Ingredient, file: &mut File) -> Result<(), String> {
-    if let Err(e) = writeln!(
-        file,
+    if let Err(e) = writeln!(file, "{}", ingredient_to_line(ingredient)) {
+        return Err(format!("Couldn't write to file: {}", e));
+    }
+    return Ok(());
+}
+
+fn ingredient_to_line(ingredient: &Ingredient) -> String {
+    let mut line = format!(
         "{};{};{}",
         ingredient.name, ingredient.group as i8, ingredient.preferred_store as i8
-    ) {
-        return Err(format!("Couldn't write to file: {}", e));
+    );
+    if !ingredient.aliases.is_empty() || ingredient.price.is_some() {
+        line.push(';');
+        line.push_str(&ingredient.aliases.join(","));
+    }
+    if let Some(price) = ingredient.price {
+        line.push_str(&format!(";{}", price));
+    }
+    return line;
+}
+
+/// Writes all recipes with their details.
+///
+/// #Arguments
+///
+/// * `all_recipes` The recipes to write
+pub fn write_all_recipes(all_recipes: &HashMap<String, Recipe>) -> Result<(), String> {
+    return write_files_atomically(&[
+        (paths::RECIPES, recipes_to_string(all_recipes)),
+        (paths::RECIPE_DETAILS, recipe_details_to_string(all_recipes)),
+    ]);
+}
+
+/// Writes the ingredients, recipes and shopping list together.
+/// Either all files are replaced or none of them.
+///
+/// #Arguments
+///
+/// * `all_ingredients` The ingredients to write
+/// * `all_recipes` The recipes to write
+/// * `shopping_list` The shopping list to write
+pub fn write_all(
+    all_ingredients: &HashMap<String, Ingredient>,
+    all_recipes: &HashMap<String, Recipe>,
+    shopping_list: &ShoppingList,
+) -> Result<(), String> {
+    return write_files_atomically(&[
+        (paths::INGREDIENTS, ingredients_to_string(all_ingredients)),
+        (paths::RECIPES, recipes_to_string(all_recipes)),
+        (paths::RECIPE_DETAILS, recipe_details_to_string(all_recipes)),
+        (paths::SHOPPING_LIST, shopping_list_to_string(shopping_list)),
+    ]);
+}
+
+fn ingredients_to_string(all_ingredients: &HashMap<String, Ingredient>) -> String {
+    let mut content = String::new();
+    for (_k, ingredient) in all_ingredients {
+        content.push_str(&ingredient_to_line(ingredient));
+        content.push('\n');
+    }
+    return content;
+}
+
+fn recipes_to_string(all_recipes: &HashMap<String, Recipe>) -> String {
+    let mut names: Vec<&String> = all_recipes.keys().collect();
+    names.sort();
+
+    let mut content = String::new();
+    content.push_str(RECIPES_HEADER);
+    content.push('\n');
+    for name in names {
+        content.push_str(&all_recipes.get(name).unwrap().to_line());
+        content.push('\n');
+    }
+    return content;
+}
+
+fn recipe_details_to_string(all_recipes: &HashMap<String, Recipe>) -> String {
+    let mut all_details: Vec<(&String, &RecipeDetails)> = all_recipes
+        .iter()
+        .map(|(name, recipe)| (name, &recipe.details))
+        .collect();
+    all_details.sort_by(|a, b| a.0.cmp(b.0));
+    return RecipeDetails::all_to_text(&all_details);
+}
+
+fn shopping_list_to_string(shopping_list: &ShoppingList) -> String {
+    let mut content = String::new();
+    for (ingredient, amount) in &shopping_list.to_buy {
+        content.push_str(&format!("{};{}\n", ingredient.name, amount));
+    }
+    return content;
+}
+
+/// Returns the content of all persistency files without creating them.
+/// A missing file counts as empty.
+pub fn read_all_files() -> Vec<(&'static str, String)> {
+    return paths::ALL
+        .iter()
+        .map(|file_name| {
+            (
+                *file_name,
+                fs::read_to_string(resolve(file_name)).unwrap_or_default(),
+            )
+        })
+        .collect();
+}
+
+/// Writes every content to a temporary file next to its target first.
+/// The targets are only replaced once all temporary files were written. If replacing one
+/// fails, the already replaced targets are restored from backups.
+/// The files of `paths::ALL` are those of the selected household.
+///
+/// #Arguments
+///
+/// * `files` The target file names with their new content.
+pub fn write_files_atomically(files: &[(&str, String)]) -> Result<(), String> {
+    let targets: Vec<String> = files.iter().map(|(f, _c)| resolve(f)).collect();
+    let mut temp_files: Vec<(String, &str)> = Vec::with_capacity(files.len());
+
+    for (file_name, (_f, content)) in targets.iter().zip(files) {
+        let temp_file = format!("{}.tmp", file_name);
+        if let Err(e) = fs::write(&temp_file, content) {
+            for (written, _target) in &temp_files {
+                let _ = fs::remove_file(written);
+            }
+            return Err(format!("Couldn't write to file: {}", e));
+        }
+        temp_files.push((temp_file, file_name));
+    }
+
+    // the old contents are kept until all targets are replaced, to restore them on failure
+    let mut replaced: Vec<(&str, Option<String>)> = Vec::with_capacity(temp_files.len());
+    for (index, (temp_file, file_name)) in temp_files.iter().enumerate() {
+        let backup = if Path::new(file_name).is_file() {
+            Some(format!("{}.bak", file_name))
+        } else {
+            None
+        };
+        let result = match &backup {
+            Some(backup) => fs::copy(file_name, backup).map(|_| ()),
+            None => Ok(()),
+        };
+        if let Err(e) = result.and_then(|_| fs::rename(temp_file, file_name)) {
+            if let Some(backup) = &backup {
+                let _ = fs::remove_file(backup);
+            }
+            for (remaining, _target) in &temp_files[index..] {
+                let _ = fs::remove_file(remaining);
+            }
+            restore_files(&replaced);
+            return Err(format!("Couldn't replace file {}: {}", file_name, e));
+        }
+        replaced.push((file_name, backup));
+    }
+
+    for (_file_name, backup) in &replaced {
+        if let Some(backup) = backup {
+            let _ = fs::remove_file(backup);
+        }
     }
     return Ok(());
 }
+
+/// Restores the replaced files from their backups. Files without backup didn't exist
+/// before and are removed.
+///
+/// #Arguments
+///
+/// * `replaced` The replaced file names with their backups.
+fn restore_files(replaced: &[(&str, Option<String>)]) {
+    for (file_name, backup) in replaced {
+        let _ = match backup {
+            Some(backup) => fs::rename(backup, file_name),
+            None => fs::remove_file(file_name),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        decode_field, encode_field, paths, resolve, select_household, write_files_atomically,
+    };
+    use std::fs;
+
+    #[test]
+    fn test_encode_field() {
+        let notes = "mit \"Puderzucker\"; 50%3B\r\nlecker";
+        assert_eq!(
+            encode_field(notes),
+            "mit \"Puderzucker\"%3B 50%253B%0D%0Alecker"
+        );
+        assert_eq!(decode_field(&encode_field(notes)), notes);
+        assert_eq!(decode_field("zu salzig"), "zu salzig");
+    }
+
+    #[test]
+    fn test_resolve() {
+        assert_eq!(resolve(paths::PANTRY), "persistency/pantry.csv");
+        select_household(Some("00ff"));
+        assert_eq!(
+            resolve(paths::PANTRY),
+            "persistency/households/00ff/pantry.csv"
+        );
+        assert_eq!(resolve(paths::ACCOUNTS), "persistency/accounts.csv");
+        select_household(None);
+        assert_eq!(resolve(paths::HISTORY), "persistency/history.csv");
+    }
+
+    #[test]
+    fn test_write_files_atomically_restores_on_failure() {
+        let directory = std::env::temp_dir().join(format!("rezept-{}", std::process::id()));
+        let first = directory.join("first.csv");
+        let second = directory.join("second");
+        fs::create_dir_all(second.join("blocked")).unwrap();
+        fs::write(&first, "alt").unwrap();
+
+        let result = write_files_atomically(&[
+            (first.to_str().unwrap(), "neu".to_string()),
+            (second.to_str().unwrap(), "neu".to_string()),
+        ]);
+        assert!(result.is_err());
+        assert_eq!(fs::read_to_string(&first).unwrap(), "alt");
+        let mut left: Vec<String> = fs::read_dir(&directory)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .collect();
+        left.sort();
+        assert_eq!(left, vec!["first.csv", "second"]);
+
+        assert!(write_files_atomically(&[(first.to_str().unwrap(), "neu".to_string())]).is_ok());
+        assert_eq!(fs::read_to_string(&first).unwrap(), "neu");
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,553 @@
+use crate::cooking_book::ingredient::Ingredient;
+use crate::cooking_book::ingredient_parser;
+use crate::cooking_book::locale::Locale;
+use crate::cooking_book::recipe::{IngredientLine, Recipe};
+use crate::cooking_book::recipe_details::{RecipeDetails, Step};
+use crate::file_access::json::Json;
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+/// A recipe read from another format, before it is saved.
+pub struct ImportedRecipe {
+    pub recipe: Recipe,
+    /// The names of the ingredients which are missing in the catalog.
+    pub unresolved: Vec<String>,
+    /// The ingredient lines which couldn't be parsed.
+    pub skipped: Vec<String>,
+}
+
+impl ImportedRecipe {
+    /// Create an imported recipe and find its ingredients missing in the catalog.
+    ///
+    /// #Arguments
+    ///
+    /// * `recipe` The imported recipe.
+    /// * `skipped` The ingredient lines which couldn't be parsed.
+    /// * `all_ingredients` The collection of all ingredients.
+    pub fn new(
+        recipe: Recipe,
+        skipped: Vec<String>,
+        all_ingredients: &HashMap<String, Ingredient>,
+    ) -> ImportedRecipe {
+        let unresolved: Vec<String> = recipe
+            .get_ingredients()
+            .iter()
+            .filter(|i| !all_ingredients.contains_key(&i.name))
+            .map(|i| i.name.to_string())
+            .collect();
+        return ImportedRecipe {
+            recipe,
+            unresolved,
+            skipped,
+        };
+    }
+
+    /// Export the imported recipe with its unresolved ingredients and skipped lines to JSON.
+    ///
+    /// #Arguments
+    ///
+    /// * `locale` The language of the labels of the recipe.
+    pub fn to_json(&self, locale: Locale) -> String {
+        let quote = |texts: &Vec<String>| -> String {
+            let quoted: Vec<String> = texts
+                .iter()
+                .map(|t| format!("\"{}\"", Json::escape(t)))
+                .collect();
+            return quoted.join(", ");
+        };
+        return format!(
+            "{{\"recipe\": {}, \"unresolved\": [{}], \"skipped\": [{}]}}",
+            self.recipe.to_json(locale),
+            quote(&self.unresolved),
+            quote(&self.skipped)
+        );
+    }
+}
+
+/// Imports the first recipe embedded as schema.org JSON-LD in a saved web page.
+/// The ingredient lines are parsed and looked up in the catalog, also by alias
+/// and similar name. Lines which can't be parsed are skipped.
+///
+/// #Arguments
+///
+/// * `html` The HTML of the page.
+/// * `all_ingredients` The collection of all ingredients.
+pub fn from_html(
+    html: &str,
+    all_ingredients: &HashMap<String, Ingredient>,
+) -> Result<ImportedRecipe, String> {
+    let mut found_json_ld = false;
+    for script in find_json_ld(html) {
+        found_json_ld = true;
+        let json = match Json::parse(script) {
+            Ok(json) => json,
+            Err(_) => continue,
+        };
+        if let Some(recipe) = find_recipe(&json) {
+            return from_json_ld(recipe, all_ingredients);
+        }
+    }
+    if found_json_ld {
+        return Err("The JSON-LD of the page contains no recipe".to_string());
+    }
+    return Err("The page contains no JSON-LD".to_string());
+}
+
+fn from_json_ld(
+    json: &Json,
+    all_ingredients: &HashMap<String, Ingredient>,
+) -> Result<ImportedRecipe, String> {
+    let name = clean(json.get("name").and_then(|n| n.as_str()).unwrap_or(""));
+    if name.is_empty() {
+        return Err("The recipe has no name".to_string());
+    }
+
+    let mut ingredients: Vec<IngredientLine> = Vec::new();
+    let mut skipped: Vec<String> = Vec::new();
+    let lines = json
+        .get("recipeIngredient")
+        .or_else(|| json.get("ingredients"))
+        .map_or(Vec::new(), |i| i.as_list());
+    for line in lines.iter().filter_map(|l| l.as_str()) {
+        let line = clean(line);
+        if !add_ingredients(&line, "", all_ingredients, &mut ingredients) {
+            skipped.push(line);
+        }
+    }
+
+    let mut details = RecipeDetails::new();
+    if let Some(instructions) = json.get("recipeInstructions") {
+        add_steps(instructions, &mut details.steps);
+    }
+    details.servings = json.get("recipeYield").and_then(parse_yield);
+    let get_time = |key: &str| {
+        json.get(key)
+            .and_then(|t| t.as_str())
+            .and_then(parse_duration)
+    };
+    details.prep_time = get_time("prepTime");
+    details.cook_time = get_time("cookTime");
+    if details.cook_time.is_none() {
+        if let Some(total_time) = get_time("totalTime") {
+            details.cook_time = Some(total_time.saturating_sub(details.prep_time.unwrap_or(0)));
+        }
+    }
+    details.source = clean(json.get("url").and_then(|u| u.as_str()).unwrap_or(""));
+    details.notes = clean(
+        json.get("description")
+            .and_then(|d| d.as_str())
+            .unwrap_or(""),
+    );
+
+    let recipe = Recipe {
+        name,
+        ingredients,
+        sub_recipes: HashMap::new(),
+        tags: HashSet::new(),
+        details,
+    };
+    return Ok(ImportedRecipe::new(recipe, skipped, all_ingredients));
+}
+
+/// Parses the ingredients of a recipe written one per line like `200 g Mehl, gesiebt`.
+/// A line like `[Für den Teig]` or `Für den Teig:` starts a section, `[]` ends it.
+/// Returns the ingredient lines and the lines which couldn't be parsed.
+///
+/// #Arguments
+///
+/// * `text` The text to parse.
+/// * `all_ingredients` The collection of all ingredients.
+pub fn ingredients_from_text(
+    text: &str,
+    all_ingredients: &HashMap<String, Ingredient>,
+) -> (Vec<IngredientLine>, Vec<String>) {
+    let mut ingredients: Vec<IngredientLine> = Vec::new();
+    let mut skipped: Vec<String> = Vec::new();
+    let mut section = String::new();
+    for line in text.lines() {
+        let line = clean(line);
+        if line.is_empty() {
+            continue;
+        }
+        if line.starts_with('[') && line.ends_with(']') {
+            section = clean(&line[1..line.len() - 1].replace(',', " "));
+            continue;
+        }
+        if line.ends_with(':') {
+            section = clean(&line[..line.len() - 1].replace(',', " "));
+            continue;
+        }
+        if !add_ingredients(&line, &section, all_ingredients, &mut ingredients) {
+            skipped.push(line);
+        }
+    }
+    return (ingredients, skipped);
+}
+
+/// Parses a line naming one or more ingredients and adds them to the section.
+/// The ingredients are looked up in the catalog by name, alias or similar name.
+/// Returns false if the line can't be parsed or names a new ingredient with an invalid name,
+/// see `Ingredient::is_valid_name`.
+fn add_ingredients(
+    line: &str,
+    section: &str,
+    all_ingredients: &HashMap<String, Ingredient>,
+    ingredients: &mut Vec<IngredientLine>,
+) -> bool {
+    let all_parsed = ingredient_parser::parse_all(line);
+    let mut lines: Vec<IngredientLine> = Vec::with_capacity(all_parsed.len());
+    for parsed in &all_parsed {
+        let ingredient = match Ingredient::find_similar(all_ingredients, &parsed.name) {
+            Some(ingredient) => ingredient.clone(),
+            None => {
+                let name = parsed
+                    .name
+                    .trim_start_matches(|c| c == '#' || c == '@' || c == '[')
+                    .trim();
+                if !Ingredient::is_valid_name(name) {
+                    return false;
+                }
+                Ingredient::new_by_name(name.to_string())
+            }
+        };
+        let mut ingredient = IngredientLine::new(ingredient, parsed.amount, &parsed.unit, section);
+        // commas separate the values in the recipes file
+        ingredient.note = parsed.note.replace(',', " /");
+        lines.push(ingredient);
+    }
+    ingredients.append(&mut lines);
+    return !all_parsed.is_empty();
+}
+
+/// Returns the contents of all `<script type="application/ld+json">` elements.
+fn find_json_ld(html: &str) -> Vec<&str> {
+    // ASCII lowercase keeps the positions of the original
+    let lower = html.to_ascii_lowercase();
+    let mut scripts: Vec<&str> = Vec::new();
+    let mut position = 0;
+    while let Some(start) = lower[position..].find("<script") {
+        let start = position + start;
+        let tag_end = match lower[start..].find('>') {
+            Some(tag_end) => start + tag_end + 1,
+            None => break,
+        };
+        let end = match lower[tag_end..].find("</script") {
+            Some(end) => tag_end + end,
+            None => break,
+        };
+        if lower[start..tag_end].contains("application/ld+json") {
+            scripts.push(&html[tag_end..end]);
+        }
+        position = end;
+    }
+    return scripts;
+}
+
+/// Finds the recipe in a JSON-LD document, which may be a list or a graph of objects.
+fn find_recipe(json: &Json) -> Option<&Json> {
+    return match json {
+        Json::Array(elements) => elements.iter().filter_map(find_recipe).next(),
+        Json::Object(_members) => {
+            let is_recipe = json.get("@type").map_or(false, |t| {
+                t.as_list()
+                    .iter()
+                    .filter_map(|t| t.as_str())
+                    .any(|t| t == "Recipe" || t.ends_with("/Recipe") || t.ends_with(":Recipe"))
+            });
+            if is_recipe {
+                Some(json)
+            } else {
+                json.get("@graph").and_then(find_recipe)
+            }
+        }
+        _ => None,
+    };
+}
+
+/// Adds the steps of the instructions, which may be a text, a list of texts,
+/// `HowToStep`s or `HowToSection`s containing them.
+fn add_steps(instructions: &Json, steps: &mut Vec<Step>) {
+    for instruction in instructions.as_list() {
+        match instruction {
+            Json::String(text) => {
+                for line in strip_tags(text).lines() {
+                    add_step(line, steps);
+                }
+            }
+            Json::Object(_members) => {
+                if let Some(items) = instruction.get("itemListElement") {
+                    add_steps(items, steps);
+                } else if let Some(text) = instruction
+                    .get("text")
+                    .or_else(|| instruction.get("name"))
+                    .and_then(|t| t.as_str())
+                {
+                    add_step(&strip_tags(text), steps);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn add_step(text: &str, steps: &mut Vec<Step>) {
+    // braces would be read as references to ingredients or timers
+    let text = clean(text).replace('{', "(").replace('}', ")");
+    if !text.is_empty() {
+        steps.push(Step { text });
+    }
+}
+
+/// Returns the servings of a yield like `4`, `"4 Portionen"` or `["4", "4 Portionen"]`.
+fn parse_yield(json: &Json) -> Option<u16> {
+    for value in json.as_list() {
+        let servings = match value {
+            Json::Number(number) => Some(*number as u16),
+            Json::String(text) => text
+                .split(|c: char| !c.is_ascii_digit())
+                .find(|n| !n.is_empty())
+                .and_then(|n| n.parse::<u16>().ok()),
+            _ => None,
+        };
+        if let Some(servings) = servings {
+            if servings > 0 {
+                return Some(servings);
+            }
+        }
+    }
+    return None;
+}
+
+/// Parses an ISO 8601 duration like `PT1H30M` into minutes, seconds are rounded up.
+pub fn parse_duration(text: &str) -> Option<u16> {
+    let text = text.trim();
+    if !text.starts_with('P') {
+        return None;
+    }
+    let mut minutes: u32 = 0;
+    let mut number = String::new();
+    let mut is_time = false;
+    for c in text[1..].chars() {
+        if c.is_ascii_digit() || c == '.' {
+            number.push(c);
+            continue;
+        }
+        if c == 'T' {
+            is_time = true;
+            continue;
+        }
+        let value = number.parse::<f64>().ok()?;
+        number.clear();
+        minutes += match (is_time, c) {
+            (false, 'D') => value * 24.0 * 60.0,
+            (true, 'H') => value * 60.0,
+            (true, 'M') => value,
+            (true, 'S') => (value / 60.0).ceil(),
+            _ => return None,
+        } as u32;
+    }
+    if !number.is_empty() || minutes > u16::max_value() as u32 {
+        return None;
+    }
+    return Some(minutes as u16);
+}
+
+/// Removes HTML tags, keeping line breaks of `<br>` and paragraphs.
+fn strip_tags(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut tag: Option<String> = None;
+    for c in text.chars() {
+        match (&mut tag, c) {
+            (None, '<') => tag = Some(String::new()),
+            (None, c) => stripped.push(c),
+            (Some(name), '>') => {
+                let name = name.trim_start_matches('/').to_ascii_lowercase();
+                if name.starts_with("br") || name.starts_with("p") || name.starts_with("li") {
+                    stripped.push('\n');
+                }
+                tag = None;
+            }
+            (Some(name), c) => name.push(c),
+        }
+    }
+    return stripped;
+}
+
+/// Decodes HTML entities and removes characters which can't be persisted,
+/// like the separators of the recipes file and line breaks.
+pub fn clean(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = match rest.find(';') {
+            Some(end) if end <= 10 => end,
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+                continue;
+            }
+        };
+        let entity = &rest[1..end];
+        let c = match entity {
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "nbsp" => Some(' '),
+            _ if entity.starts_with("#x") || entity.starts_with("#X") => {
+                u32::from_str_radix(&entity[2..], 16)
+                    .ok()
+                    .and_then(std::char::from_u32)
+            }
+            _ if entity.starts_with('#') => entity[1..]
+                .parse::<u32>()
+                .ok()
+                .and_then(std::char::from_u32),
+            _ => None,
+        };
+        match c {
+            Some(c) => {
+                decoded.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+
+    let cleaned = decoded.replace(';', ",").replace('"', "'");
+    return cleaned.split_whitespace().collect::<Vec<&str>>().join(" ");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{clean, from_html, ingredients_from_text, parse_duration};
+    use crate::cooking_book::ingredient::Ingredient;
+    use crate::cooking_book::locale::Locale;
+    use std::collections::HashMap;
+
+    static PAGE: &str = r#"<html><head>
+<script type="application/ld+json">{"@context": "https://schema.org", "@type": "WebSite"}</script>
+<SCRIPT type="application/ld+json">
+{"@context": "https://schema.org", "@graph": [{"@type": "Organization", "name": "Koch"},
+ {"@type": ["Recipe"], "name": "Pfannkuchen &amp; Sirup", "recipeYield": ["4", "4 Portionen"],
+  "prepTime": "PT10M", "totalTime": "PT1H",
+  "url": "https://example.com/pfannkuchen",
+  "recipeIngredient": ["250 g Mehl", "1/2 l Milch", "3 Eier", "1 Prise Salz", "1 EL"],
+  "recipeInstructions": [
+   {"@type": "HowToSection", "name": "Teig", "itemListElement": [
+     {"@type": "HowToStep", "text": "Mehl und Milch {gut} verrühren."},
+     {"@type": "HowToStep", "text": "Eier unterheben; 10 Minuten ruhen lassen."}]},
+   "In der Pfanne ausbacken."]}]}
+</SCRIPT></head><body></body></html>"#;
+
+    #[test]
+    fn test_from_html() {
+        let mut all_ingredients: HashMap<String, Ingredient> = HashMap::new();
+        all_ingredients.insert("Mehl".to_string(), Ingredient::new_by_line("Mehl;5;-1"));
+        all_ingredients.insert("Ei".to_string(), Ingredient::new_by_line("Ei;0;-1;Eier"));
+
+        let imported = from_html(PAGE, &all_ingredients).unwrap();
+        let recipe = &imported.recipe;
+        assert_eq!(
+            recipe.to_line(),
+            "Pfannkuchen & Sirup;Mehl,250,G;Milch,500,ML;Ei,3,;Salz,1,Prise"
+        );
+        assert_eq!(imported.unresolved, vec!["Milch", "Salz"]);
+        assert_eq!(imported.skipped, vec!["1 EL"]);
+
+        let steps: Vec<&str> = recipe
+            .details
+            .steps
+            .iter()
+            .map(|s| s.text.as_str())
+            .collect();
+        assert_eq!(
+            steps,
+            vec![
+                "Mehl und Milch (gut) verrühren.",
+                "Eier unterheben, 10 Minuten ruhen lassen.",
+                "In der Pfanne ausbacken."
+            ]
+        );
+        assert_eq!(recipe.details.servings, Some(4));
+        assert_eq!(recipe.details.prep_time, Some(10));
+        assert_eq!(recipe.details.total_time(), Some(60));
+        assert_eq!(recipe.details.source, "https://example.com/pfannkuchen");
+        assert!(imported
+            .to_json(Locale::De)
+            .ends_with("\"unresolved\": [\"Milch\", \"Salz\"], \"skipped\": [\"1 EL\"]}"));
+    }
+
+    #[test]
+    fn test_ingredients_from_text() {
+        let mut all_ingredients: HashMap<String, Ingredient> = HashMap::new();
+        all_ingredients.insert("Ei".to_string(), Ingredient::new_by_line("Ei;0;-1;Eier"));
+
+        let (ingredients, skipped) = ingredients_from_text(
+            "Für den Teig:\n250 g Mehl\n3 Eier (Größe M)\n\n[Zum Backen]\nSalz und Pfeffer\n1 EL\nZucker und \u{7}Zimt",
+            &all_ingredients,
+        );
+        let lines: Vec<String> = ingredients
+            .iter()
+            .map(|l| {
+                format!(
+                    "{}|{},{},{},{}",
+                    l.section, l.ingredient.name, l.amount, l.unit, l.note
+                )
+            })
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                "Für den Teig|Mehl,250,G,",
+                "Für den Teig|Ei,3,,Größe M",
+                "Zum Backen|Salz,0,,",
+                "Zum Backen|Pfeffer,0,,"
+            ]
+        );
+        assert_eq!(skipped, vec!["1 EL", "Zucker und \u{7}Zimt"]);
+    }
+
+    #[test]
+    fn test_from_html_without_recipe() {
+        let all_ingredients: HashMap<String, Ingredient> = HashMap::new();
+        assert_eq!(
+            from_html("<html></html>", &all_ingredients).err(),
+            Some("The page contains no JSON-LD".to_string())
+        );
+        assert_eq!(
+            from_html(
+                "<script type=\"application/ld+json\">{\"@type\": \"Person\"}</script>",
+                &all_ingredients
+            )
+            .err(),
+            Some("The JSON-LD of the page contains no recipe".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("PT1H30M"), Some(90));
+        assert_eq!(parse_duration("P0DT0H45M"), Some(45));
+        assert_eq!(parse_duration("PT90S"), Some(2));
+        assert_eq!(parse_duration("P1D"), Some(1440));
+        assert_eq!(parse_duration("30 min"), None);
+        assert_eq!(parse_duration("PT30"), None);
+    }
+
+    #[test]
+    fn test_clean() {
+        assert_eq!(
+            clean("Salz &amp;\n Pfeffer&#33; &unknown; a&b"),
+            "Salz & Pfeffer! &unknown, a&b"
+        );
+        assert_eq!(clean("\"Omas\" Kuchen; lecker"), "'Omas' Kuchen, lecker");
+    }
+}
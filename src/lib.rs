@@ -0,0 +1,87 @@
+//! The cooking book: ingredients, recipes, the shopping list, the pantry, the meal plan and
+//! the history of cooked recipes, with their persistence in plain text files.
+//!
+//! The web server (`rezept`, needs the `server` feature) and the command line interface
+//! (`cooking-book`) are thin binaries on top of this library.
+//!
+//! ```no_run
+//! use rezept::cooking_book::locale::Locale;
+//! use rezept::cooking_book::render::{self, Format};
+//! use rezept::file_access::persistency;
+//!
+//! let shopping_list = persistency::load_shopping_list();
+//! println!("{}", render::shopping_list(&shopping_list, Format::Text, Locale::En));
+//! ```
+
+/// The cooking book itself, independent of how it is stored or presented.
+pub mod cooking_book {
+    /// Exports the meal plan and the shopping list as iCalendar.
+    pub mod calendar;
+    /// Calendar dates without time zone.
+    pub mod date;
+    /// The groups of ingredients, like vegetables or spices.
+    pub mod group;
+    /// The recipes cooked so far.
+    pub mod history;
+    /// The ingredients with their group, store, aliases and price.
+    pub mod ingredient;
+    /// Parses free text like `2 Liter Milch` into ingredients with amount and unit.
+    pub mod ingredient_parser;
+    /// The languages of the labels shown to the user.
+    pub mod locale;
+    /// The recipes planned for the meals of the days.
+    pub mod meal_plan;
+    /// The ingredients at home.
+    pub mod pantry;
+    /// Suggests recipes for the meal plan.
+    pub mod planner;
+    /// Queries for recipes like `#vegan AND NOT Milch`.
+    pub mod query;
+    /// The recipes with their ingredients, sub recipes and tags.
+    pub mod recipe;
+    /// The steps, times and other details of a recipe.
+    pub mod recipe_details;
+    /// Finds the recipes which can be cooked with the ingredients at hand.
+    pub mod recipe_match;
+    /// Renders recipes and the shopping list as text, Markdown or HTML.
+    pub mod render;
+    /// Fuzzy text search, tolerating typos and umlauts.
+    pub mod search;
+    /// The shopping list.
+    pub mod shopping_list;
+    /// Statistics about the cooked recipes and used ingredients.
+    pub mod stats;
+    /// The stores to buy ingredients in.
+    pub mod store;
+    /// The units of amounts and their conversion.
+    pub mod unit;
+    /// Ingredients used by recipes but missing in the catalog.
+    pub mod unresolved;
+}
+
+/// Reading, writing, importing and exporting the cooking book.
+pub mod file_access {
+    /// Exports and imports the whole cooking book as one JSON document.
+    pub mod archive;
+    /// Strictly checks the persisted files for problems.
+    pub mod check;
+    /// Imports and exports recipes in the Cooklang format.
+    pub mod cooklang;
+    /// A small JSON parser.
+    pub mod json;
+    /// Loads and writes the files of the cooking book.
+    pub mod persistency;
+    /// Imports recipes from schema.org JSON-LD in web pages.
+    pub mod recipe_import;
+}
+
+/// User accounts, households and their authentication.
+pub mod account {
+    /// The households, users, sessions, API tokens and share links.
+    pub mod accounts;
+    /// Hashes passwords and tokens with SHA-256 and PBKDF2.
+    pub mod password;
+}
+
+/// The commands of the command line interface.
+pub mod cli;
@@ -4,126 +4,1712 @@
 extern crate rocket;
 extern crate rocket_contrib;
 
+use rocket::http::{Accept, ContentType, Cookie, Cookies, SameSite, Status};
+use rocket::request::{self, FromRequest, Request};
+use rocket::response::content;
+use rocket::response::status;
+use rocket::Outcome;
 use rocket_contrib::serve::StaticFiles;
+use std::collections::HashMap;
+use std::collections::HashSet;
 
-mod cooking_book {
-    pub mod group;
-    pub mod ingredient;
-    pub mod recipe;
-    pub mod shopping_list;
-    pub mod store;
+use rezept::account::accounts::User;
+use rezept::cli;
+use rezept::cooking_book::calendar;
+use rezept::cooking_book::date::Date;
+use rezept::cooking_book::group::Group;
+use rezept::cooking_book::history::History;
+use rezept::cooking_book::ingredient::Ingredient;
+use rezept::cooking_book::locale::Locale;
+use rezept::cooking_book::meal_plan::Slot;
+use rezept::cooking_book::pantry::Pantry;
+use rezept::cooking_book::planner;
+use rezept::cooking_book::planner::Constraints;
+use rezept::cooking_book::query::Query;
+use rezept::cooking_book::recipe::Recipe;
+use rezept::cooking_book::recipe_details::RecipeDetails;
+use rezept::cooking_book::recipe_match::{Ranking, RecipeMatch};
+use rezept::cooking_book::render;
+use rezept::cooking_book::render::Format;
+use rezept::cooking_book::shopping_list::ShoppingList;
+use rezept::cooking_book::stats;
+use rezept::cooking_book::stats::{Order, RecipeStats};
+use rezept::cooking_book::store::Store;
+use rezept::cooking_book::unresolved::UnresolvedIngredient;
+use rezept::file_access::archive;
+use rezept::file_access::check;
+use rezept::file_access::check::Diagnostic;
+use rezept::file_access::cooklang;
+use rezept::file_access::json::Json;
+use rezept::file_access::persistency;
+use rezept::file_access::recipe_import;
+use rezept::file_access::recipe_import::ImportedRecipe;
+
+/// The language of a request: the one of the cookie `lang`, else the preferred one of the
+/// `Accept-Language` header, else German.
+struct RequestLocale(Locale);
+
+impl<'a, 'r> FromRequest<'a, 'r> for RequestLocale {
+    type Error = ();
+
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<RequestLocale, ()> {
+        let from_cookie = request
+            .cookies()
+            .get("lang")
+            .and_then(|cookie| Locale::lookup_locale(cookie.value()));
+        let from_header = request
+            .headers()
+            .get_one("Accept-Language")
+            .and_then(Locale::from_accept_language);
+        let locale = from_cookie.or(from_header).unwrap_or(Locale::De);
+        return Outcome::Success(RequestLocale(locale));
+    }
+}
+
+/// The user of a request, authenticated by the cookie `session` of a login or by an API token
+/// in the header `Authorization: Bearer <token>`. Other requests are answered with 401.
+/// Selects the household of the user for the persistency, so every route reading or writing
+/// the cooking book needs this guard.
+struct Member(User);
+
+impl<'a, 'r> FromRequest<'a, 'r> for Member {
+    type Error = ();
+
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<Member, ()> {
+        let from_cookie = request
+            .cookies()
+            .get("session")
+            .map(|cookie| cookie.value().to_string());
+        let from_header = request
+            .headers()
+            .get_one("Authorization")
+            .filter(|value| value.starts_with("Bearer "))
+            .map(|value| value["Bearer ".len()..].trim().to_string());
+        let token = match from_cookie.or(from_header) {
+            Some(token) => token,
+            None => return Outcome::Failure((Status::Unauthorized, ())),
+        };
+        let accounts = persistency::load_accounts();
+        return match accounts.authenticate(&token, Date::today()) {
+            Some(user) => {
+                persistency::select_household(Some(&user.household));
+                Outcome::Success(Member(user.clone()))
+            }
+            None => Outcome::Failure((Status::Unauthorized, ())),
+        };
+    }
+}
+
+/// Logs a user in with a session cookie valid for 30 days.
+/// Returns the user with the household.
+///
+/// #Arguments
+///
+/// * `name` The name of the user
+/// * `password` The password of the user
+#[post("/account/login?<name>", format = "text/plain", data = "<password>")]
+fn post_login(
+    name: String,
+    password: String,
+    mut cookies: Cookies,
+) -> Result<String, status::Custom<String>> {
+    let mut accounts = persistency::load_accounts();
+    let token = match accounts.login(&name, &password, Date::today()) {
+        Ok(token) => token,
+        Err(e) => return Err(status::Custom(Status::Unauthorized, e)),
+    };
+    if let Err(e) = persistency::write_accounts(&accounts) {
+        return Err(status::Custom(Status::InternalServerError, e));
+    }
+    cookies.add(
+        Cookie::build("session", token)
+            .path("/")
+            .http_only(true)
+            .same_site(SameSite::Strict)
+            .finish(),
+    );
+    return Ok(accounts.user_to_json(accounts.lookup_user(&name).unwrap()));
+}
+
+/// Ends the session of the cookie.
+#[post("/account/logout")]
+fn post_logout(mut cookies: Cookies) -> Result<(), status::Custom<String>> {
+    if let Some(cookie) = cookies.get("session") {
+        let mut accounts = persistency::load_accounts();
+        accounts.logout(cookie.value());
+        if let Err(e) = persistency::write_accounts(&accounts) {
+            return Err(status::Custom(Status::InternalServerError, e));
+        }
+    }
+    cookies.remove(Cookie::named("session"));
+    return Ok(());
+}
+
+/// Returns the logged in user with the household and its members.
+#[get("/account", format = "application/json")]
+fn get_account(member: Member) -> String {
+    return persistency::load_accounts().user_to_json(&member.0);
+}
+
+/// Creates an API token for scripts, sent as `Authorization: Bearer <token>`.
+/// It is only shown once and valid until it is revoked.
+/// Returns the token.
+#[post("/account/token")]
+fn post_api_token(member: Member) -> Result<String, status::Custom<String>> {
+    let mut accounts = persistency::load_accounts();
+    let token = match accounts.create_api_token(&member.0.name) {
+        Ok(token) => token,
+        Err(e) => return Err(status::Custom(Status::InternalServerError, e)),
+    };
+    if let Err(e) = persistency::write_accounts(&accounts) {
+        return Err(status::Custom(Status::InternalServerError, e));
+    }
+    return Ok(token);
+}
+
+/// Revokes an API token.
+///
+/// #Arguments
+///
+/// * `token` The token to revoke
+#[delete("/account/token", format = "text/plain", data = "<token>")]
+fn delete_api_token(member: Member, token: String) -> Result<(), status::Custom<String>> {
+    let mut accounts = persistency::load_accounts();
+    let is_own = accounts
+        .authenticate(token.trim(), Date::today())
+        .map_or(false, |user| user.name == member.0.name);
+    if is_own {
+        accounts.logout(token.trim());
+        if let Err(e) = persistency::write_accounts(&accounts) {
+            return Err(status::Custom(Status::InternalServerError, e));
+        }
+    }
+    return Ok(());
+}
+
+/// Adds a user to the household of the logged in user.
+/// Returns the user with the household.
+///
+/// #Arguments
+///
+/// * `name` The name of the new user
+/// * `password` The password of the new user, at least 8 characters
+#[post("/household/member?<name>", format = "text/plain", data = "<password>")]
+fn post_household_member(
+    member: Member,
+    name: String,
+    password: String,
+) -> Result<String, status::Custom<String>> {
+    let mut accounts = persistency::load_accounts();
+    if let Err(e) = accounts.add_member(&member.0.household, &name, &password) {
+        return Err(status::Custom(Status::BadRequest, e));
+    }
+    if let Err(e) = persistency::write_accounts(&accounts) {
+        return Err(status::Custom(Status::InternalServerError, e));
+    }
+    return Ok(accounts.user_to_json(&member.0));
+}
+
+/// Creates a read-only share link of the shopping list and the meal plan of the household,
+/// replacing the previous one.
+/// Returns the token of the link, used as `/share/<token>/shopping_list`.
+#[post("/household/share")]
+fn post_share(member: Member) -> Result<String, status::Custom<String>> {
+    let mut accounts = persistency::load_accounts();
+    let token = match accounts.share(&member.0.household) {
+        Ok(token) => token,
+        Err(e) => return Err(status::Custom(Status::InternalServerError, e)),
+    };
+    if let Err(e) = persistency::write_accounts(&accounts) {
+        return Err(status::Custom(Status::InternalServerError, e));
+    }
+    return Ok(token);
+}
+
+/// Revokes the share link of the household.
+#[delete("/household/share")]
+fn delete_share(member: Member) -> Result<(), status::Custom<String>> {
+    let mut accounts = persistency::load_accounts();
+    accounts.unshare(&member.0.household);
+    if let Err(e) = persistency::write_accounts(&accounts) {
+        return Err(status::Custom(Status::InternalServerError, e));
+    }
+    return Ok(());
+}
+
+/// Returns the shopping list of a shared household, like `/shopping_list`.
+///
+/// #Arguments
+///
+/// * `token` The token of the share link
+/// * `format` The format like `text`, `md` or `html`, overrides the `Accept` header
+#[get("/share/<token>/shopping_list?<format>")]
+fn get_shared_shopping_list(
+    token: String,
+    format: Option<String>,
+    accept: Option<&Accept>,
+    locale: RequestLocale,
+) -> Result<content::Content<String>, status::Custom<String>> {
+    select_shared_household(&token)?;
+    return render_shopping_list(format, accept, locale.0);
+}
+
+/// Returns the meal plan and the shopping list of a shared household as iCalendar,
+/// like `/meal_plan.ics`.
+///
+/// #Arguments
+///
+/// * `token` The token of the share link
+#[get("/share/<token>/meal_plan.ics")]
+fn get_shared_meal_plan_ical(
+    token: String,
+    locale: RequestLocale,
+) -> Result<content::Content<String>, status::Custom<String>> {
+    select_shared_household(&token)?;
+    return Ok(meal_plan_ical(locale.0));
 }
 
-mod file_access {
-    pub mod persistency;
+/// Selects the household shared by the token of a share link, else answers 404.
+fn select_shared_household(token: &str) -> Result<(), status::Custom<String>> {
+    return match persistency::load_accounts().lookup_share(token) {
+        Some(household) => {
+            persistency::select_household(Some(&household.id));
+            Ok(())
+        }
+        None => Err(status::Custom(
+            Status::NotFound,
+            "Unknown share link".to_string(),
+        )),
+    };
 }
 
-use crate::cooking_book::ingredient::Ingredient;
-use crate::file_access::persistency;
+/// Returns all labels of the language of the request, for the web interface.
+#[get("/locale", format = "application/json")]
+fn get_locale(locale: RequestLocale) -> String {
+    return locale.0.catalog_to_json();
+}
 
-/// Returns the stores.
+/// Returns the stores with their ids and labels.
 #[get("/store", format = "application/json")]
-fn get_store() -> String {
-    return crate::cooking_book::store::Store::all_as_json();
+fn get_store(locale: RequestLocale) -> String {
+    return Store::all_as_json(locale.0);
 }
 
-/// Returns the groups.
+/// Returns the groups with their ids and labels.
 #[get("/group", format = "application/json")]
-fn get_group() -> String {
-    return crate::cooking_book::group::Group::all_as_json();
+fn get_group(locale: RequestLocale) -> String {
+    return Group::all_as_json(locale.0);
 }
 
 ///Returns a list of all ingredients
 #[get("/ingredient", format = "application/json")]
-fn get_ingredient() -> String {
+fn get_ingredient(_member: Member, locale: RequestLocale) -> String {
     let ingredients = persistency::load_ingredients();
-    return Ingredient::all_to_json(&ingredients);
+    return Ingredient::all_to_json(&ingredients, locale.0);
+}
+
+/// Returns the ingredients matching the query by name or alias, best match first.
+///
+/// #Arguments
+///
+/// * `q` The text searched for
+#[get("/ingredient/suggest?<q>", format = "application/json")]
+fn get_ingredient_suggestions(_member: Member, q: String, locale: RequestLocale) -> String {
+    let ingredients = persistency::load_ingredients();
+
+    let mut json: String = String::new();
+    json.push('[');
+    let mut is_first: bool = true;
+    for ingredient in Ingredient::suggest(&ingredients, &q, 10) {
+        if !is_first {
+            json.push_str(", ");
+        }
+        json.push_str(&ingredient.to_json(locale.0));
+        is_first = false;
+    }
+    json.push(']');
+    return json;
 }
 
 /// Adds an ingredient to the shopping list. If the ingredients doesn't exist it will be created.
+/// Only names and aliases are resolved to the existing ingredient, so "Paste" is created even
+/// if "Pasta" exists. Near duplicates are offered by `/ingredient/suggest` while typing.
+/// Free text like "2 Liter Milch" or "Salz und Pfeffer" adds the parsed ingredients
+/// with the number of items needed. New ingredients with invalid names are answered with 400.
 /// Returns the updated shopping list.
 ///
 /// #Arguments
 ///
-/// * `name` - The name of the ingredient to add
+/// * `name` - The name or alias of the ingredient to add, optionally with amount and unit
 #[put("/ingredient/<name>", format = "application/json")]
-fn put_new_ingredient(name: String) -> String {
+fn put_new_ingredient(
+    _member: Member,
+    name: String,
+    locale: RequestLocale,
+) -> Result<String, status::Custom<String>> {
     let mut ingredients = persistency::load_ingredients();
-
-    if !ingredients.contains_key(&name) {
-        let _ = Ingredient::persist_new_ingredient(&name, &mut ingredients);
-    }
-    let ingredient = ingredients.get(&name).unwrap();
     let mut shopping_list = persistency::load_shopping_list();
-    let _ = shopping_list.add_and_save(&ingredient);
+    let entries = match ShoppingList::parse_text(&name, &ingredients) {
+        Ok(entries) => entries,
+        Err(e) => return Err(status::Custom(Status::BadRequest, e)),
+    };
+    let added = shopping_list.add_entries(entries, &mut ingredients);
+    if let Err(e) = persistency::write_shopping_list(&shopping_list) {
+        return Err(status::Custom(Status::InternalServerError, e));
+    }
+    if let Err(e) = added {
+        return Err(status::Custom(Status::InternalServerError, e));
+    }
 
-    return shopping_list.to_json();
+    return Ok(shopping_list.to_json(locale.0));
 }
 
-/// Upserts an ingredient.
+/// Upserts an ingredient. Unknown groups and stores as well as invalid names of new ingredients
+/// are answered with 400.
 ///
 /// #Arguments
 ///
 /// * `name` - The name of the ingredient
-/// * `group` - The group of the ingredient
-/// * `store` - The store of the ingredient
+/// * `group` - The group of the ingredient, by id like `vegetable`, label or number
+/// * `store` - The store of the ingredient, by id like `rewe`, label or number
 #[put("/ingredient/<name>/<group>/<store>", format = "application/json")]
-fn put_update_ingredient(name: String, group: usize, store: usize) {
+fn put_update_ingredient(
+    _member: Member,
+    name: String,
+    group: String,
+    store: String,
+) -> Result<(), status::Custom<String>> {
+    let group = match Group::lookup_group_name(&group) {
+        Some(group) => group,
+        None => {
+            return Err(status::Custom(
+                Status::BadRequest,
+                format!("Unknown group '{}'", group),
+            ))
+        }
+    };
+    let store = match Store::lookup_store_name(&store) {
+        Some(store) => store,
+        None => {
+            return Err(status::Custom(
+                Status::BadRequest,
+                format!("Unknown store '{}'", store),
+            ))
+        }
+    };
     let mut ingredients = persistency::load_ingredients();
 
     if ingredients.contains_key(&name) {
         let ingredient = ingredients.get_mut(&name).unwrap();
-        ingredient.set_group(group);
-        ingredient.set_store(store);
+        ingredient.group = group;
+        ingredient.preferred_store = store;
+    } else if !Ingredient::is_valid_name(&name) {
+        return Err(status::Custom(
+            Status::BadRequest,
+            format!("Invalid ingredient name '{}'", name),
+        ));
     } else {
         let new_ingredient = Ingredient {
             name: name.to_string(),
-            group: cooking_book::group::Group::lookup_group_number(group),
-            preferred_store: cooking_book::store::Store::lookup_store_number(store),
+            group,
+            preferred_store: store,
+            aliases: Vec::new(),
+            price: None,
         };
         ingredients.insert(name, new_ingredient);
     }
 
-    let _ = persistency::write_all_ingredients(&ingredients);
+    if let Err(e) = persistency::write_all_ingredients(&ingredients) {
+        return Err(status::Custom(Status::InternalServerError, e));
+    }
+    return Ok(());
+}
+
+/// Renames an ingredient and updates all recipes and the shopping list.
+/// Returns the updated list of all ingredients.
+///
+/// #Arguments
+///
+/// * `name` The current name of the ingredient
+/// * `to` The new name of the ingredient
+#[post("/ingredient/<name>/rename?<to>", format = "application/json")]
+fn post_rename_ingredient(
+    _member: Member,
+    name: String,
+    to: String,
+    locale: RequestLocale,
+) -> Result<String, status::Custom<String>> {
+    if !persistency::load_ingredients().contains_key(&name) {
+        return Err(status::Custom(
+            Status::NotFound,
+            format!("Unknown ingredient '{}'", name),
+        ));
+    }
+    if let Err(e) = Ingredient::rename(&name, &to) {
+        return Err(status::Custom(Status::Conflict, e));
+    }
+    return Ok(Ingredient::all_to_json(
+        &persistency::load_ingredients(),
+        locale.0,
+    ));
+}
+
+/// Merges an ingredient into another one and updates all recipes and the shopping list.
+/// Returns the updated list of all ingredients.
+///
+/// #Arguments
+///
+/// * `name` The name of the ingredient to merge and remove
+/// * `target` The name of the ingredient to keep
+#[post("/ingredient/<name>/merge_into/<target>", format = "application/json")]
+fn post_merge_ingredient(
+    _member: Member,
+    name: String,
+    target: String,
+    locale: RequestLocale,
+) -> Result<String, status::Custom<String>> {
+    let ingredients = persistency::load_ingredients();
+    if !ingredients.contains_key(&name) || !ingredients.contains_key(&target) {
+        return Err(status::Custom(
+            Status::NotFound,
+            "Unknown ingredient".to_string(),
+        ));
+    }
+    if let Err(e) = Ingredient::merge_into(&name, &target) {
+        return Err(status::Custom(Status::Conflict, e));
+    }
+    return Ok(Ingredient::all_to_json(
+        &persistency::load_ingredients(),
+        locale.0,
+    ));
+}
+
+/// Sets the price of one package of an ingredient.
+/// Returns the updated list of all ingredients.
+///
+/// #Arguments
+///
+/// * `name` The name of the ingredient
+/// * `price` The price in cent
+#[post("/ingredient/<name>/price/<price>", format = "application/json")]
+fn post_price(
+    _member: Member,
+    name: String,
+    price: u32,
+    locale: RequestLocale,
+) -> Result<String, status::Custom<String>> {
+    let mut ingredients = persistency::load_ingredients();
+
+    match ingredients.get_mut(&name) {
+        Some(ingredient) => ingredient.set_price(price),
+        None => {
+            return Err(status::Custom(
+                Status::NotFound,
+                format!("Unknown ingredient '{}'", name),
+            ))
+        }
+    }
+    if let Err(e) = persistency::write_all_ingredients(&ingredients) {
+        return Err(status::Custom(Status::InternalServerError, e));
+    }
+    return Ok(Ingredient::all_to_json(&ingredients, locale.0));
+}
+
+/// Adds an alias to an ingredient.
+/// Returns the updated list of all ingredients.
+///
+/// #Arguments
+///
+/// * `name` The name of the ingredient
+/// * `alias` The alias to add
+#[post("/ingredient/<name>/alias/<alias>", format = "application/json")]
+fn post_alias(
+    _member: Member,
+    name: String,
+    alias: String,
+    locale: RequestLocale,
+) -> Result<String, status::Custom<String>> {
+    if !persistency::load_ingredients().contains_key(&name) {
+        return Err(status::Custom(
+            Status::NotFound,
+            format!("Unknown ingredient '{}'", name),
+        ));
+    }
+    if let Err(e) = Ingredient::add_alias(&name, &alias) {
+        return Err(status::Custom(Status::Conflict, e));
+    }
+    return Ok(Ingredient::all_to_json(
+        &persistency::load_ingredients(),
+        locale.0,
+    ));
+}
+
+/// Removes an alias from an ingredient.
+/// Returns the updated list of all ingredients.
+///
+/// #Arguments
+///
+/// * `name` The name of the ingredient
+/// * `alias` The alias to remove
+#[delete("/ingredient/<name>/alias/<alias>", format = "application/json")]
+fn delete_alias(
+    _member: Member,
+    name: String,
+    alias: String,
+    locale: RequestLocale,
+) -> Result<String, status::Custom<String>> {
+    if let Err(e) = Ingredient::remove_alias(&name, &alias) {
+        return Err(status::Custom(Status::NotFound, e));
+    }
+    return Ok(Ingredient::all_to_json(
+        &persistency::load_ingredients(),
+        locale.0,
+    ));
 }
 
-/// Removes an ingredient from the shopping list.
+/// Deletes an ingredient from the catalog.
+/// If recipes use the ingredient, this is refused with 409 and the names of the recipes
+/// unless `force` is set. Then the ingredient is removed from those recipes too.
+/// Returns the updated list of all ingredients.
+///
+/// #Arguments
+///
+/// * `name` The name of the ingredient to delete
+/// * `force` Whether to remove the ingredient from the recipes using it
+#[delete("/ingredient/<name>?<force>", format = "application/json")]
+fn delete_ingredient(
+    _member: Member,
+    name: String,
+    force: Option<bool>,
+    locale: RequestLocale,
+) -> Result<String, status::Custom<String>> {
+    if !persistency::load_ingredients().contains_key(&name) {
+        return Err(status::Custom(
+            Status::NotFound,
+            format!("Unknown ingredient '{}'", name),
+        ));
+    }
+
+    let recipes = Recipe::get_recipe_names_by_ingredient(&persistency::load_recipes(), &name);
+    if !recipes.is_empty() && !force.unwrap_or(false) {
+        let recipes: Vec<String> = recipes
+            .iter()
+            .map(|r| format!("\"{}\"", Json::escape(r)))
+            .collect();
+        return Err(status::Custom(
+            Status::Conflict,
+            format!("{{\"recipes\": [{}]}}", recipes.join(", ")),
+        ));
+    }
+
+    if let Err(e) = Ingredient::delete(&name) {
+        return Err(status::Custom(Status::InternalServerError, e));
+    }
+    return Ok(Ingredient::all_to_json(
+        &persistency::load_ingredients(),
+        locale.0,
+    ));
+}
+
+/// Removes an item from the shopping list.
+/// If `to_pantry` is set, the item is checked off and added to the pantry.
 /// Returns the updated shopping list.
 ///
 /// #Arguments
 ///
 /// * `name` The name of the ingredient to remove
-#[delete("/ingredient/<name>", format = "application/json")]
-fn delete_ingredient(name: String) -> String {
-    let ingredients = persistency::load_ingredients();
+/// * `to_pantry` Whether to add the bought item to the pantry
+/// * `amount` The amount bought, defaults to the amount on the list
+/// * `unit` The unit of the amount bought
+#[delete(
+    "/shopping_list/<name>?<to_pantry>&<amount>&<unit>",
+    format = "application/json"
+)]
+fn delete_shopping_list_item(
+    _member: Member,
+    name: String,
+    to_pantry: Option<bool>,
+    amount: Option<u16>,
+    unit: Option<String>,
+    locale: RequestLocale,
+) -> String {
+    let mut shopping_list = persistency::load_shopping_list();
+
+    if to_pantry.unwrap_or(false) {
+        let item = shopping_list
+            .to_buy
+            .iter()
+            .find(|(i, _a)| i.name == name)
+            .map(|(i, a)| (i.clone(), *a));
+        if let Some((ingredient, bought)) = item {
+            let mut pantry = persistency::load_pantry();
+            pantry.add(
+                ingredient,
+                amount.unwrap_or(bought),
+                &unit.unwrap_or_default(),
+                None,
+            );
+            persistency::write_pantry(&pantry).unwrap_or_else(|e| eprintln!("{}", e));
+        }
+    }
+
+    shopping_list.remove_by_name(&name);
+    let _ = persistency::write_shopping_list(&shopping_list);
+    return shopping_list.to_json(locale.0);
+}
+
+/// Adds the ingredients of the recipes to the shopping list which are not sufficiently in the pantry.
+/// Returns the updated shopping list.
+///
+/// #Arguments
+///
+/// * `names` The comma separated names of the recipes
+#[post("/shopping_list/from_recipes?<names>", format = "application/json")]
+fn post_shopping_list_from_recipes(
+    _member: Member,
+    names: String,
+    locale: RequestLocale,
+) -> Result<String, status::Custom<String>> {
+    let all_recipes = persistency::load_recipes();
+
+    let mut recipes: Vec<Recipe> = Vec::new();
+    for name in names.split(',').map(|n| n.trim()).filter(|n| !n.is_empty()) {
+        match all_recipes.get(name) {
+            Some(recipe) => recipes.push(expand_recipe(recipe, &all_recipes)?),
+            None => {
+                return Err(status::Custom(
+                    Status::NotFound,
+                    format!("Unknown recipe '{}'", name),
+                ))
+            }
+        }
+    }
 
     let mut shopping_list = persistency::load_shopping_list();
-    if ingredients.contains_key(&name) {
-        let ingredient = ingredients.get(&name).unwrap();
-        let _ = shopping_list.remove_and_save(&ingredient);
+    shopping_list.add_shortfall(&recipes.iter().collect(), &persistency::load_pantry());
+    if let Err(e) = persistency::write_shopping_list(&shopping_list) {
+        return Err(status::Custom(Status::InternalServerError, e));
+    }
+    return Ok(shopping_list.to_json(locale.0));
+}
+
+/// Adds the ingredients of the planned recipes to the shopping list which are not sufficiently
+/// in the pantry. Returns the updated shopping list.
+///
+/// #Arguments
+///
+/// * `from` The first day as `YYYY-MM-DD`, defaults to today
+/// * `to` The last day as `YYYY-MM-DD`, defaults to a week after `from`
+#[post(
+    "/shopping_list/from_meal_plan?<from>&<to>",
+    format = "application/json"
+)]
+fn post_shopping_list_from_meal_plan(
+    _member: Member,
+    from: Option<String>,
+    to: Option<String>,
+    locale: RequestLocale,
+) -> Result<String, status::Custom<String>> {
+    let from = match from {
+        Some(from) => parse_date(&from)?,
+        None => Date::today(),
+    };
+    let to = match to {
+        Some(to) => parse_date(&to)?,
+        None => from.add_days(6),
+    };
+
+    let all_recipes = persistency::load_recipes();
+    let meal_plan = persistency::load_meal_plan();
+    let mut recipes: Vec<Recipe> = Vec::new();
+    for name in meal_plan.get_recipe_names(from, to) {
+        if let Some(recipe) = all_recipes.get(name) {
+            recipes.push(expand_recipe(recipe, &all_recipes)?);
+        }
+    }
+
+    let mut shopping_list = persistency::load_shopping_list();
+    shopping_list.add_shortfall(&recipes.iter().collect(), &persistency::load_pantry());
+    if let Err(e) = persistency::write_shopping_list(&shopping_list) {
+        return Err(status::Custom(Status::InternalServerError, e));
+    }
+    return Ok(shopping_list.to_json(locale.0));
+}
+
+/// Returns the ingredients at home.
+#[get("/pantry", format = "application/json")]
+fn get_pantry(_member: Member) -> String {
+    return persistency::load_pantry().to_json();
+}
+
+/// Returns the ingredients at home which should be used within the next days,
+/// including the expired ones, sorted by their best before date.
+///
+/// #Arguments
+///
+/// * `days` The number of days from today, defaults to 3
+#[get("/pantry/expiring?<days>", format = "application/json")]
+fn get_expiring_pantry_items(_member: Member, days: Option<u16>) -> String {
+    let pantry = persistency::load_pantry();
+    let today = Date::today();
+    let expiring = pantry.get_expiring(today.add_days(days.unwrap_or(3) as i64));
+    return Pantry::items_to_json(&expiring, Some(today));
+}
+
+/// Sets the amount of an ingredient at home.
+/// Returns the updated pantry.
+///
+/// #Arguments
+///
+/// * `name` The name or alias of the ingredient
+/// * `amount` The amount at home, 0 removes the ingredient
+/// * `unit` The unit of the amount
+/// * `best_before` The date until which the ingredient should be used as `YYYY-MM-DD`
+#[put(
+    "/pantry/<name>?<amount>&<unit>&<best_before>",
+    format = "application/json"
+)]
+fn put_pantry_item(
+    _member: Member,
+    name: String,
+    amount: u16,
+    unit: Option<String>,
+    best_before: Option<String>,
+) -> Result<String, status::Custom<String>> {
+    let ingredient = lookup_ingredient(&name)?;
+    let best_before = match best_before {
+        Some(date) => Some(parse_date(&date)?),
+        None => None,
+    };
+    let mut pantry = persistency::load_pantry();
+    pantry.set(ingredient, amount, &unit.unwrap_or_default(), best_before);
+    if let Err(e) = persistency::write_pantry(&pantry) {
+        return Err(status::Custom(Status::InternalServerError, e));
+    }
+    return Ok(pantry.to_json());
+}
+
+/// Adds an amount of an ingredient to the pantry.
+/// Returns the updated pantry.
+///
+/// #Arguments
+///
+/// * `name` The name or alias of the ingredient
+/// * `amount` The amount to add
+/// * `unit` The unit of the amount
+/// * `best_before` The date until which the added amount should be used as `YYYY-MM-DD`
+#[post(
+    "/pantry/<name>?<amount>&<unit>&<best_before>",
+    format = "application/json"
+)]
+fn post_pantry_item(
+    _member: Member,
+    name: String,
+    amount: u16,
+    unit: Option<String>,
+    best_before: Option<String>,
+) -> Result<String, status::Custom<String>> {
+    let ingredient = lookup_ingredient(&name)?;
+    let best_before = match best_before {
+        Some(date) => Some(parse_date(&date)?),
+        None => None,
+    };
+    let mut pantry = persistency::load_pantry();
+    pantry.add(ingredient, amount, &unit.unwrap_or_default(), best_before);
+    if let Err(e) = persistency::write_pantry(&pantry) {
+        return Err(status::Custom(Status::InternalServerError, e));
+    }
+    return Ok(pantry.to_json());
+}
+
+/// Removes an ingredient from the pantry.
+/// Returns the updated pantry.
+///
+/// #Arguments
+///
+/// * `name` The name of the ingredient
+#[delete("/pantry/<name>", format = "application/json")]
+fn delete_pantry_item(_member: Member, name: String) -> String {
+    let mut pantry = persistency::load_pantry();
+    pantry.remove(&name);
+    persistency::write_pantry(&pantry).unwrap_or_else(|e| eprintln!("{}", e));
+    return pantry.to_json();
+}
+
+/// Records a cooked recipe in the history and takes its ingredients from the pantry.
+/// Returns the updated pantry.
+///
+/// #Arguments
+///
+/// * `name` The name of the recipe
+/// * `rating` The rating from 1 to 5
+/// * `notes` Notes on how it turned out
+/// * `date` The day as `YYYY-MM-DD`, defaults to today
+#[post(
+    "/recipe/<name>/cook?<rating>&<notes>&<date>",
+    format = "application/json"
+)]
+fn post_cook_recipe(
+    _member: Member,
+    name: String,
+    rating: Option<u8>,
+    notes: Option<String>,
+    date: Option<String>,
+) -> Result<String, status::Custom<String>> {
+    let recipes = persistency::load_recipes();
+    let recipe = match recipes.get(&name) {
+        Some(recipe) => recipe,
+        None => {
+            return Err(status::Custom(
+                Status::NotFound,
+                format!("Unknown recipe '{}'", name),
+            ))
+        }
+    };
+
+    let recipe = expand_recipe(recipe, &recipes)?;
+    let date = match date {
+        Some(date) => parse_date(&date)?,
+        None => Date::today(),
+    };
+
+    let mut history = persistency::load_history();
+    if let Err(e) = history.record(date, &name, rating, &notes.unwrap_or_default()) {
+        return Err(status::Custom(Status::BadRequest, e));
+    }
+    if let Err(e) = persistency::write_history(&history) {
+        return Err(status::Custom(Status::InternalServerError, e));
     }
-    return shopping_list.to_json();
+
+    let mut pantry = persistency::load_pantry();
+    pantry.cook(&recipe);
+    if let Err(e) = persistency::write_pantry(&pantry) {
+        return Err(status::Custom(Status::InternalServerError, e));
+    }
+    return Ok(pantry.to_json());
+}
+
+/// Returns the cooking history.
+///
+/// #Arguments
+///
+/// * `recipe` Only return the entries of this recipe
+#[get("/history?<recipe>", format = "application/json")]
+fn get_history(_member: Member, recipe: Option<String>) -> String {
+    let history = persistency::load_history();
+    return History::entries_to_json(&history.get_entries(recipe.as_ref().map(|r| r.as_str())));
+}
+
+/// Returns how often and when all recipes were cooked, including meals planned in the past.
+///
+/// #Arguments
+///
+/// * `order` `most` (default), `least` or `longest_ago`
+/// * `limit` The maximum number of recipes to return
+#[get("/stats/recipes?<order>&<limit>", format = "application/json")]
+fn get_recipe_stats(_member: Member, order: Option<String>, limit: Option<usize>) -> String {
+    let recipes = persistency::load_recipes();
+    let history = persistency::load_history();
+    let meal_plan = persistency::load_meal_plan();
+    let today = Date::today();
+
+    let meals = stats::get_meals(&history, &meal_plan, today);
+    let order = Order::lookup_order(&order.unwrap_or_default());
+    let all_stats = RecipeStats::find_all(&recipes, &meals, &history, order);
+    let limited: Vec<&RecipeStats> = all_stats
+        .iter()
+        .take(limit.unwrap_or(all_stats.len()))
+        .collect();
+    return RecipeStats::all_to_json(&limited, today);
+}
+
+/// Returns how often the ingredients were used in cooked meals, the most used first.
+///
+/// #Arguments
+///
+/// * `limit` The maximum number of ingredients to return
+#[get("/stats/ingredients?<limit>", format = "application/json")]
+fn get_ingredient_stats(_member: Member, limit: Option<usize>) -> String {
+    let recipes = persistency::load_recipes();
+    let history = persistency::load_history();
+    let meal_plan = persistency::load_meal_plan();
+
+    let meals = stats::get_meals(&history, &meal_plan, Date::today());
+    let mut usage = stats::get_ingredient_usage(&recipes, &meals);
+    if let Some(limit) = limit {
+        usage.truncate(limit);
+    }
+    return stats::counts_to_json("name", &usage);
+}
+
+/// Returns how often an ingredient was used in cooked meals per month.
+///
+/// #Arguments
+///
+/// * `name` The name or alias of the ingredient
+#[get("/stats/ingredients/<name>", format = "application/json")]
+fn get_ingredient_usage(_member: Member, name: String) -> Result<String, status::Custom<String>> {
+    let ingredient = lookup_ingredient(&name)?;
+    let recipes = persistency::load_recipes();
+    let history = persistency::load_history();
+    let meal_plan = persistency::load_meal_plan();
+
+    let meals = stats::get_meals(&history, &meal_plan, Date::today());
+    let usage = stats::get_ingredient_usage_by_month(&recipes, &meals, &ingredient.name);
+    return Ok(stats::counts_to_json("month", &usage));
+}
+
+/// Returns the meal slots.
+#[get("/slot", format = "application/json")]
+fn get_slot(locale: RequestLocale) -> String {
+    return Slot::all_as_json(locale.0);
+}
+
+/// Returns the meal plan.
+#[get("/meal_plan", format = "application/json")]
+fn get_meal_plan(_member: Member, locale: RequestLocale) -> String {
+    return persistency::load_meal_plan().to_json(locale.0);
+}
+
+/// Returns the meal plan and the shopping list as iCalendar to subscribe to in calendar apps.
+#[get("/meal_plan.ics")]
+fn get_meal_plan_ical(_member: Member, locale: RequestLocale) -> content::Content<String> {
+    return meal_plan_ical(locale.0);
+}
+
+/// Returns the meal plan and the shopping list of the selected household as iCalendar.
+fn meal_plan_ical(locale: Locale) -> content::Content<String> {
+    let ical = calendar::to_ical(
+        &persistency::load_meal_plan(),
+        &persistency::load_recipes(),
+        &persistency::load_shopping_list(),
+        Date::today(),
+        locale,
+    );
+    let content_type = ContentType::with_params("text", "calendar", ("charset", "utf-8"));
+    return content::Content(content_type, ical);
+}
+
+/// Plans a recipe for a meal.
+/// Returns the updated meal plan.
+///
+/// #Arguments
+///
+/// * `date` The day as `YYYY-MM-DD`
+/// * `slot` The encoded meal of the day
+/// * `recipe` The name of the recipe
+#[put("/meal_plan/<date>/<slot>/<recipe>", format = "application/json")]
+fn put_meal(
+    _member: Member,
+    date: String,
+    slot: usize,
+    recipe: String,
+    locale: RequestLocale,
+) -> Result<String, status::Custom<String>> {
+    let date = parse_date(&date)?;
+    let slot = lookup_slot(slot)?;
+    if !persistency::load_recipes().contains_key(&recipe) {
+        return Err(status::Custom(
+            Status::NotFound,
+            format!("Unknown recipe '{}'", recipe),
+        ));
+    }
+
+    let mut meal_plan = persistency::load_meal_plan();
+    meal_plan.plan(date, slot, &recipe);
+    if let Err(e) = persistency::write_meal_plan(&meal_plan) {
+        return Err(status::Custom(Status::InternalServerError, e));
+    }
+    return Ok(meal_plan.to_json(locale.0));
+}
+
+/// Removes the recipe planned for a meal.
+/// Returns the updated meal plan.
+///
+/// #Arguments
+///
+/// * `date` The day as `YYYY-MM-DD`
+/// * `slot` The encoded meal of the day
+#[delete("/meal_plan/<date>/<slot>", format = "application/json")]
+fn delete_meal(
+    _member: Member,
+    date: String,
+    slot: usize,
+    locale: RequestLocale,
+) -> Result<String, status::Custom<String>> {
+    let date = parse_date(&date)?;
+    let slot = lookup_slot(slot)?;
+
+    let mut meal_plan = persistency::load_meal_plan();
+    meal_plan.remove(date, slot);
+    if let Err(e) = persistency::write_meal_plan(&meal_plan) {
+        return Err(status::Custom(Status::InternalServerError, e));
+    }
+    return Ok(meal_plan.to_json(locale.0));
+}
+
+/// Fills the empty slots of the meal plan with suggested recipes.
+/// The same seed and data always give the same suggestions.
+/// Returns the suggested meals.
+///
+/// #Arguments
+///
+/// * `from` The first day as `YYYY-MM-DD`, defaults to today
+/// * `to` The last day as `YYYY-MM-DD`, defaults to a week after `from`
+/// * `seed` The seed for picking among equally good recipes, defaults to a number derived from `from`
+/// * `slots` The comma separated encoded slots to fill, defaults to all
+/// * `breakfast` The comma separated tags of which breakfast recipes need one, defaults to `#Breakfast`
+/// * `lunch` The comma separated tags of which lunch recipes need one
+/// * `dinner` The comma separated tags of which dinner recipes need one
+/// * `no_repeat_days` The minimum number of days between the same recipe, defaults to 7
+/// * `budget` The maximum price in cent of the meals of a week
+/// * `preview` Whether to only return the suggestions without planning them
+#[post(
+    "/meal_plan/suggest?<from>&<to>&<seed>&<slots>&<breakfast>&<lunch>&<dinner>&<no_repeat_days>&<budget>&<preview>",
+    format = "application/json"
+)]
+fn post_suggest_meals(
+    _member: Member,
+    from: Option<String>,
+    to: Option<String>,
+    seed: Option<u64>,
+    slots: Option<String>,
+    breakfast: Option<String>,
+    lunch: Option<String>,
+    dinner: Option<String>,
+    no_repeat_days: Option<u16>,
+    budget: Option<u32>,
+    preview: Option<bool>,
+    locale: RequestLocale,
+) -> Result<String, status::Custom<String>> {
+    let from = match from {
+        Some(from) => parse_date(&from)?,
+        None => Date::today(),
+    };
+    let to = match to {
+        Some(to) => parse_date(&to)?,
+        None => from.add_days(6),
+    };
+
+    let mut constraints = Constraints::new();
+    if let Some(slots) = slots {
+        constraints.slots = Vec::new();
+        for slot in slots.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()) {
+            match slot.parse::<usize>() {
+                Ok(number) => constraints.slots.push(lookup_slot(number)?),
+                Err(_) => {
+                    return Err(status::Custom(
+                        Status::BadRequest,
+                        format!("Invalid slot number '{}'", slot),
+                    ))
+                }
+            }
+        }
+    }
+    for (slot, tags) in vec![
+        (Slot::Breakfast, breakfast),
+        (Slot::Lunch, lunch),
+        (Slot::Dinner, dinner),
+    ] {
+        if let Some(tags) = tags {
+            let tags = if tags.trim().is_empty() {
+                Vec::new()
+            } else {
+                Recipe::unify_tags(&tags)
+            };
+            constraints.slot_tags.insert(slot, tags);
+        }
+    }
+    if let Some(days) = no_repeat_days {
+        constraints.no_repeat_days = days;
+    }
+    constraints.max_weekly_budget = budget;
+
+    let mut meal_plan = persistency::load_meal_plan();
+    let suggestions = planner::suggest(
+        &meal_plan,
+        &persistency::load_recipes(),
+        &persistency::load_history(),
+        from,
+        to,
+        &constraints,
+        seed.unwrap_or(from.to_days() as u64),
+    );
+
+    if !preview.unwrap_or(false) {
+        for ((date, slot), recipe) in &suggestions.meals {
+            meal_plan.plan(*date, *slot, recipe);
+        }
+        if let Err(e) = persistency::write_meal_plan(&meal_plan) {
+            return Err(status::Custom(Status::InternalServerError, e));
+        }
+    }
+    return Ok(suggestions.to_json(locale.0));
+}
+
+fn expand_recipe(
+    recipe: &Recipe,
+    all_recipes: &HashMap<String, Recipe>,
+) -> Result<Recipe, status::Custom<String>> {
+    return match recipe.expand(all_recipes) {
+        Ok(expanded) => Ok(expanded),
+        Err(e) => Err(status::Custom(Status::UnprocessableEntity, e)),
+    };
+}
+
+fn lookup_slot(number: usize) -> Result<Slot, status::Custom<String>> {
+    return match Slot::lookup_slot_number(number) {
+        Some(slot) => Ok(slot),
+        None => Err(status::Custom(
+            Status::BadRequest,
+            format!("Unknown slot '{}'", number),
+        )),
+    };
+}
+
+fn parse_date(date: &str) -> Result<Date, status::Custom<String>> {
+    return match Date::parse(date) {
+        Some(date) => Ok(date),
+        None => Err(status::Custom(
+            Status::BadRequest,
+            format!("Invalid date '{}', expected YYYY-MM-DD", date),
+        )),
+    };
+}
+
+fn lookup_ingredient(name: &str) -> Result<Ingredient, status::Custom<String>> {
+    return match Ingredient::resolve(&persistency::load_ingredients(), name) {
+        Some(ingredient) => Ok(ingredient.clone()),
+        None => Err(status::Custom(
+            Status::NotFound,
+            format!("Unknown ingredient '{}'", name),
+        )),
+    };
+}
+
+/// Returns the ingredients which are referenced by recipes or the shopping list but missing in the catalog.
+#[get("/ingredient/unresolved", format = "application/json")]
+fn get_unresolved_ingredients(_member: Member) -> String {
+    let ingredients = persistency::load_ingredients();
+    let unresolved = UnresolvedIngredient::find_all(
+        &ingredients,
+        &persistency::load_recipes(),
+        &persistency::load_shopping_list(),
+    );
+    return UnresolvedIngredient::all_to_json(&unresolved);
+}
+
+/// Creates all unresolved ingredients with default group and store.
+/// Returns the remaining unresolved ingredients.
+#[post("/ingredient/unresolved", format = "application/json")]
+fn post_create_unresolved_ingredients(_member: Member) -> String {
+    let mut ingredients = persistency::load_ingredients();
+    let recipes = persistency::load_recipes();
+    let shopping_list = persistency::load_shopping_list();
+
+    let unresolved = UnresolvedIngredient::find_all(&ingredients, &recipes, &shopping_list);
+    UnresolvedIngredient::create_all(&unresolved, &mut ingredients)
+        .unwrap_or_else(|e| eprintln!("{}", e));
+
+    let unresolved = UnresolvedIngredient::find_all(&ingredients, &recipes, &shopping_list);
+    return UnresolvedIngredient::all_to_json(&unresolved);
+}
+
+/// Returns the recipes matching the query sorted by name, or all recipes without a query.
+/// An invalid query is answered with 400 and the position of the error.
+///
+/// #Arguments
+///
+/// * `q` The query like `#Breakfast AND (#vegan OR #vegetarian) AND NOT Milch`
+/// * `max_time` Only return recipes known to be done within these minutes
+/// * `format` The format like `text`, `md` or `html`, overrides the `Accept` header
+#[get("/recipe?<q>&<max_time>&<format>")]
+fn get_recipe(
+    _member: Member,
+    q: Option<String>,
+    max_time: Option<u16>,
+    format: Option<String>,
+    accept: Option<&Accept>,
+    locale: RequestLocale,
+) -> Result<content::Content<String>, status::Custom<String>> {
+    let format = choose_format(format, accept)?;
+    let recipes = persistency::load_recipes();
+
+    let mut found: Vec<&Recipe> = match q {
+        Some(q) => match Query::parse(&q) {
+            Ok(query) => Recipe::get_recipes_by_query(&recipes, &query),
+            Err(e) => return Err(status::Custom(Status::BadRequest, e.to_json())),
+        },
+        None => {
+            let mut all_recipes: Vec<&Recipe> = recipes.values().collect();
+            all_recipes.sort_by(|a, b| a.name.cmp(&b.name));
+            all_recipes
+        }
+    };
+    if let Some(max_time) = max_time {
+        found.retain(|r| r.is_done_within(max_time));
+    }
+    return Ok(rendered(format, render::recipes(&found, format, locale.0)));
+}
+
+/// Returns one recipe, e.g. as plain text or as HTML page for printing.
+///
+/// #Arguments
+///
+/// * `name` The name of the recipe
+/// * `format` The format like `text`, `md` or `html`, overrides the `Accept` header
+#[get("/recipe/<name>?<format>", rank = 2)]
+fn get_recipe_by_name(
+    _member: Member,
+    name: String,
+    format: Option<String>,
+    accept: Option<&Accept>,
+    locale: RequestLocale,
+) -> Result<content::Content<String>, status::Custom<String>> {
+    let format = choose_format(format, accept)?;
+    let recipes = persistency::load_recipes();
+    return match recipes.get(&name) {
+        Some(recipe) => Ok(rendered(
+            format,
+            render::recipes(&vec![recipe], format, locale.0),
+        )),
+        None => Err(status::Custom(
+            Status::NotFound,
+            format!("Unknown recipe '{}'", name),
+        )),
+    };
+}
+
+/// Replaces the steps, times and other details of a recipe.
+/// Returns the updated recipe.
+///
+/// #Arguments
+///
+/// * `name` The name of the recipe
+/// * `details` The details as in the details file, like `servings: 2` and `step: {Mehl} sieben`
+#[put("/recipe/<name>/details", format = "text/plain", data = "<details>")]
+fn put_recipe_details(
+    _member: Member,
+    name: String,
+    details: String,
+    locale: RequestLocale,
+) -> Result<String, status::Custom<String>> {
+    let mut recipes = persistency::load_recipes();
+    if !recipes.contains_key(&name) {
+        return Err(status::Custom(
+            Status::NotFound,
+            format!("Unknown recipe '{}'", name),
+        ));
+    }
+
+    let (details, problems) = RecipeDetails::new_by_text(&details);
+    if !problems.is_empty() {
+        let problems: Vec<String> = problems
+            .iter()
+            .map(|(i, problem)| format!("line {}: {}", i + 1, problem))
+            .collect();
+        return Err(status::Custom(
+            Status::UnprocessableEntity,
+            problems.join("\n"),
+        ));
+    }
+
+    let recipe = recipes.get_mut(&name).unwrap();
+    recipe.details = details;
+    let json = recipe.to_json(locale.0);
+    if let Err(e) = persistency::write_all_recipes(&recipes) {
+        return Err(status::Custom(Status::InternalServerError, e));
+    }
+    return Ok(json);
+}
+
+/// Replaces the ingredients of a recipe, the recipe is created if it doesn't exist.
+/// Returns the recipe with the ingredients missing in the catalog.
+/// Invalid recipe names are answered with 400, lines which can't be parsed with 422.
+///
+/// #Arguments
+///
+/// * `name` The name of the recipe
+/// * `ingredients` One ingredient per line like `200 g Mehl, gesiebt` or `Salz und Pfeffer`,
+///   a line like `Für den Teig:` starts a section
+#[put(
+    "/recipe/<name>/ingredients",
+    format = "text/plain",
+    data = "<ingredients>"
+)]
+fn put_recipe_ingredients(
+    _member: Member,
+    name: String,
+    ingredients: String,
+    locale: RequestLocale,
+) -> Result<String, status::Custom<String>> {
+    if name.trim().is_empty() || !Ingredient::is_valid_name(&name) {
+        return Err(status::Custom(
+            Status::BadRequest,
+            format!("Invalid recipe name '{}'", name),
+        ));
+    }
+    let all_ingredients = persistency::load_ingredients();
+    let (lines, skipped) = recipe_import::ingredients_from_text(&ingredients, &all_ingredients);
+    if !skipped.is_empty() {
+        let skipped: Vec<String> = skipped
+            .iter()
+            .map(|s| format!("Can't parse ingredient '{}'", s))
+            .collect();
+        return Err(status::Custom(
+            Status::UnprocessableEntity,
+            skipped.join("\n"),
+        ));
+    }
+
+    let mut recipes = persistency::load_recipes();
+    let recipe = recipes.entry(name.to_string()).or_insert_with(|| Recipe {
+        name,
+        ingredients: Vec::new(),
+        sub_recipes: HashMap::new(),
+        tags: HashSet::new(),
+        details: RecipeDetails::new(),
+    });
+    recipe.ingredients = lines;
+    let json = ImportedRecipe::new(recipe.clone(), Vec::new(), &all_ingredients).to_json(locale.0);
+    if let Err(e) = persistency::write_all_recipes(&recipes) {
+        return Err(status::Custom(Status::InternalServerError, e));
+    }
+    return Ok(json);
+}
+
+/// Imports a recipe from a saved web page containing schema.org JSON-LD.
+/// Returns a preview with the ingredients missing in the catalog and the skipped ingredient lines.
+/// The recipe is only saved if requested.
+///
+/// #Arguments
+///
+/// * `save` Whether to save the recipe, defaults to false
+/// * `html` The HTML of the page
+#[post("/recipe/import?<save>", format = "text/html", data = "<html>")]
+fn post_import_recipe(
+    _member: Member,
+    save: Option<bool>,
+    html: String,
+    locale: RequestLocale,
+) -> Result<String, status::Custom<String>> {
+    let imported = match recipe_import::from_html(&html, &persistency::load_ingredients()) {
+        Ok(imported) => imported,
+        Err(e) => return Err(status::Custom(Status::UnprocessableEntity, e)),
+    };
+    let json = imported.to_json(locale.0);
+    if !save.unwrap_or(false) {
+        return Ok(json);
+    }
+
+    let mut recipes = persistency::load_recipes();
+    if recipes.contains_key(&imported.recipe.name) {
+        return Err(status::Custom(
+            Status::Conflict,
+            format!("Recipe '{}' already exists", imported.recipe.name),
+        ));
+    }
+    recipes.insert(imported.recipe.name.to_string(), imported.recipe);
+    if let Err(e) = persistency::write_all_recipes(&recipes) {
+        return Err(status::Custom(Status::InternalServerError, e));
+    }
+    return Ok(json);
+}
+
+/// Imports a recipe written in Cooklang.
+/// Returns a preview with the ingredients missing in the catalog and the skipped ingredients.
+/// The recipe is only saved if requested.
+///
+/// #Arguments
+///
+/// * `name` The name of the recipe, unless the text contains a title
+/// * `save` Whether to save the recipe, defaults to false
+/// * `text` The Cooklang text
+#[post(
+    "/recipe/import/cooklang?<name>&<save>",
+    format = "text/plain",
+    data = "<text>"
+)]
+fn post_import_cooklang(
+    _member: Member,
+    name: Option<String>,
+    save: Option<bool>,
+    text: String,
+    locale: RequestLocale,
+) -> Result<String, status::Custom<String>> {
+    let name = name.unwrap_or_default();
+    let imported = match cooklang::from_cooklang(&name, &text, &persistency::load_ingredients()) {
+        Ok(imported) => imported,
+        Err(e) => return Err(status::Custom(Status::UnprocessableEntity, e)),
+    };
+    let json = imported.to_json(locale.0);
+    if !save.unwrap_or(false) {
+        return Ok(json);
+    }
+
+    let mut recipes = persistency::load_recipes();
+    if recipes.contains_key(&imported.recipe.name) {
+        return Err(status::Custom(
+            Status::Conflict,
+            format!("Recipe '{}' already exists", imported.recipe.name),
+        ));
+    }
+    recipes.insert(imported.recipe.name.to_string(), imported.recipe);
+    if let Err(e) = persistency::write_all_recipes(&recipes) {
+        return Err(status::Custom(Status::InternalServerError, e));
+    }
+    return Ok(json);
+}
+
+/// Returns a recipe written in Cooklang.
+///
+/// #Arguments
+///
+/// * `name` The name of the recipe
+#[get("/recipe/<name>/cooklang", format = "text/plain")]
+fn get_recipe_cooklang(_member: Member, name: String) -> Result<String, status::Custom<String>> {
+    let recipes = persistency::load_recipes();
+    return match recipes.get(&name) {
+        Some(recipe) => Ok(cooklang::to_cooklang(recipe)),
+        None => Err(status::Custom(
+            Status::NotFound,
+            format!("Unknown recipe '{}'", name),
+        )),
+    };
+}
+
+/// Returns all recipes with the ingredients missing to cook them.
+///
+/// #Arguments
+///
+/// * `have` The comma separated names of the available ingredients, none if left out
+/// * `sort` How to rank the recipes: `missing` (default), `coverage` or `shopping_list`
+#[get("/recipe/cookable?<have>&<sort>", format = "application/json")]
+fn get_cookable_recipes(_member: Member, have: Option<String>, sort: Option<String>) -> String {
+    let recipes = persistency::load_recipes();
+    let shopping_list = persistency::load_shopping_list();
+
+    let available: Vec<String> = have
+        .unwrap_or_default()
+        .split(',')
+        .map(|h| h.trim().to_string())
+        .filter(|h| !h.is_empty())
+        .collect();
+    let ranking = Ranking::lookup_ranking(&sort.unwrap_or_default());
+
+    let matches = RecipeMatch::find_all(&recipes, &available, &shopping_list, ranking);
+    return RecipeMatch::all_to_json(&matches);
 }
 
-/// Returns the shopping list.
-#[get("/shopping_list", format = "application/json")]
-fn get_shopping_list() -> String {
+/// Returns the recipes which use ingredients at home expiring within the next days.
+/// Recipes using up more of them come first, then those using the ingredient expiring first.
+///
+/// #Arguments
+///
+/// * `days` The number of days from today, defaults to 3
+#[get("/recipe/use_up?<days>", format = "application/json")]
+fn get_recipes_using_up(_member: Member, days: Option<u16>) -> String {
+    let recipes = persistency::load_recipes();
+    let pantry = persistency::load_pantry();
+    let today = Date::today();
+    let using_up = pantry.get_recipes_using_up(&recipes, today.add_days(days.unwrap_or(3) as i64));
+    return Pantry::using_up_to_json(&using_up, today);
+}
+
+/// Returns the shopping list, e.g. as plain text or as HTML page with checkboxes for printing.
+///
+/// #Arguments
+///
+/// * `format` The format like `text`, `md` or `html`, overrides the `Accept` header
+#[get("/shopping_list?<format>")]
+fn get_shopping_list(
+    _member: Member,
+    format: Option<String>,
+    accept: Option<&Accept>,
+    locale: RequestLocale,
+) -> Result<content::Content<String>, status::Custom<String>> {
+    return render_shopping_list(format, accept, locale.0);
+}
+
+/// Returns the shopping list of the selected household in the chosen format.
+fn render_shopping_list(
+    format: Option<String>,
+    accept: Option<&Accept>,
+    locale: Locale,
+) -> Result<content::Content<String>, status::Custom<String>> {
+    let format = choose_format(format, accept)?;
     let shopping_list = persistency::load_shopping_list();
-    return shopping_list.to_json();
+    return Ok(rendered(
+        format,
+        render::shopping_list(&shopping_list, format, locale),
+    ));
 }
 
+/// Chooses the format of a response. The query parameter wins over the `Accept` header,
+/// JSON is the default.
+///
+/// #Arguments
+///
+/// * `format` The format given as query parameter
+/// * `accept` The `Accept` header of the request
+fn choose_format(
+    format: Option<String>,
+    accept: Option<&Accept>,
+) -> Result<Format, status::Custom<String>> {
+    if let Some(format) = format {
+        return match Format::lookup_format(&format) {
+            Some(format) => Ok(format),
+            None => Err(status::Custom(
+                Status::BadRequest,
+                format!("Unknown format '{}'", format),
+            )),
+        };
+    }
+    let format = accept.and_then(|accept| {
+        let media_type = accept.preferred().media_type();
+        return Format::lookup_media_type(media_type.top().as_str(), media_type.sub().as_str());
+    });
+    return Ok(format.unwrap_or(Format::Json));
+}
+
+/// Returns the text with the content type of its format.
+fn rendered(format: Format, text: String) -> content::Content<String> {
+    let (top, sub) = format.media_type();
+    let content_type = ContentType::with_params(top, sub, ("charset", "utf-8"));
+    return content::Content(content_type, text);
+}
+
+/// Returns the whole cooking book as one versioned JSON document.
+#[get("/export", format = "application/json")]
+fn get_export(_member: Member) -> String {
+    return archive::export(&persistency::read_all_files(), Date::today());
+}
+
+/// Imports a document created by `/export`. Nothing is written if the document is invalid
+/// or the files would contain problems afterwards, which is answered with 422.
+/// Returns the number of imported entries per part, the conflicts and the problems.
+///
+/// #Arguments
+///
+/// * `mode` `merge` (default) adds missing entries and reports conflicts,
+///   `replace` replaces the imported parts
+/// * `dry_run` Only returns the report without writing, defaults to false
+/// * `document` The exported document
+#[post(
+    "/import?<mode>&<dry_run>",
+    format = "application/json",
+    data = "<document>"
+)]
+fn post_import(
+    _member: Member,
+    mode: Option<String>,
+    dry_run: Option<bool>,
+    document: String,
+) -> Result<String, status::Custom<String>> {
+    let mode = mode.unwrap_or_else(|| "merge".to_string());
+    let mode = match archive::Mode::lookup_mode(&mode) {
+        Some(mode) => mode,
+        None => {
+            return Err(status::Custom(
+                Status::BadRequest,
+                format!("Unknown mode '{}'", mode),
+            ))
+        }
+    };
+    let imported = match archive::import(&document, &persistency::read_all_files(), mode) {
+        Ok(imported) => imported,
+        Err(e) => return Err(status::Custom(Status::UnprocessableEntity, e)),
+    };
+    if !imported.diagnostics.is_empty() {
+        return Err(status::Custom(
+            Status::UnprocessableEntity,
+            imported.to_json(),
+        ));
+    }
+    if !dry_run.unwrap_or(false) {
+        if let Err(e) = persistency::write_files_atomically(&imported.files) {
+            return Err(status::Custom(Status::InternalServerError, e));
+        }
+    }
+    return Ok(imported.to_json());
+}
+
+/// Strictly parses all persistency files and returns the problems found.
+#[get("/admin/check", format = "application/json")]
+fn get_check(_member: Member) -> String {
+    let diagnostics = check::check_all();
+    return Diagnostic::all_to_json(&diagnostics);
+}
+
+/// Runs a command of the command line interface, see `cli::USAGE`.
+/// Without a command or with `serve` the web server is started.
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() && args[0] != "serve" {
+        std::process::exit(cli::run(&args));
+    }
+
     rocket::ignite()
         .mount(
             "/",
             routes![
+                get_locale,
                 get_store,
                 get_group,
                 get_ingredient,
+                get_ingredient_suggestions,
                 put_new_ingredient,
                 put_update_ingredient,
                 delete_ingredient,
-                get_shopping_list
+                get_recipe,
+                get_recipe_by_name,
+                put_recipe_details,
+                put_recipe_ingredients,
+                post_import_recipe,
+                post_import_cooklang,
+                get_recipe_cooklang,
+                get_cookable_recipes,
+                get_recipes_using_up,
+                get_shopping_list,
+                delete_shopping_list_item,
+                post_shopping_list_from_recipes,
+                post_shopping_list_from_meal_plan,
+                get_pantry,
+                get_expiring_pantry_items,
+                put_pantry_item,
+                post_pantry_item,
+                delete_pantry_item,
+                post_cook_recipe,
+                get_history,
+                get_recipe_stats,
+                get_ingredient_stats,
+                get_ingredient_usage,
+                get_slot,
+                get_meal_plan,
+                get_meal_plan_ical,
+                put_meal,
+                post_suggest_meals,
+                delete_meal,
+                get_check,
+                get_export,
+                post_import,
+                get_unresolved_ingredients,
+                post_create_unresolved_ingredients,
+                post_rename_ingredient,
+                post_merge_ingredient,
+                post_price,
+                post_alias,
+                delete_alias,
+                post_login,
+                post_logout,
+                get_account,
+                post_api_token,
+                delete_api_token,
+                post_household_member,
+                post_share,
+                delete_share,
+                get_shared_shopping_list,
+                get_shared_meal_plan_ical
             ],
         )
         .mount("/", StaticFiles::from("web"))
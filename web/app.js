@@ -1,5 +1,66 @@
 let aGroups;
 let aStores;
+let oMessages = {};
+
+function loadLocale() {
+    return getQuery("/locale")
+        .then((data) => {
+            if (!data.messages) {
+                return;
+            }
+            oMessages = data.messages;
+            document.documentElement.lang = data.locale;
+            document.getElementById("selLanguage").value = data.locale;
+            document.title = oMessages["label.shopping_list"];
+            for (let oElement of document.querySelectorAll("[data-label]")) {
+                oElement.innerText = oMessages[oElement.dataset.label];
+            }
+        });
+}
+
+function setLanguage(sLocale) {
+    document.cookie = `lang=${sLocale}; path=/; max-age=31536000`;
+    aGroups = undefined;
+    aStores = undefined;
+    return loadLocale()
+        .then(() => {
+            if (document.getElementById("c_ingredients").style.display === "block") {
+                return showIngredients();
+            }
+            if (document.getElementById("c_recipes").style.display === "block") {
+                return showRecipes();
+            }
+            return showShoppingList();
+        });
+}
+
+function showLogin() {
+    hideElements(["c_shoppingList", "c_recipes", "c_ingredients", "shoppingList", "btnLogout"]);
+    showElement(["c_login"]);
+}
+
+function login() {
+    const sName = document.getElementById("loginName").value;
+    return postText(`/account/login?name=${encodeURIComponent(sName)}`, document.getElementById("loginPassword").value)
+        .then(loggedIn, showLoginError);
+}
+
+function loggedIn() {
+    document.getElementById("loginPassword").value = "";
+    document.getElementById("loginError").innerText = "";
+    hideElements(["c_login"]);
+    showElement(["btnLogout"]);
+    return showShoppingList();
+}
+
+function showLoginError(sError) {
+    document.getElementById("loginError").innerText = sError;
+}
+
+function logout() {
+    return ajax("POST", "/account/logout")
+        .finally(showLogin);
+}
 
 function activateButton(sId) {
     deactivateAllTabs();
@@ -67,7 +128,7 @@ function displayIngredients() {
                 const oGroup = document.createElement("select");
                 oGroup.setAttribute("id", `group_${ingredient.name}`);
                 addOptions(aGroups, oGroup);
-                oGroup.value = aGroups.indexOf(ingredient.group);
+                oGroup.value = ingredient.group;
                 oGroup.addEventListener('change', handleChange, false);
                 oIngredient.appendChild(oGroup);
 
@@ -75,7 +136,7 @@ function displayIngredients() {
                 const oStore = document.createElement("select");
                 oStore.setAttribute("id", `store_${ingredient.name}`);
                 addOptions(aStores, oStore);
-                oStore.value = aStores.indexOf(ingredient.store);
+                oStore.value = ingredient.store;
                 oStore.addEventListener('change', handleChange, false);
                 oIngredient.appendChild(oStore);
 
@@ -102,16 +163,16 @@ function displayIngredients() {
 
 function handleChange(e) {
     const sIngredient = e.target.id.substring(e.target.id.indexOf('_') + 1);
-    const iGroup = document.getElementById(`group_${sIngredient}`).selectedIndex;
-    const iStore = document.getElementById(`store_${sIngredient}`).selectedIndex;
-    putData("ingredient", [sIngredient, iGroup, iStore]);
+    const sGroup = document.getElementById(`group_${sIngredient}`).value;
+    const sStore = document.getElementById(`store_${sIngredient}`).value;
+    putData("ingredient", [sIngredient, sGroup, sStore]);
 }
 
 function addOptions(aArr, oSelect) {
-    aArr.forEach((sOption, i) => {
+    aArr.forEach((oEntry) => {
         const oOption = document.createElement('option');
-        oOption.value = i;
-        oOption.innerHTML = sOption;
+        oOption.value = oEntry.id;
+        oOption.innerText = oEntry.label;
         oSelect.appendChild(oOption);
     });
 }
@@ -148,7 +209,7 @@ function isOffline() {
     return document.getElementById("cbOffline").checked;
 }
 
-function ajax(sMethod, sUrl) {
+function ajax(sMethod, sUrl, sData) {
     if (isOffline()) {
         return Promise.resolve([]);
     }
@@ -158,19 +219,26 @@ function ajax(sMethod, sUrl) {
         xmlHttp.onreadystatechange = () => {
             if (xmlHttp.readyState == 4) {
                 if (xmlHttp.status == 200) {
-                    resolve(JSON.parse(xmlHttp.response));
+                    resolve(xmlHttp.response ? JSON.parse(xmlHttp.response) : null);
                 }
                 else {
+                    if (xmlHttp.status == 401) {
+                        showLogin();
+                    }
                     reject(xmlHttp.responseText);
                 }
             }
         }
         xmlHttp.open(sMethod, sUrl, true);
-        xmlHttp.setRequestHeader("Content-Type", "application/json");
-        xmlHttp.send(null);
+        xmlHttp.setRequestHeader("Content-Type", sData === undefined ? "application/json" : "text/plain");
+        xmlHttp.send(sData === undefined ? null : sData);
     });
 }
 
+function postText(sUrl, sText) {
+    return ajax("POST", sUrl, sText);
+}
+
 function deleteData(sUrl, sData) {
     return ajax("DELETE", `${sUrl}/${sData}`);
 }
@@ -209,6 +277,31 @@ function getAllIngredients() {
     cleanOptions();
     return getQuery("/ingredient")
         .then((ingredients) => {
+            const oIngredients = document.getElementById("dl_ingredients");
+            ingredients.forEach((i) => {
+                const oOption = document.createElement('option');
+                oOption.value = i.name;
+                oIngredients.appendChild(oOption);
+
+                (i.aliases || []).forEach((sAlias) => {
+                    const oAlias = document.createElement('option');
+                    oAlias.value = sAlias;
+                    oAlias.label = i.name;
+                    oIngredients.appendChild(oAlias);
+                });
+            });
+        });
+}
+
+function suggestIngredients() {
+    const sInput = document.getElementById("newIngredient").value;
+    if (!sInput) {
+        return getAllIngredients();
+    }
+
+    return getQuery(`/ingredient/suggest?q=${encodeURIComponent(sInput)}`)
+        .then((ingredients) => {
+            cleanOptions();
             const oIngredients = document.getElementById("dl_ingredients");
             ingredients.forEach((i) => {
                 const oOption = document.createElement('option');
@@ -224,17 +317,18 @@ function displayShoppingList(shoppingList) {
     let oNewContent = [];
     return Promise.resolve().then(() => {
 
-        Object.keys(shoppingList).forEach((store) => {
-            if (store !== "Any") {
+        shoppingList.forEach((store) => {
+            if (store.store !== "any") {
                 const oStoreHeader = document.createElement('h1');
-                oStoreHeader.innerText = store;
+                oStoreHeader.innerText = store.label;
                 oNewContent.push(oStoreHeader);
             }
-            Object.keys(shoppingList[store]).forEach((category) => {
+            store.groups.forEach((group) => {
                 const oList = document.createElement('ul');
+                oList.setAttribute("title", group.label);
                 oNewContent.push(oList);
 
-                shoppingList[store][category].forEach((item) => {
+                group.items.forEach((item) => {
                     const oItem = document.createElement('li');
                     oItem.setAttribute("id", `li_${item.name}`);
                     oItem.setAttribute("draggable", true);
@@ -309,7 +403,7 @@ function removeIngredient(sIngredient) {
     }
 
     const iPosition = document.documentElement.scrollTop || document.body.scrollTop;
-    return deleteData("ingredient", sIngredient)
+    return deleteData("shopping_list", sIngredient)
         .then((shoppingList) => {
             displayShoppingList(shoppingList);
         })